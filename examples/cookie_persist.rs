@@ -0,0 +1,103 @@
+//! This example keeps cookies across runs, in a JSON file.
+//!
+//! `Client`s use any `reqwest::cookie::CookieStore` given to
+//! `ClientBuilder::cookie_provider`. This one wraps the store of the
+//! `cookie_store` crate, which is also what `cookie_store(true)` uses, loads
+//! it from a file when created and writes it back when dropped. Wrapping it in
+//! an `Arc` also lets several clients share it.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use reqwest::cookie::CookieStore;
+use reqwest::header::HeaderValue;
+use reqwest::Url;
+
+// This requires the `cookie_store` dependency:
+//
+// `cookie_store = "0.20"`
+struct FileStore {
+    path: PathBuf,
+    store: RwLock<cookie_store::CookieStore>,
+}
+
+impl FileStore {
+    fn open(path: impl Into<PathBuf>) -> FileStore {
+        let path = path.into();
+        let store = File::open(&path)
+            .map(BufReader::new)
+            .ok()
+            .and_then(|reader| cookie_store::CookieStore::load_json(reader).ok())
+            .unwrap_or_default();
+        FileStore {
+            path,
+            store: RwLock::new(store),
+        }
+    }
+}
+
+impl CookieStore for FileStore {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies = cookie_headers
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| cookie_store::RawCookie::parse(value.to_owned()).ok());
+        self.store
+            .write()
+            .unwrap()
+            .store_response_cookies(cookies, url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let value = self
+            .store
+            .read()
+            .unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if value.is_empty() {
+            None
+        } else {
+            HeaderValue::from_str(&value).ok()
+        }
+    }
+}
+
+impl Drop for FileStore {
+    fn drop(&mut self) {
+        // only persistent cookies are saved, session cookies end with the run
+        let saved = File::create(&self.path)
+            .map(BufWriter::new)
+            .map(|mut writer| self.store.read().unwrap().save_json(&mut writer));
+        if let Err(err) = saved {
+            eprintln!("failed to save cookies to {}: {err}", self.path.display());
+        }
+    }
+}
+
+// This is using the `tokio` runtime. You'll need the following dependency:
+//
+// `tokio = { version = "1", features = ["full"] }`
+#[tokio::main]
+async fn main() -> Result<(), reqwest::Error> {
+    let store = Arc::new(FileStore::open("cookies.json"));
+
+    let client = reqwest::Client::builder()
+        .cookie_provider(store.clone())
+        .build()?;
+
+    let res = client
+        .get("https://httpbin.org/cookies/set?visited=yes")
+        .send()
+        .await?;
+    println!("Status: {}", res.status());
+
+    let cookies = store.cookies(&"https://httpbin.org/".parse().unwrap());
+    println!("Cookies: {cookies:?}");
+
+    // the store is saved once the last client using it is dropped
+    Ok(())
+}
@@ -32,9 +32,55 @@ pub(crate) struct TotalTimeoutBody<B> {
     timeout: Pin<Box<Sleep>>,
 }
 
+/// A body that fails once it yielded more than `limit` bytes of data.
+pub(crate) struct LimitedBody<B> {
+    inner: B,
+    remaining: u64,
+    limit: u64,
+    failed: bool,
+}
+
 /// Converts any `impl Body` into a `impl Stream` of just its DATA frames.
 pub(crate) struct DataStream<B>(pub(crate) B);
 
+/// Like `DataStream`, but splits frames into chunks of at most `max` bytes.
+///
+/// Only the rest of the last frame is held, and the body isn't polled again
+/// until it has all been yielded.
+#[cfg(feature = "stream")]
+pub(crate) struct CappedDataStream<B> {
+    body: B,
+    rest: Bytes,
+    max: usize,
+}
+
+/// Serializes each item of an iterator as a line of JSON, as it is sent.
+#[cfg(feature = "json")]
+pub(crate) struct JsonLinesBody<I> {
+    items: sync_wrapper::SyncWrapper<I>,
+}
+
+/// Deserializes each line of a stream of chunks as JSON.
+#[cfg(feature = "json")]
+pub(crate) struct JsonLines<S, T> {
+    chunks: Option<S>,
+    buf: bytes::BytesMut,
+    // how much of `buf` is known to have no newline
+    scanned: usize,
+    _value: std::marker::PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "stream")]
+pin_project_lite::pin_project! {
+    /// A body that reports a known total length, so it can be sent with a
+    /// `Content-Length` instead of chunked.
+    struct ExactSizeBody<B> {
+        #[pin]
+        inner: B,
+        len: u64,
+    }
+}
+
 impl Body {
     /// Returns a reference to the internal data of the `Body`.
     ///
@@ -80,7 +126,61 @@ impl Body {
         Body::stream(stream)
     }
 
-    #[cfg(any(feature = "stream", feature = "multipart", feature = "blocking"))]
+    /// Stream a body from an `AsyncRead`, such as a `tokio::fs::File`.
+    ///
+    /// The reader is only read as the connection is ready to send more, so
+    /// the body is never buffered in full. If `len` is `Some`, it is sent as
+    /// the `Content-Length` and the reader must produce exactly that many
+    /// bytes. Otherwise, the body is sent with chunked transfer encoding.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use reqwest::Body;
+    /// # async fn run() -> std::io::Result<()> {
+    /// let file = tokio::fs::File::open("Cargo.toml").await?;
+    /// let len = file.metadata().await?.len();
+    ///
+    /// let body = Body::from_async_read(file, Some(len));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn from_async_read<R>(reader: R, len: Option<u64>) -> Body
+    where
+        R: tokio::io::AsyncRead + Send + Sync + 'static,
+    {
+        use futures_util::TryStreamExt;
+        use http_body::Frame;
+        use http_body_util::{BodyExt, StreamBody};
+
+        let body = StreamBody::new(
+            ReaderStream::new(reader)
+                .map_ok(Frame::data)
+                .map_err(Into::into),
+        );
+        let boxed = match len {
+            Some(len) => ExactSizeBody { inner: body, len }.boxed(),
+            None => body.boxed(),
+        };
+        Body {
+            inner: Inner::Streaming(boxed),
+        }
+    }
+
+    #[cfg(any(
+        feature = "stream",
+        feature = "multipart",
+        feature = "blocking",
+        feature = "gzip",
+        feature = "brotli",
+        feature = "zstd"
+    ))]
     pub(crate) fn stream<S>(stream: S) -> Body
     where
         S: futures_core::stream::TryStream + Send + Sync + 'static,
@@ -141,6 +241,22 @@ impl Body {
         }
     }
 
+    /// Like `streaming`, for a body that already has the right types, so
+    /// its size hint is kept.
+    pub(crate) fn boxed<B>(inner: B) -> Body
+    where
+        B: HttpBody<Data = Bytes, Error = Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        use http_body_util::BodyExt;
+
+        Body {
+            inner: Inner::Streaming(inner.boxed()),
+        }
+    }
+
     pub(crate) fn try_reuse(self) -> (Option<Bytes>, Self) {
         let reuse = match self.inner {
             Inner::Reusable(ref chunk) => Some(chunk.clone()),
@@ -150,6 +266,35 @@ impl Body {
         (reuse, self)
     }
 
+    /// Reads the body into memory if it is at most `limit` bytes long, so it
+    /// can be sent again. A longer one is returned as a stream of what was
+    /// read followed by the rest.
+    pub(crate) async fn buffer_up_to(self, limit: usize) -> crate::Result<Body> {
+        use http_body_util::BodyExt;
+
+        let mut rest = match self.inner {
+            Inner::Reusable(_) => return Ok(self),
+            Inner::Streaming(body) => body,
+        };
+        let mut read = Vec::new();
+        let mut len = 0;
+        while let Some(frame) = rest.frame().await {
+            let data = match frame.map_err(crate::error::body)?.into_data() {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+            len += data.len();
+            read.push(data);
+            if len > limit {
+                return Ok(Body::boxed(ReadAhead {
+                    read: read.into(),
+                    rest,
+                }));
+            }
+        }
+        Ok(Body::reusable(read.concat().into()))
+    }
+
     pub(crate) fn try_clone(&self) -> Option<Body> {
         match self.inner {
             Inner::Reusable(ref chunk) => Some(Body::reusable(chunk.clone())),
@@ -157,7 +302,12 @@ impl Body {
         }
     }
 
-    #[cfg(feature = "multipart")]
+    #[cfg(any(
+        feature = "multipart",
+        feature = "gzip",
+        feature = "brotli",
+        feature = "zstd"
+    ))]
     pub(crate) fn into_stream(self) -> DataStream<Body> {
         DataStream(self)
     }
@@ -280,6 +430,67 @@ impl HttpBody for Body {
     }
 }
 
+// ===== impl ReadAhead =====
+
+/// A body partly read by `Body::buffer_up_to`.
+struct ReadAhead {
+    read: std::collections::VecDeque<Bytes>,
+    rest: BoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl HttpBody for ReadAhead {
+    type Data = Bytes;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        if let Some(data) = self.read.pop_front() {
+            return Poll::Ready(Some(Ok(hyper::body::Frame::data(data))));
+        }
+        Pin::new(&mut self.rest).poll_frame(cx)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        let read = self.read.iter().map(|data| data.len() as u64).sum::<u64>();
+        let rest = self.rest.size_hint();
+        let mut hint = http_body::SizeHint::new();
+        hint.set_lower(rest.lower() + read);
+        if let Some(upper) = rest.upper() {
+            hint.set_upper(upper + read);
+        }
+        hint
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.read.is_empty() && self.rest.is_end_stream()
+    }
+}
+
+// ===== impl ExactSizeBody =====
+
+#[cfg(feature = "stream")]
+impl<B: HttpBody> HttpBody for ExactSizeBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        self.project().inner.poll_frame(cx)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        http_body::SizeHint::with_exact(self.len)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.len == 0 || self.inner.is_end_stream()
+    }
+}
+
 // ===== impl TotalTimeoutBody =====
 
 pub(crate) fn total_timeout<B>(body: B, timeout: Pin<Box<Sleep>>) -> TotalTimeoutBody<B> {
@@ -321,19 +532,93 @@ where
     }
 }
 
+// ===== impl LimitedBody =====
+
+pub(crate) fn limited<B>(body: B, limit: u64) -> LimitedBody<B> {
+    LimitedBody {
+        inner: body,
+        remaining: limit,
+        limit,
+        failed: false,
+    }
+}
+
+impl<B> hyper::body::Body for LimitedBody<B>
+where
+    B: hyper::body::Body<Data = Bytes> + Unpin,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Data = Bytes;
+    type Error = crate::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        if self.failed {
+            return Poll::Ready(None);
+        }
+        // a declared length fails before anything is read
+        let too_large = self.inner.size_hint().lower() > self.remaining;
+        let frame = if too_large {
+            None
+        } else {
+            futures_core::ready!(Pin::new(&mut self.inner).poll_frame(cx))
+        };
+        match frame {
+            Some(Ok(frame)) => {
+                let len = frame.data_ref().map_or(0, |data| data.len() as u64);
+                match self.remaining.checked_sub(len) {
+                    Some(remaining) => {
+                        self.remaining = remaining;
+                        Poll::Ready(Some(Ok(frame)))
+                    }
+                    None => self.fail(),
+                }
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(crate::error::body_read(err.into())))),
+            None if too_large => self.fail(),
+            None => Poll::Ready(None),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.failed || self.inner.is_end_stream()
+    }
+}
+
+impl<B> LimitedBody<B> {
+    fn fail(&mut self) -> Poll<Option<Result<hyper::body::Frame<Bytes>, crate::Error>>> {
+        self.failed = true;
+        let limit = self.limit;
+        Poll::Ready(Some(Err(crate::error::body(
+            crate::error::BodyTooLarge { limit },
+        ))))
+    }
+}
+
 pub(crate) type ResponseBody =
     http_body_util::combinators::BoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>;
 
-pub(crate) fn response(
-    body: hyper::body::Incoming,
-    timeout: Option<Pin<Box<Sleep>>>,
-) -> ResponseBody {
+pub(crate) fn incoming(body: hyper::body::Incoming) -> ResponseBody {
+    use http_body_util::BodyExt;
+
+    body.map_err(Into::into).boxed()
+}
+
+pub(crate) fn response(body: ResponseBody, timeout: Option<Pin<Box<Sleep>>>) -> ResponseBody {
     use http_body_util::BodyExt;
 
     if let Some(timeout) = timeout {
         total_timeout(body, timeout).map_err(Into::into).boxed()
     } else {
-        body.map_err(Into::into).boxed()
+        body
     }
 }
 
@@ -363,6 +648,153 @@ where
     }
 }
 
+// ===== impl CappedDataStream =====
+
+#[cfg(feature = "stream")]
+impl<B> CappedDataStream<B> {
+    pub(crate) fn new(body: B, max: usize) -> CappedDataStream<B> {
+        assert!(max > 0, "stream chunks must be allowed at least one byte");
+        CappedDataStream {
+            body,
+            rest: Bytes::new(),
+            max,
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<B> futures_core::Stream for CappedDataStream<B>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+{
+    type Item = Result<Bytes, B::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        while self.rest.is_empty() {
+            match futures_core::ready!(Pin::new(&mut self.body).poll_frame(cx)) {
+                Some(Ok(frame)) => {
+                    // skip non-data frames
+                    if let Ok(buf) = frame.into_data() {
+                        self.rest = buf;
+                    }
+                }
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => return Poll::Ready(None),
+            }
+        }
+        let len = self.rest.len().min(self.max);
+        Poll::Ready(Some(Ok(self.rest.split_to(len))))
+    }
+}
+
+// ===== impl JsonLines =====
+
+#[cfg(feature = "json")]
+impl<S, T> JsonLines<S, T> {
+    pub(crate) fn new(chunks: S) -> JsonLines<S, T> {
+        JsonLines {
+            chunks: Some(chunks),
+            buf: bytes::BytesMut::new(),
+            scanned: 0,
+            _value: std::marker::PhantomData,
+        }
+    }
+
+    /// Takes the next complete line out of the buffer, or the rest of it
+    /// once the chunks have ended.
+    fn next_line(&mut self) -> Option<bytes::BytesMut> {
+        match self.buf[self.scanned..].iter().position(|&b| b == b'\n') {
+            Some(pos) => {
+                let line = self.buf.split_to(self.scanned + pos + 1);
+                self.scanned = 0;
+                Some(line)
+            }
+            None if self.chunks.is_none() && !self.buf.is_empty() => {
+                self.scanned = 0;
+                Some(self.buf.split())
+            }
+            None => {
+                self.scanned = self.buf.len();
+                None
+            }
+        }
+    }
+}
+
+// ===== impl JsonLinesBody =====
+
+#[cfg(feature = "json")]
+impl<I> JsonLinesBody<I> {
+    pub(crate) fn new(items: I) -> JsonLinesBody<I> {
+        JsonLinesBody {
+            items: sync_wrapper::SyncWrapper::new(items),
+        }
+    }
+}
+
+// the iterator is never pinned
+#[cfg(feature = "json")]
+impl<I> Unpin for JsonLinesBody<I> {}
+
+#[cfg(feature = "json")]
+impl<I> HttpBody for JsonLinesBody<I>
+where
+    I: Iterator,
+    I::Item: serde::Serialize,
+{
+    type Data = Bytes;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        _cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        let item = match self.get_mut().items.get_mut().next() {
+            Some(item) => item,
+            None => return Poll::Ready(None),
+        };
+        let mut line = match serde_json::to_vec(&item) {
+            Ok(line) => line,
+            Err(err) => return Poll::Ready(Some(Err(err.into()))),
+        };
+        line.push(b'\n');
+        Poll::Ready(Some(Ok(hyper::body::Frame::data(line.into()))))
+    }
+}
+
+#[cfg(feature = "json")]
+impl<S, T> futures_core::Stream for JsonLines<S, T>
+where
+    S: futures_core::Stream<Item = crate::Result<Bytes>> + Unpin,
+    T: serde::de::DeserializeOwned,
+{
+    type Item = crate::Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            while let Some(line) = self.next_line() {
+                // skip blank lines, which also covers `\r\n` endings
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                return Poll::Ready(Some(
+                    serde_json::from_slice(&line).map_err(crate::error::decode),
+                ));
+            }
+
+            let chunks = match self.chunks.as_mut() {
+                Some(chunks) => chunks,
+                None => return Poll::Ready(None),
+            };
+            match futures_core::ready!(Pin::new(chunks).poll_next(cx)) {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => self.chunks = None,
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use http_body::Body as _;
@@ -390,4 +822,54 @@ mod tests {
         assert!(!stream_body.is_end_stream());
         assert_eq!(stream_body.size_hint().exact(), None);
     }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn capped_data_stream_splits_frames() {
+        use futures_util::{stream, FutureExt, StreamExt};
+
+        let frames = stream::iter(["0123456789", "ab"].map(|s| {
+            Ok::<_, std::io::Error>(http_body::Frame::data(bytes::Bytes::from(s)))
+        }));
+        let body = http_body_util::StreamBody::new(frames);
+        let chunks = super::CappedDataStream::new(body, 4)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(chunks, ["0123", "4567", "89", "ab"]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_lines_across_chunks() {
+        use futures_util::{stream, FutureExt, StreamExt};
+
+        let chunks = ["{\"a\":", "1}\n\n{\"a\"", ":2}\r\n{\"a\":3}"]
+            .map(|s| Ok(bytes::Bytes::from(s)));
+        let values = super::JsonLines::<_, serde_json::Value>::new(stream::iter(chunks))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(values, [1, 2, 3].map(|a| serde_json::json!({ "a": a })));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_lines_malformed_line_and_split_char() {
+        use futures_util::{stream, FutureExt, StreamExt};
+
+        // "é" is split between the first two chunks
+        let chunks = [&b"\"caf\xc3"[..], b"\xa9\"\n{oops\n", b"\"tea\""]
+            .map(|s| Ok(bytes::Bytes::from_static(s)));
+        let values = super::JsonLines::<_, String>::new(stream::iter(chunks))
+            .collect::<Vec<_>>()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0].as_ref().unwrap(), "café");
+        assert!(values[1].as_ref().unwrap_err().is_decode());
+        assert_eq!(values[2].as_ref().unwrap(), "tea");
+    }
 }
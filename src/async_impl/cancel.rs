@@ -0,0 +1,100 @@
+//! Support for aborting a request from outside of its future.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::task::AtomicWaker;
+use url::Url;
+
+use super::response::Response;
+use crate::error;
+
+struct Shared {
+    aborted: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// A handle to abort a request sent with `RequestBuilder::send_cancelable`.
+///
+/// It can be cloned and moved to other tasks or threads.
+#[derive(Clone)]
+pub struct CancelHandle {
+    shared: Arc<Shared>,
+}
+
+impl CancelHandle {
+    /// Aborts the request.
+    ///
+    /// Its future resolves with an error for which `Error::is_aborted` is
+    /// true, dropping the request, and the connection with it if the
+    /// request was in progress on it. This does nothing if the future
+    /// already resolved: a `Response` it returned keeps working.
+    pub fn abort(&self) {
+        self.shared.aborted.store(true, Ordering::Release);
+        self.shared.waker.wake();
+    }
+
+    /// Returns true if `abort` was called.
+    pub fn is_aborted(&self) -> bool {
+        self.shared.aborted.load(Ordering::Acquire)
+    }
+}
+
+impl fmt::Debug for CancelHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CancelHandle")
+            .field("aborted", &self.is_aborted())
+            .finish()
+    }
+}
+
+/// A request future that a `CancelHandle` can abort.
+pub(crate) struct Cancelable<F> {
+    inner: Option<Pin<Box<F>>>,
+    url: Option<Url>,
+    shared: Arc<Shared>,
+}
+
+/// Wraps `fut`, sending a request to `url`, so it can be aborted.
+pub(crate) fn cancelable<F>(fut: F, url: Option<Url>) -> (Cancelable<F>, CancelHandle) {
+    let shared = Arc::new(Shared {
+        aborted: AtomicBool::new(false),
+        waker: AtomicWaker::new(),
+    });
+    let fut = Cancelable {
+        inner: Some(Box::pin(fut)),
+        url,
+        shared: shared.clone(),
+    };
+    (fut, CancelHandle { shared })
+}
+
+impl<F> Future for Cancelable<F>
+where
+    F: Future<Output = crate::Result<Response>>,
+{
+    type Output = crate::Result<Response>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.shared.waker.register(cx.waker());
+        if self.shared.aborted.load(Ordering::Acquire) {
+            // drop the request now, rather than when this future is
+            self.inner = None;
+            let mut err = error::request(error::Aborted);
+            if let Some(url) = self.url.take() {
+                err = err.with_url(url);
+            }
+            return Poll::Ready(Err(err));
+        }
+
+        self.inner
+            .as_mut()
+            .expect("Cancelable polled after completion")
+            .as_mut()
+            .poll(cx)
+    }
+}
@@ -1,6 +1,6 @@
 #[cfg(any(feature = "native-tls", feature = "__rustls",))]
 use std::any::Any;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
 use std::time::Duration;
 use std::{collections::HashMap, convert::TryInto, net::SocketAddr};
@@ -8,8 +8,8 @@ use std::{fmt, str};
 
 use bytes::Bytes;
 use http::header::{
-    Entry, HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH,
-    CONTENT_TYPE, LOCATION, PROXY_AUTHORIZATION, RANGE, REFERER, TRANSFER_ENCODING, USER_AGENT,
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING,
+    CONTENT_LENGTH, CONTENT_TYPE, EXPECT, LOCATION, PROXY_AUTHORIZATION, RANGE, REFERER, TRANSFER_ENCODING, USER_AGENT,
 };
 use http::uri::Scheme;
 use http::Uri;
@@ -23,29 +23,50 @@ use std::task::{Context, Poll};
 use tokio::time::Sleep;
 
 use super::decoder::Accepts;
-use super::request::{Request, RequestBuilder};
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+use super::encoder::ContentEncoding;
+use super::expect::{self, ExpectContinue};
+use super::request::{set_bearer_token, Request, RequestBuilder, TokenFn};
 use super::response::Response;
+use super::single_flight::{self, SingleFlight};
+use super::body::ResponseBody;
+use super::upgrade::Upgraded;
 use super::Body;
 #[cfg(feature = "http3")]
 use crate::async_impl::h3_client::connect::H3Connector;
 #[cfg(feature = "http3")]
 use crate::async_impl::h3_client::{H3Client, H3ResponseFuture};
-use crate::connect::Connector;
+use crate::pool::{ConnectionActivity, PoolCounters, PoolStats};
+use crate::trace;
+use crate::connect::{
+    ConnectTimeoutGuard, ConnectionHook, ConnectionInfo, ConnectionReused, ConnectionUse, Connector,
+    RequestProxiesGuard,
+};
 #[cfg(feature = "cookies")]
 use crate::cookie;
 #[cfg(feature = "hickory-dns")]
 use crate::dns::hickory::HickoryDnsResolver;
-use crate::dns::{gai::GaiResolver, DnsResolverWithOverrides, DynResolver, Resolve};
+use crate::digest_auth;
+use crate::dns::{
+    gai::GaiResolver, DnsResolverWithLocalAddress, DnsResolverWithOverrides, DynResolver, Resolve,
+};
 use crate::error;
 use crate::into_url::try_uri;
+use crate::middleware::{Hop, Middleware, Next};
+use crate::retry::RetryPolicy;
 use crate::redirect::{self, remove_sensitive_headers};
+use crate::sign::{RequestParts, RequestSigner};
+use crate::retry;
+use crate::timings::RequestTimer;
+use crate::wire::{WireDebug, WireDebugConfig};
 #[cfg(feature = "__tls")]
 use crate::tls::{self, TlsBackend};
 #[cfg(feature = "__tls")]
 use crate::Certificate;
 #[cfg(any(feature = "native-tls", feature = "__rustls"))]
 use crate::Identity;
-use crate::{IntoUrl, Method, Proxy, StatusCode, Url};
+use crate::proxy::RequestProxies;
+use crate::{IntoUrl, Method, NoProxy, Proxy, StatusCode, Url};
 use log::debug;
 #[cfg(feature = "http3")]
 use quinn::TransportConfig;
@@ -54,6 +75,12 @@ use quinn::VarInt;
 
 type HyperResponseFuture = hyper_util::client::legacy::ResponseFuture;
 
+// Flow-control window and frame size limits from RFC 9113, section 6.5.2.
+#[cfg(feature = "http2")]
+const HTTP2_MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+#[cfg(feature = "http2")]
+const HTTP2_FRAME_SIZE_RANGE: std::ops::RangeInclusive<u32> = (1 << 14)..=(1 << 24) - 1;
+
 /// An asynchronous `Client` to make Requests with.
 ///
 /// The Client has various configuration values to tweak, but the defaults
@@ -90,21 +117,45 @@ enum HttpVersionPref {
 struct Config {
     // NOTE: When adding a new field, update `fmt::Debug for ClientBuilder`
     accepts: Accepts,
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    compress_body: Option<ContentEncoding>,
     headers: HeaderMap,
     #[cfg(feature = "native-tls")]
     hostname_verification: bool,
     #[cfg(feature = "__tls")]
     certs_verification: bool,
+    // lowercase host patterns to skip certificate validation for
+    #[cfg(feature = "__tls")]
+    insecure_hosts: Vec<String>,
     #[cfg(feature = "__tls")]
     tls_sni: bool,
+    #[cfg(feature = "__tls")]
+    sni_overrides: HashMap<String, String>,
+    #[cfg(feature = "__tls")]
+    spki_pins: HashMap<String, Vec<[u8; 32]>>,
     connect_timeout: Option<Duration>,
+    expect_continue_timeout: Duration,
     connection_verbose: bool,
+    collect_timings: bool,
+    on_new_connection: Option<ConnectionHook>,
+    on_connection_reuse: Option<ConnectionHook>,
+    error_body_limit: usize,
+    max_response_size: Option<u64>,
     pool_idle_timeout: Option<Duration>,
+    pool_idle_timeout_per_host: HashMap<String, Duration>,
     pool_max_idle_per_host: usize,
+    pool_max_connections_per_host: Option<usize>,
     tcp_keepalive: Option<Duration>,
+    tcp_keepalive_interval: Option<Duration>,
+    tcp_keepalive_retries: Option<u32>,
+    tcp_send_buffer_size: Option<usize>,
+    tcp_recv_buffer_size: Option<usize>,
     #[cfg(any(feature = "native-tls", feature = "__rustls"))]
     identity: Option<Identity>,
+    #[cfg(feature = "__rustls")]
+    host_identities: HashMap<String, Identity>,
     proxies: Vec<Proxy>,
+    no_proxy_list: Option<NoProxy>,
     auto_sys_proxy: bool,
     redirect_policy: redirect::Policy,
     referer: bool,
@@ -131,6 +182,8 @@ struct Config {
     http1_allow_obsolete_multiline_headers_in_responses: bool,
     http1_ignore_invalid_headers_in_responses: bool,
     http1_allow_spaces_after_header_name_in_responses: bool,
+    http1_max_headers: Option<usize>,
+    http1_max_header_size: Option<usize>,
     #[cfg(feature = "http2")]
     http2_initial_stream_window_size: Option<u32>,
     #[cfg(feature = "http2")]
@@ -140,14 +193,21 @@ struct Config {
     #[cfg(feature = "http2")]
     http2_max_frame_size: Option<u32>,
     #[cfg(feature = "http2")]
+    http2_max_send_buf_size: Option<usize>,
+    #[cfg(feature = "http2")]
+    http2_max_header_list_size: Option<u32>,
+    #[cfg(feature = "http2")]
     http2_keep_alive_interval: Option<Duration>,
     #[cfg(feature = "http2")]
     http2_keep_alive_timeout: Option<Duration>,
     #[cfg(feature = "http2")]
     http2_keep_alive_while_idle: bool,
     local_address: Option<IpAddr>,
+    local_addresses: (Option<Ipv4Addr>, Option<Ipv6Addr>),
     #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
     interface: Option<String>,
+    #[cfg(all(unix, feature = "unix-socket"))]
+    unix_socket: Option<Arc<std::path::Path>>,
     nodelay: bool,
     #[cfg(feature = "cookies")]
     cookie_store: Option<Arc<dyn cookie::CookieStore>>,
@@ -166,6 +226,16 @@ struct Config {
     quic_send_window: Option<u64>,
     dns_overrides: HashMap<String, Vec<SocketAddr>>,
     dns_resolver: Option<Arc<dyn Resolve>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    hop_middleware: Vec<Arc<dyn Middleware>>,
+    single_flight: single_flight::Config,
+    #[cfg(feature = "cassette")]
+    cassette: Option<crate::cassette::Mode>,
+    #[cfg(feature = "cassette")]
+    cassette_match: crate::cassette::Match,
+    wire_debug: Option<WireDebugConfig>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    buffer_signed_bodies: bool,
 }
 
 impl Default for ClientBuilder {
@@ -186,21 +256,42 @@ impl ClientBuilder {
             config: Config {
                 error: None,
                 accepts: Accepts::default(),
+                #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+                compress_body: None,
                 headers,
                 #[cfg(feature = "native-tls")]
                 hostname_verification: true,
                 #[cfg(feature = "__tls")]
                 certs_verification: true,
                 #[cfg(feature = "__tls")]
+                insecure_hosts: Vec::new(),
+                #[cfg(feature = "__tls")]
                 tls_sni: true,
+                #[cfg(feature = "__tls")]
+                sni_overrides: HashMap::new(),
+                #[cfg(feature = "__tls")]
+                spki_pins: HashMap::new(),
                 connect_timeout: None,
+                expect_continue_timeout: Duration::from_secs(1),
                 connection_verbose: false,
+                collect_timings: false,
+                on_new_connection: None,
+                on_connection_reuse: None,
+                error_body_limit: 8 * 1024,
+                max_response_size: None,
                 pool_idle_timeout: Some(Duration::from_secs(90)),
+                pool_idle_timeout_per_host: HashMap::new(),
                 pool_max_idle_per_host: std::usize::MAX,
+                pool_max_connections_per_host: None,
                 // TODO: Re-enable default duration once hyper's HttpConnector is fixed
                 // to no longer error when an option fails.
                 tcp_keepalive: None, //Some(Duration::from_secs(60)),
+                tcp_keepalive_interval: None,
+                tcp_keepalive_retries: None,
+                tcp_send_buffer_size: None,
+                tcp_recv_buffer_size: None,
                 proxies: Vec::new(),
+                no_proxy_list: None,
                 auto_sys_proxy: true,
                 redirect_policy: redirect::Policy::default(),
                 referer: true,
@@ -215,6 +306,8 @@ impl ClientBuilder {
                 tls_built_in_certs_native: true,
                 #[cfg(any(feature = "native-tls", feature = "__rustls"))]
                 identity: None,
+                #[cfg(feature = "__rustls")]
+                host_identities: HashMap::new(),
                 #[cfg(feature = "__tls")]
                 min_tls_version: None,
                 #[cfg(feature = "__tls")]
@@ -229,6 +322,8 @@ impl ClientBuilder {
                 http1_allow_obsolete_multiline_headers_in_responses: false,
                 http1_ignore_invalid_headers_in_responses: false,
                 http1_allow_spaces_after_header_name_in_responses: false,
+                http1_max_headers: None,
+                http1_max_header_size: None,
                 #[cfg(feature = "http2")]
                 http2_initial_stream_window_size: None,
                 #[cfg(feature = "http2")]
@@ -238,14 +333,21 @@ impl ClientBuilder {
                 #[cfg(feature = "http2")]
                 http2_max_frame_size: None,
                 #[cfg(feature = "http2")]
+                http2_max_send_buf_size: None,
+                #[cfg(feature = "http2")]
+                http2_max_header_list_size: None,
+                #[cfg(feature = "http2")]
                 http2_keep_alive_interval: None,
                 #[cfg(feature = "http2")]
                 http2_keep_alive_timeout: None,
                 #[cfg(feature = "http2")]
                 http2_keep_alive_while_idle: false,
                 local_address: None,
+                local_addresses: (None, None),
                 #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
                 interface: None,
+                #[cfg(all(unix, feature = "unix-socket"))]
+                unix_socket: None,
                 nodelay: true,
                 hickory_dns: cfg!(feature = "hickory-dns"),
                 #[cfg(feature = "cookies")]
@@ -263,6 +365,16 @@ impl ClientBuilder {
                 #[cfg(feature = "http3")]
                 quic_send_window: None,
                 dns_resolver: None,
+                middleware: Vec::new(),
+                hop_middleware: Vec::new(),
+                single_flight: Default::default(),
+                #[cfg(feature = "cassette")]
+                cassette: None,
+                #[cfg(feature = "cassette")]
+                cassette_match: Default::default(),
+                wire_debug: None,
+                signer: None,
+                buffer_signed_bodies: false,
             },
         }
     }
@@ -284,36 +396,66 @@ impl ClientBuilder {
         if config.auto_sys_proxy {
             proxies.push(Proxy::system());
         }
+        if let Some(ref no_proxy) = config.no_proxy_list {
+            for proxy in &mut proxies {
+                proxy.no_proxy_or(no_proxy);
+            }
+        }
         let proxies = Arc::new(proxies);
 
+        #[cfg(target_os = "linux")]
+        if let Some(ref interface) = config.interface {
+            if !std::path::Path::new("/sys/class/net").join(interface).exists() {
+                log::warn!(
+                    "network interface {:?} not found, connections bound to it will fail",
+                    interface
+                );
+            }
+        }
+
         #[allow(unused)]
         #[cfg(feature = "http3")]
         let mut h3_connector = None;
 
+        let mut resolver: Arc<dyn Resolve> = match config.hickory_dns {
+            false => Arc::new(GaiResolver::new()),
+            #[cfg(feature = "hickory-dns")]
+            true => Arc::new(HickoryDnsResolver::default()),
+            #[cfg(not(feature = "hickory-dns"))]
+            true => unreachable!("hickory-dns shouldn't be enabled unless the feature is"),
+        };
+        if let Some(dns_resolver) = config.dns_resolver {
+            resolver = dns_resolver;
+        }
+        if !config.dns_overrides.is_empty() {
+            resolver = Arc::new(DnsResolverWithOverrides::new(
+                resolver,
+                config.dns_overrides,
+            ));
+        }
+        if let Some(local_address) = config.local_address {
+            resolver = Arc::new(DnsResolverWithLocalAddress::new(resolver, local_address));
+        }
+        // no-proxy IP ranges are matched against what the client resolves
+        let no_proxy_resolver = resolver.clone();
+
         let mut connector = {
             #[cfg(feature = "__tls")]
             fn user_agent(headers: &HeaderMap) -> Option<HeaderValue> {
                 headers.get(USER_AGENT).cloned()
             }
 
-            let mut resolver: Arc<dyn Resolve> = match config.hickory_dns {
-                false => Arc::new(GaiResolver::new()),
-                #[cfg(feature = "hickory-dns")]
-                true => Arc::new(HickoryDnsResolver::default()),
-                #[cfg(not(feature = "hickory-dns"))]
-                true => unreachable!("hickory-dns shouldn't be enabled unless the feature is"),
-            };
-            if let Some(dns_resolver) = config.dns_resolver {
-                resolver = dns_resolver;
-            }
-            if !config.dns_overrides.is_empty() {
-                resolver = Arc::new(DnsResolverWithOverrides::new(
-                    resolver,
-                    config.dns_overrides,
-                ));
-            }
             let mut http = HttpConnector::new_with_resolver(DynResolver::new(resolver.clone()));
             http.set_connect_timeout(config.connect_timeout);
+            // the OS may cap these, which isn't an error
+            if let Some(size) = config.tcp_send_buffer_size {
+                log::trace!("requesting a TCP send buffer of {size} bytes");
+            }
+            if let Some(size) = config.tcp_recv_buffer_size {
+                log::trace!("requesting a TCP receive buffer of {size} bytes");
+            }
+            http.set_send_buffer_size(config.tcp_send_buffer_size);
+            http.set_recv_buffer_size(config.tcp_recv_buffer_size);
 
             #[cfg(all(feature = "http3", feature = "__rustls"))]
             let build_h3_connector =
@@ -364,6 +506,13 @@ impl ClientBuilder {
                     }
                 };
 
+            #[cfg(feature = "__rustls")]
+            if !config.host_identities.is_empty() && !matches!(config.tls, TlsBackend::Rustls) {
+                return Err(crate::error::builder(
+                    "identities for hosts require the rustls TLS backend",
+                ));
+            }
+
             #[cfg(feature = "__tls")]
             match config.tls {
                 #[cfg(feature = "default-tls")]
@@ -436,7 +585,16 @@ impl ClientBuilder {
                         tls.max_protocol_version(Some(protocol));
                     }
 
-                    Connector::new_default_tls(
+                    let insecure_tls = if config.insecure_hosts.is_empty() {
+                        None
+                    } else {
+                        tls.danger_accept_invalid_certs(true);
+                        let insecure_tls = tls.build().map_err(crate::error::builder)?;
+                        tls.danger_accept_invalid_certs(!config.certs_verification);
+                        Some(insecure_tls)
+                    };
+
+                    let mut connector = Connector::new_default_tls(
                         http,
                         tls,
                         proxies.clone(),
@@ -450,22 +608,42 @@ impl ClientBuilder {
                         config.interface.as_deref(),
                         config.nodelay,
                         config.tls_info,
-                    )?
+                    )?;
+                    if let Some(insecure_tls) = insecure_tls {
+                        connector.set_native_tls_insecure_hosts(config.insecure_hosts, insecure_tls);
+                    }
+                    connector
                 }
                 #[cfg(feature = "native-tls")]
-                TlsBackend::BuiltNativeTls(conn) => Connector::from_built_default_tls(
-                    http,
-                    conn,
-                    proxies.clone(),
-                    user_agent(&config.headers),
-                    config.local_address,
-                    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
-                    config.interface.as_deref(),
-                    config.nodelay,
-                    config.tls_info,
-                ),
+                TlsBackend::BuiltNativeTls(conn) => {
+                    if !config.insecure_hosts.is_empty() {
+                        return Err(crate::error::builder(
+                            "danger_accept_invalid_certs_for can't be used with a preconfigured TLS backend",
+                        ));
+                    }
+                    Connector::from_built_default_tls(
+                        http,
+                        conn,
+                        proxies.clone(),
+                        user_agent(&config.headers),
+                        config.local_address,
+                        #[cfg(any(
+                            target_os = "android",
+                            target_os = "fuchsia",
+                            target_os = "linux"
+                        ))]
+                        config.interface.as_deref(),
+                        config.nodelay,
+                        config.tls_info,
+                    )
+                }
                 #[cfg(feature = "__rustls")]
                 TlsBackend::BuiltRustls(conn) => {
+                    if !config.insecure_hosts.is_empty() {
+                        return Err(crate::error::builder(
+                            "danger_accept_invalid_certs_for can't be used with a preconfigured TLS backend",
+                        ));
+                    }
                     #[cfg(feature = "http3")]
                     {
                         h3_connector = build_h3_connector(
@@ -498,7 +676,7 @@ impl ClientBuilder {
                 }
                 #[cfg(feature = "__rustls")]
                 TlsBackend::Rustls => {
-                    use crate::tls::NoVerifier;
+                    use crate::tls::{HostPatternVerifier, NoVerifier};
 
                     // Set root certificates.
                     let mut root_cert_store = rustls::RootCertStore::empty();
@@ -564,9 +742,20 @@ impl ClientBuilder {
                     }
 
                     // Build TLS config
+                    let root_cert_store = Arc::new(root_cert_store);
                     let config_builder =
                         rustls::ClientConfig::builder_with_protocol_versions(&versions)
-                            .with_root_certificates(root_cert_store);
+                            .with_root_certificates(root_cert_store.clone());
+
+                    // Hosts with their own identity get a copy of the config
+                    // below, only presenting another client certificate.
+                    let mut host_resolvers = HashMap::new();
+                    for (host, id) in config.host_identities {
+                        let resolver = id
+                            .add_to_rustls(config_builder.clone())?
+                            .client_auth_cert_resolver;
+                        host_resolvers.insert(host, resolver);
+                    }
 
                     // Finalize TLS config
                     let mut tls = if let Some(id) = config.identity {
@@ -579,6 +768,15 @@ impl ClientBuilder {
                     if !config.certs_verification {
                         tls.dangerous()
                             .set_certificate_verifier(Arc::new(NoVerifier));
+                    } else if !config.insecure_hosts.is_empty() {
+                        let inner = rustls::client::WebPkiServerVerifier::builder(root_cert_store)
+                            .build()
+                            .map_err(crate::error::builder)?;
+                        tls.dangerous()
+                            .set_certificate_verifier(Arc::new(HostPatternVerifier {
+                                inner,
+                                patterns: config.insecure_hosts,
+                            }));
                     }
 
                     tls.enable_sni = config.tls_sni;
@@ -621,7 +819,16 @@ impl ClientBuilder {
                         )?;
                     }
 
-                    Connector::new_rustls_tls(
+                    let host_tls = host_resolvers
+                        .into_iter()
+                        .map(|(host, resolver)| {
+                            let mut host_tls = tls.clone();
+                            host_tls.client_auth_cert_resolver = resolver;
+                            (host, host_tls)
+                        })
+                        .collect();
+
+                    let mut connector = Connector::new_rustls_tls(
                         http,
                         tls,
                         proxies.clone(),
@@ -635,7 +842,9 @@ impl ClientBuilder {
                         config.interface.as_deref(),
                         config.nodelay,
                         config.tls_info,
-                    )
+                    );
+                    connector.set_rustls_per_host(host_tls);
+                    connector
                 }
                 #[cfg(any(feature = "native-tls", feature = "__rustls",))]
                 TlsBackend::UnknownPreconfigured => {
@@ -657,7 +866,23 @@ impl ClientBuilder {
         };
 
         connector.set_timeout(config.connect_timeout);
+        connector.set_no_proxy_resolver(no_proxy_resolver);
         connector.set_verbose(config.connection_verbose);
+        connector.set_timings(config.collect_timings);
+        connector.set_connection_hooks(
+            config.on_new_connection.clone(),
+            config.on_connection_reuse.is_some(),
+        );
+        connector.set_max_connections_per_host(config.pool_max_connections_per_host);
+        if config.local_addresses != (None, None) {
+            connector.set_local_addresses(config.local_addresses.0, config.local_addresses.1);
+        }
+        #[cfg(feature = "__tls")]
+        connector.set_sni_overrides(config.sni_overrides);
+        #[cfg(feature = "__tls")]
+        connector.set_spki_pins(config.spki_pins);
+        #[cfg(all(unix, feature = "unix-socket"))]
+        connector.set_unix_socket(config.unix_socket);
 
         let mut builder =
             hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new());
@@ -682,6 +907,12 @@ impl ClientBuilder {
             if let Some(http2_max_frame_size) = config.http2_max_frame_size {
                 builder.http2_max_frame_size(http2_max_frame_size);
             }
+            if let Some(http2_max_send_buf_size) = config.http2_max_send_buf_size {
+                builder.http2_max_send_buf_size(http2_max_send_buf_size);
+            }
+            if let Some(http2_max_header_list_size) = config.http2_max_header_list_size {
+                builder.http2_max_header_list_size(http2_max_header_list_size);
+            }
             if let Some(http2_keep_alive_interval) = config.http2_keep_alive_interval {
                 builder.http2_keep_alive_interval(http2_keep_alive_interval);
             }
@@ -698,6 +929,8 @@ impl ClientBuilder {
         builder.pool_idle_timeout(config.pool_idle_timeout);
         builder.pool_max_idle_per_host(config.pool_max_idle_per_host);
         connector.set_keepalive(config.tcp_keepalive);
+        connector.set_keepalive_interval(config.tcp_keepalive_interval);
+        connector.set_keepalive_retries(config.tcp_keepalive_retries);
 
         if config.http09_responses {
             builder.http09_responses(true);
@@ -719,7 +952,43 @@ impl ClientBuilder {
             builder.http1_allow_spaces_after_header_name_in_responses(true);
         }
 
+        if let Some(http1_max_headers) = config.http1_max_headers {
+            builder.http1_max_headers(http1_max_headers);
+        }
+
+        if let Some(http1_max_header_size) = config.http1_max_header_size {
+            builder.http1_max_buf_size(http1_max_header_size);
+        }
+
+        let pool = connector.pool_counters();
+
+        // hyper's pool has a single idle timeout, so each host with its own
+        // gets a separate pool, sharing the same connector.
+        let hyper_per_host = config
+            .pool_idle_timeout_per_host
+            .into_iter()
+            .map(|(host, timeout)| {
+                let mut builder = builder.clone();
+                builder.pool_idle_timeout(timeout);
+                (host, builder.build(connector.clone()))
+            })
+            .collect();
+
         let proxies_maybe_http_auth = proxies.iter().any(|p| p.maybe_has_http_auth());
+        let proxies_by_request = proxies.iter().any(Proxy::is_by_request);
+
+        #[allow(unused_mut)]
+        let mut middleware = config.middleware;
+        #[cfg(feature = "cassette")]
+        if let Some(mode) = config.cassette {
+            let cassette = crate::cassette::Cassette::new(mode, config.cassette_match)?;
+            middleware.insert(0, Arc::new(cassette));
+        }
+
+        let mut hop_middleware = config.hop_middleware;
+        if let Some(wire_debug) = config.wire_debug {
+            hop_middleware.push(Arc::new(WireDebug(Arc::new(wire_debug))));
+        }
 
         Ok(Client {
             inner: Arc::new(ClientRef {
@@ -735,14 +1004,31 @@ impl ClientBuilder {
                     }
                     None => None,
                 },
-                hyper: builder.build(connector),
+                hyper: builder.build(connector.clone()),
+                hyper_per_host,
+                connector,
                 headers: config.headers,
                 redirect_policy: config.redirect_policy,
                 referer: config.referer,
                 request_timeout: config.timeout,
+                expect_continue_timeout: config.expect_continue_timeout,
                 proxies,
                 proxies_maybe_http_auth,
+                proxies_by_request,
                 https_only: config.https_only,
+                middleware,
+                hop_middleware,
+                signer: config.signer,
+                buffer_signed_bodies: config.buffer_signed_bodies,
+                collect_timings: config.collect_timings,
+                on_connection_reuse: config.on_connection_reuse,
+                error_body_limit: config.error_body_limit,
+                max_response_size: config.max_response_size,
+                #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+                compress_body: config.compress_body,
+                pool,
+                digest_nonces: Default::default(),
+                single_flight: SingleFlight::new(config.single_flight),
             }),
         })
     }
@@ -786,6 +1072,11 @@ impl ClientBuilder {
     }
     /// Sets the default headers for every request.
     ///
+    /// These are a baseline: any header set on a request replaces all the
+    /// default values of the same name, instead of being added to them.
+    /// Calling this again replaces the defaults with the same names as in
+    /// `headers`, and keeps the rest.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -808,9 +1099,7 @@ impl ClientBuilder {
     /// # }
     /// ```
     pub fn default_headers(mut self, headers: HeaderMap) -> ClientBuilder {
-        for (key, value) in headers.iter() {
-            self.config.headers.insert(key, value.clone());
-        }
+        crate::util::replace_headers(&mut self.config.headers, headers);
         self
     }
 
@@ -931,6 +1220,29 @@ impl ClientBuilder {
         self
     }
 
+    /// Enable auto zstd decompression by checking the `Content-Encoding` response header.
+    ///
+    /// If auto zstd decompression is turned on:
+    ///
+    /// - When sending a request and if the request's headers do not already contain
+    ///   an `Accept-Encoding` **and** `Range` values, the `Accept-Encoding` header is set to `zstd`.
+    ///   The request body is **not** automatically compressed.
+    /// - When receiving a response, if its headers contain a `Content-Encoding` value of
+    ///   `zstd`, both `Content-Encoding` and `Content-Length` are removed from the
+    ///   headers' set. The response body is automatically decompressed.
+    ///
+    /// If the `zstd` feature is turned on, the default option is enabled.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `zstd` feature to be enabled
+    #[cfg(feature = "zstd")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+    pub fn zstd(mut self, enable: bool) -> ClientBuilder {
+        self.config.accepts.zstd = enable;
+        self
+    }
+
     /// Disable auto response body gzip decompression.
     ///
     /// This method exists even if the optional `gzip` feature is not enabled.
@@ -982,6 +1294,46 @@ impl ClientBuilder {
         }
     }
 
+    /// Disable auto response body zstd decompression.
+    ///
+    /// This method exists even if the optional `zstd` feature is not enabled.
+    /// This can be used to ensure a `Client` doesn't use zstd decompression
+    /// even if another dependency were to enable the optional `zstd` feature.
+    pub fn no_zstd(self) -> ClientBuilder {
+        #[cfg(feature = "zstd")]
+        {
+            self.zstd(false)
+        }
+
+        #[cfg(not(feature = "zstd"))]
+        {
+            self
+        }
+    }
+
+    /// Compress request bodies with `encoding` by default.
+    ///
+    /// This applies to every request with a body, unless the request sets
+    /// its own with `RequestBuilder::compress_body`, or already has a
+    /// `Content-Encoding` header. See `RequestBuilder::compress_body` for
+    /// how the body is sent.
+    ///
+    /// Default is to not compress request bodies.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `gzip`, `brotli` or `zstd` feature to be
+    /// enabled, for the matching `ContentEncoding`.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "gzip", feature = "brotli", feature = "zstd")))
+    )]
+    pub fn compress_body(mut self, encoding: ContentEncoding) -> ClientBuilder {
+        self.config.compress_body = Some(encoding);
+        self
+    }
+
     // Redirect options
 
     /// Set a `RedirectPolicy` for this client.
@@ -1026,6 +1378,28 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets a `No Proxy` exclusion list for every proxy that has none.
+    ///
+    /// This includes the "system" proxy, unless the `NO_PROXY` environment
+    /// variable gives it one. The list can contain IP ranges, which also
+    /// match host names resolving into them, see [`NoProxy::from_string`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = reqwest::Client::builder()
+    ///     .proxy(reqwest::Proxy::all("http://proxy.example:8080")?)
+    ///     .no_proxy_list(reqwest::NoProxy::from_string("10.0.0.0/8, .internal").unwrap())
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn no_proxy_list(mut self, no_proxy: NoProxy) -> ClientBuilder {
+        self.config.no_proxy_list = Some(no_proxy);
+        self
+    }
+
     // Timeout options
 
     /// Enables a request timeout.
@@ -1052,17 +1426,115 @@ impl ClientBuilder {
         self
     }
 
+    /// Set how long a request sent with `RequestBuilder::expect_continue`
+    /// waits for `100 Continue` before sending its body anyway.
+    ///
+    /// Servers that don't know about `Expect` never answer it, so the body
+    /// is eventually sent without an answer.
+    ///
+    /// Default is 1 second.
+    pub fn expect_continue_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.config.expect_continue_timeout = timeout;
+        self
+    }
+
     /// Set whether connections should emit verbose logs.
     ///
     /// Enabling this option will emit [log][] messages at the `TRACE` level
     /// for read and write operations on connections.
     ///
+    /// It also emits [tracing][] events at the `TRACE` level, with the
+    /// target `reqwest::connection`, as each connection goes through DNS
+    /// resolution, the TCP connect and the TLS handshake, each with a
+    /// started and a completed event, sends its first byte, receives its
+    /// first byte, and is closed, and for each response, as a connection
+    /// pool hit or miss. The events have the fields `url.host`,
+    /// `remote.addr`, `duration_ms` and `http.version`, once they are known.
+    ///
     /// [log]: https://crates.io/crates/log
+    /// [tracing]: https://crates.io/crates/tracing
     pub fn connection_verbose(mut self, verbose: bool) -> ClientBuilder {
         self.config.connection_verbose = verbose;
         self
     }
 
+    /// Set whether to measure how long each phase of a request takes.
+    ///
+    /// When enabled, `Response::timings` reports the time spent on DNS,
+    /// connecting, the TLS handshake and waiting for the response.
+    ///
+    /// Default is `false`.
+    pub fn collect_timings(mut self, enabled: bool) -> ClientBuilder {
+        self.config.collect_timings = enabled;
+        self
+    }
+
+    /// Sets a function to call each time the client opens a connection.
+    ///
+    /// It is called as soon as the connection is open, before any request
+    /// is sent on it, from the task opening the connection. It should
+    /// return quickly, since the connection waits for it.
+    ///
+    /// This is independent of any middleware: a request may open a
+    /// connection, reuse one, or neither if a middleware answers it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn doc() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .on_new_connection(|info| {
+    ///         println!("connected to {:?} in {:?}", info.remote_addr(), info.handshake_duration());
+    ///     })
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_new_connection<F>(mut self, hook: F) -> ClientBuilder
+    where
+        F: Fn(ConnectionInfo) + Send + Sync + 'static,
+    {
+        self.config.on_new_connection = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets a function to call each time a request is sent over a pooled
+    /// connection that was opened earlier.
+    ///
+    /// It is called when the response arrives, with the details recorded
+    /// when the connection was opened. It should return quickly, like
+    /// `on_new_connection`.
+    pub fn on_connection_reuse<F>(mut self, hook: F) -> ClientBuilder
+    where
+        F: Fn(ConnectionInfo) + Send + Sync + 'static,
+    {
+        self.config.on_connection_reuse = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set how many bytes of the body `RequestBuilder::send_success` keeps
+    /// in its error, when the response is a client or server error.
+    ///
+    /// Default is 8 KiB.
+    pub fn error_body_limit(mut self, limit: usize) -> ClientBuilder {
+        self.config.error_body_limit = limit;
+        self
+    }
+
+    /// Set the largest response body, in bytes, that can be read.
+    ///
+    /// Once a body turns out to be larger, reading it fails with an error
+    /// for which `Error::is_body_too_large` is true, whichever method reads
+    /// it, as with `Response::bytes_limited`. The limit applies to the body
+    /// after decompression. The response itself is still returned, so its
+    /// status and headers can be looked at.
+    ///
+    /// Default is no limit.
+    pub fn max_response_size(mut self, max: usize) -> ClientBuilder {
+        self.config.max_response_size = Some(max as u64);
+        self
+    }
+
     // HTTP options
 
     /// Set an optional timeout for idle sockets being kept-alive.
@@ -1078,12 +1550,66 @@ impl ClientBuilder {
         self
     }
 
+    /// Set the timeout for idle sockets to one host, overriding
+    /// `pool_idle_timeout` for it.
+    ///
+    /// `host` must match the request URL's host and port exactly, such as
+    /// `"example.com:443"`. The port is required, even when it is the
+    /// default for the scheme.
+    ///
+    /// Connections to `host` are kept in a pool of their own, so the other
+    /// pool settings apply to it separately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .pool_idle_timeout_for("lb.example.com:443", Duration::from_secs(5))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pool_idle_timeout_for(mut self, host: &str, timeout: Duration) -> ClientBuilder {
+        self.config
+            .pool_idle_timeout_per_host
+            .insert(host.to_ascii_lowercase(), timeout);
+        self
+    }
+
     /// Sets the maximum idle connection per host allowed in the pool.
     pub fn pool_max_idle_per_host(mut self, max: usize) -> ClientBuilder {
         self.config.pool_max_idle_per_host = max;
         self
     }
 
+    /// Sets the maximum number of connections open to a host at once.
+    ///
+    /// Once `max` connections to a host are open, idle or not, a request
+    /// that needs a new one waits until a connection to that host is
+    /// closed, or one becomes idle for it to reuse. This counts
+    /// connections, not requests: an HTTP/2 connection counts once however
+    /// many requests it carries.
+    ///
+    /// Idle connections also count, so this is best combined with a
+    /// `pool_idle_timeout` or `pool_max_idle_per_host`.
+    ///
+    /// Default is no limit.
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if `max` is 0.
+    pub fn pool_max_connections_per_host(mut self, max: usize) -> ClientBuilder {
+        if max == 0 {
+            self.config.error = Some(crate::error::builder(
+                "pool_max_connections_per_host must be at least 1",
+            ));
+        }
+        self.config.pool_max_connections_per_host = Some(max);
+        self
+    }
+
     /// Send headers as title case instead of lowercase.
     pub fn http1_title_case_headers(mut self) -> ClientBuilder {
         self.config.http1_title_case_headers = true;
@@ -1124,6 +1650,40 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the maximum number of headers of an HTTP/1 response.
+    ///
+    /// A response with more headers fails with a request error, instead of
+    /// keeping only some of them.
+    ///
+    /// Default is 100, the default of hyper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn http1_max_headers_count(mut self, max: usize) -> ClientBuilder {
+        assert!(max > 0, "HTTP/1 max headers count must be at least 1");
+        self.config.http1_max_headers = Some(max);
+        self
+    }
+
+    /// Sets the maximum size of the head of an HTTP/1 response, in bytes.
+    ///
+    /// This is the size of the buffer the status line and headers are read
+    /// into, so a response with a larger head fails with a request error,
+    /// instead of keeping only some of its headers.
+    ///
+    /// Default is 417,792 (about 400KB), the default of hyper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is less than 8,192, the minimum of hyper, including
+    /// if it is 0.
+    pub fn http1_max_header_size(mut self, max: usize) -> ClientBuilder {
+        assert!(max >= 8192, "HTTP/1 max header size must be at least 8,192");
+        self.config.http1_max_header_size = Some(max);
+        self
+    }
+
     /// Only use HTTP/1.
     pub fn http1_only(mut self) -> ClientBuilder {
         self.config.http_version_pref = HttpVersionPref::Http1;
@@ -1137,6 +1697,9 @@ impl ClientBuilder {
     }
 
     /// Only use HTTP/2.
+    ///
+    /// Server push is always disabled, by sending `SETTINGS_ENABLE_PUSH` as
+    /// 0, so servers never push resources to a `Client`.
     #[cfg(feature = "http2")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
     pub fn http2_prior_knowledge(mut self) -> ClientBuilder {
@@ -1155,23 +1718,45 @@ impl ClientBuilder {
     /// Sets the `SETTINGS_INITIAL_WINDOW_SIZE` option for HTTP2 stream-level flow control.
     ///
     /// Default is currently 65,535 but may change internally to optimize for common uses.
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if `sz` is larger than 2^31-1, the maximum
+    /// window size allowed by RFC 9113.
     #[cfg(feature = "http2")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
     pub fn http2_initial_stream_window_size(mut self, sz: impl Into<Option<u32>>) -> ClientBuilder {
-        self.config.http2_initial_stream_window_size = sz.into();
+        let sz = sz.into();
+        if sz.map_or(false, |sz| sz > HTTP2_MAX_WINDOW_SIZE) {
+            self.config.error = Some(crate::error::builder(
+                "HTTP2 stream window size exceeds 2^31-1",
+            ));
+        }
+        self.config.http2_initial_stream_window_size = sz;
         self
     }
 
     /// Sets the max connection-level flow control for HTTP2
     ///
     /// Default is currently 65,535 but may change internally to optimize for common uses.
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if `sz` is larger than 2^31-1, the maximum
+    /// window size allowed by RFC 9113.
     #[cfg(feature = "http2")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
     pub fn http2_initial_connection_window_size(
         mut self,
         sz: impl Into<Option<u32>>,
     ) -> ClientBuilder {
-        self.config.http2_initial_connection_window_size = sz.into();
+        let sz = sz.into();
+        if sz.map_or(false, |sz| sz > HTTP2_MAX_WINDOW_SIZE) {
+            self.config.error = Some(crate::error::builder(
+                "HTTP2 connection window size exceeds 2^31-1",
+            ));
+        }
+        self.config.http2_initial_connection_window_size = sz;
         self
     }
 
@@ -1189,10 +1774,60 @@ impl ClientBuilder {
     /// Sets the maximum frame size to use for HTTP2.
     ///
     /// Default is currently 16,384 but may change internally to optimize for common uses.
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if `sz` is not between 16,384 and
+    /// 16,777,215, the range allowed by RFC 9113.
     #[cfg(feature = "http2")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
     pub fn http2_max_frame_size(mut self, sz: impl Into<Option<u32>>) -> ClientBuilder {
-        self.config.http2_max_frame_size = sz.into();
+        let sz = sz.into();
+        if sz.map_or(false, |sz| !HTTP2_FRAME_SIZE_RANGE.contains(&sz)) {
+            self.config.error = Some(crate::error::builder(
+                "HTTP2 max frame size must be between 16,384 and 16,777,215",
+            ));
+        }
+        self.config.http2_max_frame_size = sz;
+        self
+    }
+
+    /// Sets the `SETTINGS_MAX_HEADER_LIST_SIZE` option for HTTP2, the
+    /// maximum size of the headers of a response, in bytes.
+    ///
+    /// It is counted as in RFC 9113, with 32 bytes of overhead per header.
+    /// A response with larger headers fails with a request error. Values
+    /// larger than `u32::MAX` are sent as `u32::MAX`.
+    ///
+    /// Default is 16,384 (16KB), the default of hyper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_max_header_list_size(mut self, max: usize) -> ClientBuilder {
+        assert!(max > 0, "HTTP2 max header list size must be at least 1");
+        self.config.http2_max_header_list_size = Some(u32::try_from(max).unwrap_or(u32::MAX));
+        self
+    }
+
+    /// Sets the maximum write buffer size for each HTTP2 stream.
+    ///
+    /// Default is currently 1MB, but may change.
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if `max` is larger than `u32::MAX`.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_max_send_buf_size(mut self, max: usize) -> ClientBuilder {
+        if u32::try_from(max).is_err() {
+            self.config.error = Some(crate::error::builder(
+                "HTTP2 max send buffer size exceeds u32::MAX",
+            ));
+        }
+        self.config.http2_max_send_buf_size = Some(max);
         self
     }
 
@@ -1247,6 +1882,10 @@ impl ClientBuilder {
 
     /// Bind to a local IP Address.
     ///
+    /// Connections are only made to remote addresses of the same family,
+    /// IPv4 or IPv6, as `addr`. Requests to a host with none fail with a
+    /// connect error saying so.
+    ///
     /// # Example
     ///
     /// ```
@@ -1261,29 +1900,100 @@ impl ClientBuilder {
         T: Into<Option<IpAddr>>,
     {
         self.config.local_address = addr.into();
+        self.config.local_addresses = (None, None);
         self
     }
 
-    /// Bind to an interface by `SO_BINDTODEVICE`.
+    /// Bind to a local IPv4 address, an IPv6 address, or both, using the
+    /// one of the same family as the address being connected to.
+    ///
+    /// Connections to a family without an address given here bind to the
+    /// unspecified address of that family, leaving the choice to the OS.
+    /// This replaces any address set with `local_address`.
     ///
     /// # Example
     ///
     /// ```
-    /// let interface = "lo";
+    /// use std::net::{Ipv4Addr, Ipv6Addr};
     /// let client = reqwest::Client::builder()
-    ///     .interface(interface)
+    ///     .local_addresses(
+    ///         Some(Ipv4Addr::new(12, 4, 1, 8)),
+    ///         Some(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+    ///     )
     ///     .build().unwrap();
     /// ```
-    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
-    pub fn interface(mut self, interface: &str) -> ClientBuilder {
-        self.config.interface = Some(interface.to_string());
+    pub fn local_addresses(
+        mut self,
+        ipv4: Option<Ipv4Addr>,
+        ipv6: Option<Ipv6Addr>,
+    ) -> ClientBuilder {
+        self.config.local_addresses = (ipv4, ipv6);
+        self.config.local_address = None;
         self
     }
 
-    /// Set that all sockets have `SO_KEEPALIVE` set with the supplied duration.
+    /// Bind to an interface by `SO_BINDTODEVICE`.
     ///
-    /// If `None`, the option will not be set.
-    pub fn tcp_keepalive<D>(mut self, val: D) -> ClientBuilder
+    /// Every new socket is bound to the network interface `interface`, such
+    /// as `eth1`, so connections only go through it, whatever addresses it
+    /// has.
+    ///
+    /// This requires elevated privileges: `CAP_NET_RAW` on Linux before
+    /// 5.7, and on later kernels too for a socket that was already bound.
+    /// If binding fails, because the interface doesn't exist or for lack of
+    /// privileges, the connection fails with an error for which
+    /// `Error::is_connect` is true; it is never made without the binding.
+    /// Building the `Client` logs a warning if no such interface exists.
+    ///
+    /// Only available on Android, Fuchsia and Linux.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let interface = "lo";
+    /// let client = reqwest::Client::builder()
+    ///     .interface(interface)
+    ///     .build().unwrap();
+    /// ```
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    pub fn interface(mut self, interface: impl Into<String>) -> ClientBuilder {
+        self.config.interface = Some(interface.into());
+        self
+    }
+
+    /// Send all requests through the Unix domain socket at `path`.
+    ///
+    /// Every connection is made to the socket, whatever the host of the
+    /// request URL, which is only used for the `Host` header. The path and
+    /// query of the URL are sent as usual. Proxies are not used, and `https`
+    /// URLs fail, since TLS isn't supported over the socket.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .unix_socket("/var/run/docker.sock")
+    ///     .build()?;
+    ///
+    /// let res = client.get("http://localhost/version").send().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// This requires the optional `unix-socket` feature to be enabled, and is
+    /// only available on Unix.
+    #[cfg(all(unix, feature = "unix-socket"))]
+    #[cfg_attr(docsrs, doc(cfg(all(unix, feature = "unix-socket"))))]
+    pub fn unix_socket<P: AsRef<std::path::Path>>(mut self, path: P) -> ClientBuilder {
+        self.config.unix_socket = Some(Arc::from(path.as_ref()));
+        self
+    }
+
+    /// Set that all sockets have `SO_KEEPALIVE` set with the supplied duration.
+    ///
+    /// If `None`, the option will not be set.
+    pub fn tcp_keepalive<D>(mut self, val: D) -> ClientBuilder
     where
         D: Into<Option<Duration>>,
     {
@@ -1291,12 +2001,86 @@ impl ClientBuilder {
         self
     }
 
+    /// Set the time between TCP keepalive probes, once the peer stopped
+    /// answering.
+    ///
+    /// With `tcp_keepalive_retries`, this decides how soon a dead connection
+    /// is detected. It is applied to every new socket, like `tcp_keepalive`,
+    /// and enables keepalive if that wasn't set.
+    ///
+    /// This sets `TCP_KEEPINTVL` on Linux, Android, Fuchsia, macOS, iOS and
+    /// the BSDs, and the probe interval on Windows. Other platforms ignore
+    /// it.
+    ///
+    /// If `None`, the system default is used.
+    pub fn tcp_keepalive_interval<D>(mut self, val: D) -> ClientBuilder
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.config.tcp_keepalive_interval = val.into();
+        self
+    }
+
+    /// Set how many TCP keepalive probes are sent without an answer before
+    /// the connection is dropped.
+    ///
+    /// It is applied to every new socket, like `tcp_keepalive`, and enables
+    /// keepalive if that wasn't set.
+    ///
+    /// This sets `TCP_KEEPCNT` on Linux, Android, Fuchsia, macOS, iOS and
+    /// the BSDs. Windows always sends 10 probes, and it and other platforms
+    /// ignore this.
+    ///
+    /// If `None`, the system default is used.
+    pub fn tcp_keepalive_retries<C>(mut self, retries: C) -> ClientBuilder
+    where
+        C: Into<Option<u32>>,
+    {
+        self.config.tcp_keepalive_retries = retries.into();
+        self
+    }
+
+    /// Set the size of the send buffer of every new socket, `SO_SNDBUF`.
+    ///
+    /// The OS may round the size, or cap it to a system limit such as
+    /// `net.core.wmem_max` on Linux, which isn't an error. Failing to set
+    /// it is logged, and the connection goes on with the default size.
+    ///
+    /// If `None`, the system default is used.
+    pub fn tcp_send_buffer_size<S>(mut self, size: S) -> ClientBuilder
+    where
+        S: Into<Option<usize>>,
+    {
+        self.config.tcp_send_buffer_size = size.into();
+        self
+    }
+
+    /// Set the size of the receive buffer of every new socket, `SO_RCVBUF`.
+    ///
+    /// A larger buffer lets a single connection keep a fast link with a
+    /// long round trip busy. The OS may round the size, or cap it to a
+    /// system limit such as `net.core.rmem_max` on Linux, which isn't an
+    /// error. Failing to set it is logged, and the connection goes on with
+    /// the default size.
+    ///
+    /// If `None`, the system default is used.
+    pub fn tcp_recv_buffer_size<S>(mut self, size: S) -> ClientBuilder
+    where
+        S: Into<Option<usize>>,
+    {
+        self.config.tcp_recv_buffer_size = size.into();
+        self
+    }
+
     // TLS options
 
     /// Add a custom root certificate.
     ///
     /// This can be used to connect to a server that has a self-signed
-    /// certificate for example.
+    /// certificate for example. The certificate can be read from PEM with
+    /// `Certificate::from_pem`, or from binary DER, as exported from the
+    /// Windows certificate store, with `Certificate::from_der`. Both work
+    /// with every TLS backend.
     ///
     /// # Optional
     ///
@@ -1391,6 +2175,28 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the identity to be used for client certificate authentication
+    /// with one host, instead of the one set with `identity`.
+    ///
+    /// The host is matched against the host of each connection, ignoring
+    /// case and the port. Connections to other hosts use the identity set
+    /// with `identity`, or none.
+    ///
+    /// # Optional
+    ///
+    /// This requires the rustls TLS backend, so building the client fails
+    /// if another one is used.
+    #[cfg(feature = "__rustls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+    pub fn identity_for_host(mut self, host: &str, identity: Identity) -> ClientBuilder {
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+        self.config.host_identities.insert(host, identity);
+        self
+    }
+
     /// Controls the use of hostname verification.
     ///
     /// Defaults to `false`.
@@ -1445,6 +2251,50 @@ impl ClientBuilder {
         self
     }
 
+    /// Disables certificate validation for the hosts matching
+    /// `host_pattern`, only.
+    ///
+    /// The pattern is either a host name, matched exactly, or a wildcard
+    /// like `*.example.com`, matching any subdomain of `example.com` but
+    /// not `example.com` itself. The certificates of all other hosts are
+    /// still validated. Calling this again adds another pattern.
+    ///
+    /// The pattern is matched against the name the certificate is checked
+    /// for, which is the server name set with `tls_sni_override` if any.
+    ///
+    /// # Warning
+    ///
+    /// You should think very carefully before using this method. If
+    /// invalid certificates are trusted, *any* certificate for the
+    /// matching hosts will be trusted for use, so anybody able to
+    /// intercept connections to them can impersonate them. This should
+    /// only be used as a last resort.
+    ///
+    /// # Errors
+    ///
+    /// Building the client fails if it uses a TLS backend configured with
+    /// `use_preconfigured_tls`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
+    /// feature to be enabled.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn danger_accept_invalid_certs_for(mut self, host_pattern: &str) -> ClientBuilder {
+        self.config
+            .insecure_hosts
+            .push(host_pattern.trim_end_matches('.').to_ascii_lowercase());
+        self
+    }
+
     /// Controls the use of TLS server name indication.
     ///
     /// Defaults to `true`.
@@ -1467,6 +2317,94 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the server name to use with TLS when connecting to `host`,
+    /// instead of `host` itself.
+    ///
+    /// The name is sent with server name indication, and the server's
+    /// certificate is verified against it, rather than against the host of
+    /// the URL. The host is matched ignoring case and the port. This is
+    /// useful to reach a server by address or through an internal name,
+    /// while it presents the certificate of its public name. Used with
+    /// `RequestBuilder::host_header`, it lets the SNI follow either the URL
+    /// or the overridden `Host` header.
+    ///
+    /// `tls_sni(false)` still disables sending the name.
+    ///
+    /// # Warning
+    ///
+    /// The certificate is only checked to be valid for `server_name`, so
+    /// the connection is only as trustworthy as the route to `host`.
+    /// Connections are pooled by the URL host, so a connection made with an
+    /// override is never reused for a host without one.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
+    /// feature to be enabled.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_sni_override(mut self, host: &str, server_name: &str) -> ClientBuilder {
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+        self.config
+            .sni_overrides
+            .insert(host, server_name.to_owned());
+        self
+    }
+
+    /// Pins the public key of the certificates of `host`.
+    ///
+    /// `spki_sha256` is the SHA-256 of the DER encoded
+    /// `SubjectPublicKeyInfo` of the leaf certificate, the `pin-sha256` of
+    /// HPKP. Once the certificate was validated as usual, connections to
+    /// `host` fail unless its public key matches one of the pins of `host`.
+    /// Calling this again for the same host adds a pin, so a backup key
+    /// can be accepted before a rotation. The host is matched ignoring case
+    /// and the port, and other hosts aren't affected.
+    ///
+    /// The pin of a certificate can be computed with:
+    ///
+    /// ```text
+    /// openssl x509 -in cert.pem -pubkey -noout \
+    ///     | openssl pkey -pubin -outform der \
+    ///     | openssl dgst -sha256
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
+    /// feature to be enabled.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn add_pinned_spki_sha256(mut self, host: &str, spki_sha256: [u8; 32]) -> ClientBuilder {
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+        self.config
+            .spki_pins
+            .entry(host)
+            .or_default()
+            .push(spki_sha256);
+        self
+    }
+
     /// Set the minimum required TLS version for connections.
     ///
     /// By default the TLS backend's own default is used.
@@ -1608,6 +2546,9 @@ impl ClientBuilder {
 
     /// Add TLS information as `TlsInfo` extension to responses.
     ///
+    /// It can be read with `Response::tls_info`. This is off by default,
+    /// since it copies the peer certificate for every connection.
+    ///
     /// # Optional
     ///
     /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
@@ -1628,6 +2569,10 @@ impl ClientBuilder {
 
     /// Restrict the Client to be used with HTTPS only requests.
     ///
+    /// A request to a URL that isn't `https` fails before anything is sent,
+    /// and so does a redirect to one, whatever the redirect policy. Either
+    /// error returns `true` for `Error::is_not_https`.
+    ///
     /// Defaults to false.
     pub fn https_only(mut self, enabled: bool) -> ClientBuilder {
         self.config.https_only = enabled;
@@ -1737,6 +2682,233 @@ impl ClientBuilder {
         self
     }
 
+    /// Adds a middleware to run around every request this client sends.
+    ///
+    /// Middleware run in the order they are added, so the first one added
+    /// sees the request first and the response last. Clones of the built
+    /// `Client` share the same middleware.
+    ///
+    /// See the [`middleware`](crate::middleware) module for an example.
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> ClientBuilder {
+        self.config.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Adds a middleware to run around every hop of the requests this
+    /// client sends.
+    ///
+    /// Unlike those added with `with_middleware`, these run inside the
+    /// redirect loop, once for each request actually sent, including every
+    /// redirect followed and every retry. They see the request as it goes
+    /// out, after default headers, cookies and the signature were added,
+    /// so a change to a signed part of it invalidates the signature. The
+    /// response they see is the one to that hop, not decompressed yet, and
+    /// calling `next.run` sends the request without following redirects.
+    ///
+    /// They run in the order they are added, inside all middleware added
+    /// with `with_middleware`. Requests sent over HTTP/3 skip them.
+    pub fn with_hop_middleware<M: Middleware + 'static>(mut self, middleware: M) -> ClientBuilder {
+        self.config.hop_middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Shares one request between identical requests sent at the same time.
+    ///
+    /// While a request is in flight, others with the same method, URL and
+    /// the headers given by `single_flight_headers` wait for it, instead of
+    /// being sent. Its response body is read into memory, and each of them
+    /// gets a `Response` with the same status, headers and a copy of the
+    /// body. A request sent after the response arrived is sent again.
+    ///
+    /// Only `GET`, `HEAD` and `OPTIONS` requests without a body are shared.
+    /// Requests are sent on their own, even if identical, if the shared
+    /// response has a body larger than `single_flight_max_body`, or if the
+    /// shared request fails, is dropped or has an error reading its body.
+    ///
+    /// The shared request goes through the middleware once, and those of
+    /// the requests waiting for it aren't run. Per request settings, like
+    /// timeouts, only apply to the request sent.
+    ///
+    /// Default is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder().single_flight(true).build()?;
+    ///
+    /// // the same request is sent once
+    /// let (a, b) = tokio::join!(
+    ///     client.get("https://hyper.rs/").send(),
+    ///     client.get("https://hyper.rs/").send(),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn single_flight(mut self, enabled: bool) -> ClientBuilder {
+        self.config.single_flight.enabled = enabled;
+        self
+    }
+
+    /// Sets the request headers that must be the same for requests to be
+    /// shared with `single_flight`, replacing the default ones.
+    ///
+    /// The values of each header, in order, are part of what makes requests
+    /// identical, so requests with different credentials never share a
+    /// response. Default headers of the client, and those added by
+    /// middleware, aren't part of it.
+    ///
+    /// Default is `Accept`, `Accept-Language`, `Authorization`, `Cookie` and
+    /// `Range`.
+    pub fn single_flight_headers<I>(mut self, headers: I) -> ClientBuilder
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        self.config.single_flight.headers = headers.into_iter().collect();
+        self
+    }
+
+    /// Sets the largest response body shared with `single_flight`, in bytes.
+    ///
+    /// A larger response is only given to the request that was sent, and
+    /// those waiting for it are sent on their own.
+    ///
+    /// Default is 1MB.
+    pub fn single_flight_max_body(mut self, max: usize) -> ClientBuilder {
+        self.config.single_flight.max_body = max;
+        self
+    }
+
+    /// Sets how requests are found identical with `single_flight`, instead
+    /// of by method, URL and headers.
+    ///
+    /// Requests with the same key are shared, and one for which `key`
+    /// returns `None` is sent on its own. It is only called for requests
+    /// that can be shared, being `GET`, `HEAD` or `OPTIONS` without a body.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// // requests to the same path are shared, whatever their query
+    /// let client = reqwest::Client::builder()
+    ///     .single_flight(true)
+    ///     .single_flight_key(|req| Some(req.url().path().to_owned()))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn single_flight_key<F>(mut self, key: F) -> ClientBuilder
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        self.config.single_flight.key = Some(Arc::new(key));
+        self
+    }
+
+    /// Records every request this client sends, with its response, to a
+    /// cassette file at `path`.
+    ///
+    /// The file is written again after each response is received, whose
+    /// body is read whole before it is returned. Recording runs as the
+    /// outermost middleware, so each request is recorded once, as built,
+    /// with its final response. Replaces any `replay_from`.
+    ///
+    /// See the [`cassette`](crate::cassette) module for details.
+    #[cfg(feature = "cassette")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cassette")))]
+    pub fn record_to(mut self, path: impl Into<std::path::PathBuf>) -> ClientBuilder {
+        self.config.cassette = Some(crate::cassette::Mode::Record(path.into()));
+        self
+    }
+
+    /// Answers every request this client sends with a response recorded in
+    /// the cassette file at `path`, without sending it.
+    ///
+    /// A request matching no recorded one fails with a request error. The
+    /// file is read by `build`, which fails if it is missing or invalid.
+    /// Replaces any `record_to`.
+    ///
+    /// See the [`cassette`](crate::cassette) module for details.
+    #[cfg(feature = "cassette")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cassette")))]
+    pub fn replay_from(mut self, path: impl Into<std::path::PathBuf>) -> ClientBuilder {
+        self.config.cassette = Some(crate::cassette::Mode::Replay(path.into()));
+        self
+    }
+
+    /// Sets which parts of requests must match recorded ones to be
+    /// replayed with `replay_from`.
+    ///
+    /// Default is the method and URL.
+    #[cfg(feature = "cassette")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cassette")))]
+    pub fn cassette_match(mut self, matching: crate::cassette::Match) -> ClientBuilder {
+        self.config.cassette_match = matching;
+        self
+    }
+
+    /// Calls back with every request sent and every response received,
+    /// as they go over the wire.
+    ///
+    /// The callback sees each hop, after redirects were resolved, inside
+    /// all other middleware. Credentials are redacted by default, and the
+    /// start of bodies can be captured without consuming them. Headers
+    /// added by the connection itself, like `Host`, aren't included, and
+    /// requests sent over HTTP/3 are skipped.
+    ///
+    /// See the [`wire`](crate::wire) module for an example.
+    pub fn wire_debug(mut self, config: WireDebugConfig) -> ClientBuilder {
+        self.config.wire_debug = Some(config);
+        self
+    }
+
+    /// Sets a policy to send requests again when they fail with an error.
+    ///
+    /// The policy runs as a middleware, at this point of the chain: those
+    /// added with `with_middleware` before this see each request once,
+    /// and those added after see every attempt. Each attempt gets its own
+    /// `timeout`. Requests with a streaming body are sent once, unless
+    /// `RequestBuilder::retry_body_buffer_limit` lets it be buffered.
+    ///
+    /// This is independent of any `retry::Policy` set on a request, which
+    /// retries inside each attempt.
+    ///
+    /// See the [`retry`](crate::retry) module for an example.
+    pub fn with_retry<P: RetryPolicy + 'static>(mut self, policy: P) -> ClientBuilder {
+        self.config
+            .middleware
+            .push(Arc::new(crate::retry::Retry(Arc::new(policy))));
+        self
+    }
+
+    /// Sets a signer to sign every request this client sends.
+    ///
+    /// The signer is called after all middleware have run, once the
+    /// request's headers and body are final, and again for every redirect
+    /// followed and every retry.
+    ///
+    /// A signer needs the whole body, so requests with a streaming body
+    /// fail with a builder error, unless `buffer_signed_bodies` is enabled.
+    /// Request bodies compressed by the client are streamed as well.
+    ///
+    /// See the [`sign`](crate::sign) module for an example.
+    pub fn sign_with(mut self, signer: Arc<dyn RequestSigner>) -> ClientBuilder {
+        self.config.signer = Some(signer);
+        self
+    }
+
+    /// Set whether streaming request bodies are read into memory so they
+    /// can be signed.
+    ///
+    /// This only has an effect if a signer was set with `sign_with`.
+    ///
+    /// Default is `false`.
+    pub fn buffer_signed_bodies(mut self, enabled: bool) -> ClientBuilder {
+        self.config.buffer_signed_bodies = enabled;
+        self
+    }
+
     /// Whether to send data on the first flight ("early data") in TLS 1.3 handshakes
     /// for HTTP/3 connections.
     ///
@@ -1914,25 +3086,289 @@ impl Client {
         self.execute_request(request)
     }
 
+    /// Executes a `Request`, also returning the URLs it was redirected to.
+    ///
+    /// Redirects are followed as with `execute`, and the URLs are returned
+    /// in the order they were followed, resolved against the URL that
+    /// redirected to them. The last one is the final URL of the `Response`.
+    /// It is empty if no redirect was followed. The bodies of the redirect
+    /// responses aren't read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::new();
+    /// let req = client.get("http://httpbin.org/redirect/2").build()?;
+    ///
+    /// let (res, redirects) = client.execute_with_history(req).await?;
+    /// for url in &redirects {
+    ///     println!("redirected to {url}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails in the same cases as `execute`.
+    pub fn execute_with_history(
+        &self,
+        request: Request,
+    ) -> impl Future<Output = Result<(Response, Vec<Url>), crate::Error>> {
+        let pending = self.execute_request(request);
+        async move {
+            let res = pending.await?;
+            let redirects = redirect::redirect_targets(res.redirect_history(), res.url());
+            Ok((res, redirects))
+        }
+    }
+
+    /// Executes a batch of `Request`s, at most `max_concurrent` at a time.
+    ///
+    /// Each request goes through the same timeout, redirect and cookie
+    /// handling as [`Client::execute`]. A failed request is yielded as an
+    /// `Err` and doesn't stop the rest of the batch.
+    ///
+    /// Responses are yielded in the order they complete, not the order the
+    /// requests were given in. A `max_concurrent` of `0` is treated as `1`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::new();
+    /// let requests = vec![
+    ///     client.get("http://httpbin.org/ip").build()?,
+    ///     client.get("http://httpbin.org/uuid").build()?,
+    /// ];
+    ///
+    /// let mut responses = client.execute_all(requests, 4);
+    /// while let Some(res) = responses.next().await {
+    ///     println!("{}", res?.status());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_all(
+        &self,
+        requests: Vec<Request>,
+        max_concurrent: usize,
+    ) -> impl futures_core::Stream<Item = Result<Response, crate::Error>> {
+        use futures_util::StreamExt;
+
+        let client = self.clone();
+        futures_util::stream::iter(requests)
+            .map(move |req| client.execute_request(req))
+            .buffer_unordered(max_concurrent.max(1))
+    }
+
+    /// Returns the connections of this `Client` and its clones, by host.
+    ///
+    /// The counts are kept as connections are opened, used and closed, so
+    /// this is cheap enough to call for every scrape of a metrics gauge.
+    /// A connection closed by the server is only counted as closed once the
+    /// pool notices it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let client = reqwest::Client::new();
+    /// for host in client.pool_stats().hosts() {
+    ///     println!(
+    ///         "{}: {} idle, {} in use, {} pending",
+    ///         host.host(),
+    ///         host.idle(),
+    ///         host.in_use(),
+    ///         host.pending()
+    ///     );
+    /// }
+    /// ```
+    pub fn pool_stats(&self) -> PoolStats {
+        self.inner.pool.snapshot()
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn pool_counters(&self) -> PoolCounters {
+        self.inner.pool.clone()
+    }
+
+    /// Opens a tunnel to the host and port of `url`, with a `CONNECT`
+    /// request to the proxy intercepting it.
+    ///
+    /// The tunnel carries whatever protocol the caller speaks over it, such
+    /// as a WebSocket handshake; no TLS is started with the host. The scheme
+    /// of `url` gives the default port and the proxies that apply, `ws` and
+    /// `wss` being proxied as `http` and `https`. The proxy is connected to
+    /// as for a request, with its credentials and headers, and within the
+    /// connect timeout. Bytes the proxy sent right after a `2xx` or `101`
+    /// response to the `CONNECT` are kept for the first read.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no HTTP proxy intercepts `url`, if the proxy can't be
+    /// reached, or if it refuses the tunnel.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// let client = reqwest::Client::builder()
+    ///     .proxy(reqwest::Proxy::all("http://proxy.example:3128")?)
+    ///     .build()?;
+    /// let mut tunnel = client.connect_tunnel("http://example.com:7000").await?;
+    /// tunnel.write_all(b"hello").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_tunnel<U: IntoUrl>(&self, url: U) -> crate::Result<Upgraded> {
+        let url = url.into_url()?;
+        let scheme = match url.scheme() {
+            "ws" => "http",
+            "wss" => "https",
+            scheme => scheme,
+        };
+        let dst = match (url.host_str(), url.port_or_known_default()) {
+            (Some(host), Some(port)) => format!("{scheme}://{host}:{port}").parse::<Uri>().ok(),
+            _ => None,
+        };
+        let dst = match dst {
+            Some(dst) => dst,
+            None => return Err(error::url_invalid_uri(url)),
+        };
+        self.inner
+            .connector
+            .clone()
+            .connect_tunnel(dst)
+            .await
+            .map(Upgraded::from)
+            .map_err(|e| error::upgrade(e).with_url(url))
+    }
+
+    pub(super) fn error_body_limit(&self) -> usize {
+        self.inner.error_body_limit
+    }
+
     pub(super) fn execute_request(&self, req: Request) -> Pending {
-        let (method, url, mut headers, body, timeout, version) = req.pieces();
+        let key = match self.inner.single_flight {
+            Some(ref flights) => flights.key(&req),
+            None => None,
+        };
+        if let Some(key) = key {
+            let client = self.clone();
+            return Pending {
+                inner: PendingInner::Middleware(Box::pin(async move {
+                    let flights = client.inner.single_flight.as_ref();
+                    let flights = flights.expect("single flight is enabled");
+                    flights
+                        .run(key, req, |req| client.execute_alone(req))
+                        .await
+                })),
+            };
+        }
+        self.execute_alone(req)
+    }
+
+    /// Executes the request, without sharing it with identical ones.
+    fn execute_alone(&self, req: Request) -> Pending {
+        if self.inner.middleware.is_empty() {
+            return self.dispatch_request(req);
+        }
+
+        let client = self.clone();
+        Pending {
+            inner: PendingInner::Middleware(Box::pin(async move {
+                Next::new(&client, &client.inner.middleware).run(req).await
+            })),
+        }
+    }
+
+    /// Sends a single hop of a request, as the end of the hop middleware.
+    pub(crate) fn send_hop(
+        &self,
+        req: Request,
+        extensions: Option<http::Extensions>,
+    ) -> impl Future<Output = crate::Result<Response>> {
+        let url = req.url().clone();
+        let req = http::Request::<Body>::try_from(req).map(|mut req| {
+            if let Some(extensions) = extensions {
+                req.extensions_mut().extend(extensions);
+            }
+            self.inner.hyper_for(req.uri()).request(req)
+        });
+        async move {
+            let res = req?
+                .await
+                .map_err(|e| error::request(e).with_url(url.clone()))?;
+            let res = res.map(super::body::incoming);
+            Ok(Response::new(res, url, Accepts::none(), None, None))
+        }
+    }
+
+    /// Sends the request, skipping any middleware.
+    pub(crate) fn dispatch_request(&self, mut req: Request) -> Pending {
+        if self.inner.signer.is_some() && self.inner.buffer_signed_bodies && self.streams_body(&req)
+        {
+            let client = self.clone();
+            return Pending {
+                inner: PendingInner::Middleware(Box::pin(async move {
+                    let req = client.buffer_body(req).await?;
+                    client.dispatch_request(req).await
+                })),
+            };
+        }
+
+        req.resolve_timeout();
+        let request_proxies = self.inner.request_proxies(&req);
+        let retry = req.retry_mut().take();
+        let bearer_token = req.bearer_token_mut().take();
+        let digest_auth = req.digest_auth_mut().take();
+        let accepts = if req.decompress() {
+            self.inner.accepts
+        } else {
+            Accepts::none()
+        };
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        let compress_body = req.compression_mut().take().or(self.inner.compress_body);
+        let expect_continue = req.expect_continue();
+        let (method, url, mut headers, body, timeout, connect_timeout, version) = req.pieces();
         if url.scheme() != "http" && url.scheme() != "https" {
             return Pending::new_err(error::url_bad_scheme(url));
         }
 
         // check if we're in https_only mode and check the scheme of the current URL
         if self.inner.https_only && url.scheme() != "https" {
-            return Pending::new_err(error::url_bad_scheme(url));
+            return Pending::new_err(error::url_not_https(url));
         }
 
-        // insert default headers in the request headers
-        // without overwriting already appended headers.
-        for (key, value) in &self.inner.headers {
-            if let Entry::Vacant(entry) = headers.entry(key) {
-                entry.insert(value.clone());
+        // insert default headers in the request headers, without
+        // overwriting any set on the request. A name set on the request
+        // replaces all of its default values, and a default with several
+        // values keeps them all.
+        for key in self.inner.headers.keys() {
+            if !headers.contains_key(key) {
+                for value in self.inner.headers.get_all(key) {
+                    headers.append(key, value.clone());
+                }
             }
         }
 
+        if let Some(ref token) = bearer_token {
+            if let Err(err) = set_bearer_token(&mut headers, token) {
+                return Pending::new_err(err.with_url(url));
+            }
+        }
+
+        let digest_auth = digest_auth.map(|credentials| {
+            let mut auth = digest_auth::Auth::new(credentials, &url);
+            auth.authorize(&self.inner.digest_nonces, &method, &url, &mut headers);
+            auth
+        });
+
         // Add cookies from the cookie store.
         #[cfg(feature = "cookies")]
         {
@@ -1943,7 +3379,7 @@ impl Client {
             }
         }
 
-        let accept_encoding = self.inner.accepts.as_str();
+        let accept_encoding = accepts.as_str();
 
         if let Some(accept_encoding) = accept_encoding {
             if !headers.contains_key(ACCEPT_ENCODING) && !headers.contains_key(RANGE) {
@@ -1956,6 +3392,15 @@ impl Client {
             _ => return Pending::new_err(error::url_invalid_uri(url)),
         };
 
+        // an empty body, or one that's already encoded, is sent as is
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        let body = match (compress_body, body) {
+            (Some(encoding), Some(body)) if should_compress(&body, &headers) => {
+                Some(compress(encoding, body, &mut headers))
+            }
+            (_, body) => body,
+        };
+
         let (reusable, body) = match body {
             Some(body) => {
                 let (reusable, body) = body.try_reuse();
@@ -1964,7 +3409,36 @@ impl Client {
             None => (None, Body::empty()),
         };
 
-        self.proxy_auth(&uri, &mut headers);
+        self.proxy_auth(&uri, request_proxies.as_ref(), &mut headers);
+
+        // informational responses only exist in HTTP/1.1
+        let expect_continue = expect_continue
+            && version < http::Version::HTTP_2
+            && !http_body::Body::is_end_stream(&body);
+        if expect_continue {
+            headers.insert(EXPECT, HeaderValue::from_static("100-continue"));
+        }
+
+        if self.inner.signer.is_some() {
+            let body = match reusable {
+                Some(Some(ref body)) => &body[..],
+                Some(None) => {
+                    return Pending::new_err(
+                        error::builder("a streaming request body can't be signed").with_url(url),
+                    );
+                }
+                None => &[][..],
+            };
+            self.inner.sign(&method, &url, &mut headers, body);
+        }
+
+        let (body, expect) = if expect_continue {
+            let (body, expect) =
+                expect::wait_for_continue(body, self.inner.expect_continue_timeout);
+            (body, Some(expect))
+        } else {
+            (body, None)
+        };
 
         let builder = hyper::Request::builder()
             .method(method.clone())
@@ -1981,7 +3455,10 @@ impl Client {
             _ => {
                 let mut req = builder.body(body).expect("valid request parts");
                 *req.headers_mut() = headers.clone();
-                ResponseFuture::Default(self.inner.hyper.request(req))
+                if let Some(ref expect) = expect {
+                    expect.register(&mut req);
+                }
+                self.inner.send(&url, req)
             }
         };
 
@@ -1998,18 +3475,71 @@ impl Client {
                 body: reusable,
 
                 urls: Vec::new(),
+                history: redirect::History::default(),
 
                 retry_count: 0,
 
+                retry,
+                retry_attempts: 0,
+                retry_backoff: None,
+
+                bearer_token,
+                digest_auth,
+
+                timer: self.inner.collect_timings.then(RequestTimer::new),
+
+                accepts,
                 client: self.inner.clone(),
 
+                connect_timeout,
+                request_proxies,
+                expect,
                 in_flight,
                 timeout,
             }),
         }
     }
 
-    fn proxy_auth(&self, dst: &Uri, headers: &mut HeaderMap) {
+    /// Whether the request's body would be sent as a stream.
+    fn streams_body(&self, req: &Request) -> bool {
+        let body = match req.body() {
+            Some(body) => body,
+            None => return false,
+        };
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        {
+            if req.compression().or(self.inner.compress_body).is_some()
+                && should_compress(body, req.headers())
+            {
+                return true;
+            }
+        }
+        body.as_bytes().is_none()
+    }
+
+    /// Reads the request's body into memory, compressing it first if it
+    /// would be, so it can be signed.
+    async fn buffer_body(&self, mut req: Request) -> crate::Result<Request> {
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        {
+            let compress_body = req.compression_mut().take().or(self.inner.compress_body);
+            if let Some(encoding) = compress_body {
+                match req.body_mut().take() {
+                    Some(body) if should_compress(&body, req.headers()) => {
+                        *req.body_mut() = Some(compress(encoding, body, req.headers_mut()));
+                    }
+                    body => *req.body_mut() = body,
+                }
+            }
+        }
+        if let Some(body) = req.body_mut().take() {
+            let bytes = http_body_util::BodyExt::collect(body).await?.to_bytes();
+            *req.body_mut() = Some(Body::reusable(bytes));
+        }
+        Ok(req)
+    }
+
+    fn proxy_auth(&self, dst: &Uri, chosen: Option<&RequestProxies>, headers: &mut HeaderMap) {
         if !self.inner.proxies_maybe_http_auth {
             return;
         }
@@ -2025,7 +3555,18 @@ impl Client {
             return;
         }
 
-        for proxy in self.inner.proxies.iter() {
+        for (index, proxy) in self.inner.proxies.iter().enumerate() {
+            if proxy.is_by_request() {
+                match chosen.and_then(|chosen| chosen.get(index)) {
+                    Some(scheme) => {
+                        if let Some(header) = scheme.maybe_http_auth() {
+                            headers.insert(PROXY_AUTHORIZATION, header.clone());
+                        }
+                        break;
+                    }
+                    None => continue,
+                }
+            }
             if proxy.is_match(dst) {
                 if let Some(header) = proxy.http_basic_auth(dst) {
                     headers.insert(PROXY_AUTHORIZATION, header);
@@ -2095,6 +3636,11 @@ impl Config {
 
         f.field("accepts", &self.accepts);
 
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        if let Some(encoding) = self.compress_body {
+            f.field("compress_body", &encoding);
+        }
+
         if !self.proxies.is_empty() {
             f.field("proxies", &self.proxies);
         }
@@ -2125,6 +3671,14 @@ impl Config {
             f.field("http1_allow_spaces_after_header_name_in_responses", &true);
         }
 
+        if let Some(ref max) = self.http1_max_headers {
+            f.field("http1_max_headers_count", max);
+        }
+
+        if let Some(ref max) = self.http1_max_header_size {
+            f.field("http1_max_header_size", max);
+        }
+
         if matches!(self.http_version_pref, HttpVersionPref::Http1) {
             f.field("http1_only", &true);
         }
@@ -2146,6 +3700,10 @@ impl Config {
             f.field("local_address", v);
         }
 
+        if self.local_addresses != (None, None) {
+            f.field("local_addresses", &self.local_addresses);
+        }
+
         #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
         if let Some(ref v) = self.interface {
             f.field("interface", v);
@@ -2168,6 +3726,10 @@ impl Config {
                 f.field("danger_accept_invalid_certs", &true);
             }
 
+            if !self.insecure_hosts.is_empty() {
+                f.field("danger_accept_invalid_certs_for", &self.insecure_hosts);
+            }
+
             if let Some(ref min_tls_version) = self.min_tls_version {
                 f.field("min_tls_version", min_tls_version);
             }
@@ -2178,6 +3740,14 @@ impl Config {
 
             f.field("tls_sni", &self.tls_sni);
 
+            if !self.sni_overrides.is_empty() {
+                f.field("sni_overrides", &self.sni_overrides);
+            }
+
+            if !self.spki_pins.is_empty() {
+                f.field("spki_pins", &self.spki_pins);
+            }
+
             f.field("tls_info", &self.tls_info);
         }
 
@@ -2190,6 +3760,39 @@ impl Config {
             f.field("dns_overrides", &self.dns_overrides);
         }
 
+        if !self.middleware.is_empty() {
+            f.field("middleware", &self.middleware.len());
+        }
+
+        if !self.hop_middleware.is_empty() {
+            f.field("hop_middleware", &self.hop_middleware.len());
+        }
+
+        #[cfg(feature = "cassette")]
+        if let Some(ref cassette) = self.cassette {
+            f.field("cassette", cassette);
+        }
+
+        if self.single_flight.enabled {
+            f.field("single_flight", &true);
+        }
+
+        if let Some(ref wire_debug) = self.wire_debug {
+            f.field("wire_debug", wire_debug);
+        }
+
+        if self.signer.is_some() {
+            f.field("signer", &true);
+        }
+
+        if self.on_new_connection.is_some() {
+            f.field("on_new_connection", &true);
+        }
+
+        if self.on_connection_reuse.is_some() {
+            f.field("on_connection_reuse", &true);
+        }
+
         #[cfg(feature = "http3")]
         {
             if self.tls_enable_early_data {
@@ -2205,17 +3808,93 @@ struct ClientRef {
     cookie_store: Option<Arc<dyn cookie::CookieStore>>,
     headers: HeaderMap,
     hyper: HyperClient,
+    hyper_per_host: HashMap<String, HyperClient>,
+    connector: Connector,
     #[cfg(feature = "http3")]
     h3_client: Option<H3Client>,
     redirect_policy: redirect::Policy,
     referer: bool,
     request_timeout: Option<Duration>,
+    expect_continue_timeout: Duration,
     proxies: Arc<Vec<Proxy>>,
     proxies_maybe_http_auth: bool,
+    proxies_by_request: bool,
     https_only: bool,
+    middleware: Vec<Arc<dyn Middleware>>,
+    hop_middleware: Vec<Arc<dyn Middleware>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    buffer_signed_bodies: bool,
+    collect_timings: bool,
+    on_connection_reuse: Option<ConnectionHook>,
+    error_body_limit: usize,
+    max_response_size: Option<u64>,
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    compress_body: Option<ContentEncoding>,
+    pool: PoolCounters,
+    digest_nonces: digest_auth::Nonces,
+    single_flight: Option<SingleFlight>,
 }
 
 impl ClientRef {
+    /// Sends a single hop of a request to `url`, through the hop
+    /// middleware if there are any.
+    fn send(self: &Arc<Self>, url: &Url, req: hyper::Request<Body>) -> ResponseFuture {
+        if self.hop_middleware.is_empty() {
+            return ResponseFuture::Default(self.hyper_for(req.uri()).request(req));
+        }
+
+        let client = Client {
+            inner: self.clone(),
+        };
+        let (parts, body) = req.into_parts();
+        let mut req = Request::new(parts.method, url.clone());
+        *req.headers_mut() = parts.headers;
+        *req.body_mut() = Some(body);
+        *req.version_mut() = parts.version;
+        let hop = Hop::new(parts.extensions);
+        ResponseFuture::Hop(Box::pin(async move {
+            let res = Next::hop(&client, &client.inner.hop_middleware, &hop)
+                .run(req)
+                .await?;
+            Ok(res.into_hop())
+        }))
+    }
+
+    /// The proxies chosen for `req` by those made with
+    /// `Proxy::custom_request`, if there are any.
+    fn request_proxies(&self, req: &Request) -> Option<RequestProxies> {
+        if !self.proxies_by_request {
+            return None;
+        }
+        Some(RequestProxies::choose(&self.proxies, req))
+    }
+
+    /// The client whose pool holds connections for `uri`.
+    fn hyper_for(&self, uri: &Uri) -> &HyperClient {
+        if self.hyper_per_host.is_empty() {
+            return &self.hyper;
+        }
+        let host = match uri.host() {
+            Some(host) => host,
+            None => return &self.hyper,
+        };
+        let port = match uri.port_u16() {
+            Some(port) => port,
+            None if uri.scheme() == Some(&Scheme::HTTPS) => 443,
+            None => 80,
+        };
+        self.hyper_per_host
+            .get(&format!("{}:{}", host.to_ascii_lowercase(), port))
+            .unwrap_or(&self.hyper)
+    }
+
+    /// Lets the signer, if there is one, sign the request about to be sent.
+    fn sign(&self, method: &Method, url: &Url, headers: &mut HeaderMap, body: &[u8]) {
+        if let Some(ref signer) = self.signer {
+            signer.sign(&mut RequestParts::new(method, url, headers, body));
+        }
+    }
+
     fn fmt_fields(&self, f: &mut fmt::DebugStruct<'_, '_>) {
         // Instead of deriving Debug, only print fields when their output
         // would provide relevant or interesting data.
@@ -2246,6 +3925,27 @@ impl ClientRef {
         if let Some(ref d) = self.request_timeout {
             f.field("timeout", d);
         }
+
+        if self.collect_timings {
+            f.field("collect_timings", &true);
+        }
+
+        if self.on_connection_reuse.is_some() {
+            f.field("on_connection_reuse", &true);
+        }
+
+        if self.signer.is_some() {
+            f.field("signer", &true);
+        }
+
+        if self.single_flight.is_some() {
+            f.field("single_flight", &true);
+        }
+
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        if let Some(encoding) = self.compress_body {
+            f.field("compress_body", &encoding);
+        }
     }
 }
 
@@ -2258,6 +3958,7 @@ pin_project! {
 
 enum PendingInner {
     Request(PendingRequest),
+    Middleware(crate::middleware::Handling<'static>),
     Error(Option<crate::Error>),
 }
 
@@ -2269,11 +3970,25 @@ pin_project! {
         body: Option<Option<Bytes>>,
 
         urls: Vec<Url>,
+        history: redirect::History,
 
         retry_count: usize,
 
+        retry: Option<retry::Policy>,
+        retry_attempts: usize,
+        retry_backoff: Option<Pin<Box<Sleep>>>,
+
+        bearer_token: Option<TokenFn>,
+        digest_auth: Option<digest_auth::Auth>,
+
+        timer: Option<RequestTimer>,
+
+        accepts: Accepts,
         client: Arc<ClientRef>,
 
+        connect_timeout: Option<Duration>,
+        request_proxies: Option<RequestProxies>,
+        expect: Option<ExpectContinue>,
         #[pin]
         in_flight: ResponseFuture,
         #[pin]
@@ -2283,6 +3998,7 @@ pin_project! {
 
 enum ResponseFuture {
     Default(HyperResponseFuture),
+    Hop(Pin<Box<dyn Future<Output = crate::Result<hyper::Response<ResponseBody>>> + Send>>),
     #[cfg(feature = "http3")]
     H3(H3ResponseFuture),
 }
@@ -2329,6 +4045,88 @@ impl PendingRequest {
         }
         self.retry_count += 1;
 
+        self.resend(body);
+        true
+    }
+
+    /// Checks the request's retry policy, and if it allows another attempt,
+    /// starts it after the policy's backoff.
+    fn retry_policy(
+        mut self: Pin<&mut Self>,
+        retryable: impl FnOnce(&retry::Policy) -> bool,
+    ) -> bool {
+        let delay = match self.retry {
+            Some(ref policy) if retryable(policy) => {
+                if self.retry_attempts >= policy.max_retries() {
+                    debug!("retry policy exhausted for '{}'", self.url);
+                    return false;
+                }
+                policy.backoff(self.retry_attempts)
+            }
+            _ => return false,
+        };
+
+        let body = match self.body {
+            Some(Some(ref body)) => Body::reusable(body.clone()),
+            Some(None) => {
+                log::warn!("not retrying '{}', body is not reusable", self.url);
+                return false;
+            }
+            None => Body::empty(),
+        };
+
+        self.retry_attempts += 1;
+        debug!(
+            "retrying '{}' in {delay:?} (retry {})",
+            self.url, self.retry_attempts
+        );
+
+        self.as_mut().resend(body);
+        *self.as_mut().project().retry_backoff = Some(Box::pin(tokio::time::sleep(delay)));
+        true
+    }
+
+    /// Sends the request again to answer the digest challenge of a `401`
+    /// response, if it has digest credentials for it.
+    fn digest_challenged(mut self: Pin<&mut Self>, headers: &HeaderMap) -> bool {
+        let body = match self.body {
+            Some(Some(ref body)) => Body::reusable(body.clone()),
+            Some(None) => return false,
+            None => Body::empty(),
+        };
+
+        let this = self.as_mut().project();
+        let challenged = match this.digest_auth {
+            Some(auth) => auth.challenged(&this.client.digest_nonces, this.url, headers),
+            None => false,
+        };
+        if !challenged {
+            return false;
+        }
+        debug!("answering the digest challenge of '{}'", self.url);
+
+        self.resend(body);
+        true
+    }
+
+    /// Replaces the in-flight request with a new one to the current `url`.
+    fn resend(mut self: Pin<&mut Self>, body: Body) {
+        if let Some(timer) = self.as_mut().project().timer {
+            timer.restart_hop();
+        }
+
+        let this = self.as_mut().project();
+        refresh_bearer_token(this.bearer_token.as_ref(), this.headers);
+        if let Some(auth) = this.digest_auth {
+            auth.authorize(&this.client.digest_nonces, this.method, this.url, this.headers);
+        }
+        let body_bytes = match this.body {
+            Some(Some(body)) => &body[..],
+            _ => &[][..],
+        };
+        this.client
+            .sign(this.method, this.url, this.headers, body_bytes);
+
         // If it parsed once, it should parse again
         let uri = try_uri(&self.url).expect("URL was already validated as URI");
 
@@ -2356,11 +4154,19 @@ impl PendingRequest {
                     .body(body)
                     .expect("valid request parts");
                 *req.headers_mut() = self.headers.clone();
-                ResponseFuture::Default(self.client.hyper.request(req))
+                self.client.send(&self.url, req)
             }
         };
+    }
+}
 
-        true
+/// Asks for a new token before sending the request again. If the new one is
+/// invalid, the previous token is sent instead.
+fn refresh_bearer_token(token: Option<&TokenFn>, headers: &mut HeaderMap) {
+    if let Some(token) = token {
+        if let Err(err) = set_bearer_token(headers, token) {
+            log::warn!("keeping previous bearer token: {err}");
+        }
     }
 }
 
@@ -2419,6 +4225,7 @@ impl Future for Pending {
         let inner = self.inner();
         match inner.get_mut() {
             PendingInner::Request(ref mut req) => Pin::new(req).poll(cx),
+            PendingInner::Middleware(ref mut fut) => fut.as_mut().poll(cx),
             PendingInner::Error(ref mut err) => Poll::Ready(Err(err
                 .take()
                 .expect("Pending error polled more than once"))),
@@ -2438,18 +4245,56 @@ impl Future for PendingRequest {
             }
         }
 
+        // The connector reads a per-request connect timeout from here when
+        // hyper asks it for a new connection.
+        let _connect_timeout = ConnectTimeoutGuard::enter(self.connect_timeout);
+        let request_proxies = RequestProxiesGuard::enter(self.request_proxies.clone());
+
         loop {
-            let res = match self.as_mut().in_flight().get_mut() {
+            if let Some(backoff) = self.as_mut().project().retry_backoff.as_mut() {
+                futures_core::ready!(backoff.as_mut().poll(cx));
+                *self.as_mut().project().retry_backoff = None;
+            }
+
+            if let Some(timer) = self.as_mut().project().timer {
+                timer.start_hop();
+            }
+
+            let mut res = match self.as_mut().in_flight().get_mut() {
                 ResponseFuture::Default(r) => match Pin::new(r).poll(cx) {
                     Poll::Ready(Err(e)) => {
                         #[cfg(feature = "http2")]
                         if self.as_mut().retry_error(&e) {
                             continue;
                         }
+                        if e.is_connect()
+                            && self.as_mut().retry_policy(|p| p.retries_connect_errors())
+                        {
+                            continue;
+                        }
                         return Poll::Ready(Err(
                             crate::error::request(e).with_url(self.url.clone())
                         ));
                     }
+                    Poll::Ready(Ok(res)) => res.map(super::body::incoming),
+                    Poll::Pending => return Poll::Pending,
+                },
+                ResponseFuture::Hop(r) => match r.as_mut().poll(cx) {
+                    Poll::Ready(Err(e)) => {
+                        // the error of the connection pool is its source
+                        #[cfg(feature = "http2")]
+                        if let Some(source) = std::error::Error::source(&e) {
+                            if self.as_mut().retry_error(source) {
+                                continue;
+                            }
+                        }
+                        if e.is_connect()
+                            && self.as_mut().retry_policy(|p| p.retries_connect_errors())
+                        {
+                            continue;
+                        }
+                        return Poll::Ready(Err(e));
+                    }
                     Poll::Ready(Ok(res)) => res,
                     Poll::Pending => return Poll::Pending,
                 },
@@ -2463,11 +4308,43 @@ impl Future for PendingRequest {
                             crate::error::request(e).with_url(self.url.clone())
                         ));
                     }
-                    Poll::Ready(Ok(res)) => res,
+                    Poll::Ready(Ok(res)) => res.map(super::body::incoming),
                     Poll::Pending => return Poll::Pending,
                 },
             };
 
+            // Every hop counts as a use of its connection, even if it is
+            // redirected or retried and never reaches the caller.
+            let mut reused = false;
+            if let Some(conn) = res.extensions_mut().remove::<ConnectionUse>() {
+                reused = conn.mark_used();
+                res.extensions_mut().insert(ConnectionReused(reused));
+            }
+            if let Some(trace) = res.extensions_mut().remove::<trace::PoolUse>() {
+                trace.record(reused, res.version());
+            }
+            if let Some(activity) = res.extensions_mut().remove::<ConnectionActivity>() {
+                res = res.map(|body| activity.track(body));
+            }
+            if let Some(info) = res.extensions_mut().remove::<ConnectionInfo>() {
+                if let (true, Some(hook)) = (reused, &self.client.on_connection_reuse) {
+                    hook(info);
+                }
+            }
+            if let Some(timer) = self.as_mut().project().timer {
+                timer.finish_hop(res.extensions_mut(), reused);
+            }
+
+            // a body that waited for `100 Continue` is only sent once, and
+            // not at all if this response came first
+            let upload = match self.as_mut().project().expect.take() {
+                Some(expect) => {
+                    self.as_mut().headers().remove(EXPECT);
+                    expect.on_response(res.status())
+                }
+                None => None,
+            };
+
             #[cfg(feature = "cookies")]
             {
                 if let Some(ref cookie_store) = self.client.cookie_store {
@@ -2478,6 +4355,15 @@ impl Future for PendingRequest {
                     }
                 }
             }
+            let status = res.status();
+            if self.as_mut().retry_policy(|p| p.retries_status(status)) {
+                continue;
+            }
+            if status == StatusCode::UNAUTHORIZED
+                && self.as_mut().digest_challenged(res.headers())
+            {
+                continue;
+            }
             let should_redirect = match res.status() {
                 StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER => {
                     self.body = None;
@@ -2532,21 +4418,28 @@ impl Future for PendingRequest {
                     loc
                 });
                 if let Some(loc) = loc {
-                    if self.client.referer {
-                        if let Some(referer) = make_referer(&loc, &self.url) {
-                            self.headers.insert(REFERER, referer);
-                        }
-                    }
                     let url = self.url.clone();
                     self.as_mut().urls().push(url);
                     let action = self
                         .client
                         .redirect_policy
-                        .check(res.status(), &loc, &self.urls);
+                        .check(res.status(), &self.method, &loc, &self.urls);
+                    // a rewritten redirect is followed like the original
+                    let loc = match action {
+                        redirect::ActionKind::FollowTo(ref url) => url.clone(),
+                        _ => loc,
+                    };
 
                     match action {
-                        redirect::ActionKind::Follow => {
+                        redirect::ActionKind::Follow | redirect::ActionKind::FollowTo(_) => {
                             debug!("redirecting '{}' to '{}'", self.url, loc);
+                            if self.client.referer {
+                                if let Some(referer) = make_referer(&loc, &self.url) {
+                                    self.headers.insert(REFERER, referer);
+                                }
+                            }
+                            let (url, status) = (self.url.clone(), res.status());
+                            self.as_mut().project().history.push(url, status);
 
                             if loc.scheme() != "http" && loc.scheme() != "https" {
                                 return Poll::Ready(Err(error::url_bad_scheme(loc)));
@@ -2554,7 +4447,7 @@ impl Future for PendingRequest {
 
                             if self.client.https_only && loc.scheme() != "https" {
                                 return Poll::Ready(Err(error::redirect(
-                                    error::url_bad_scheme(loc.clone()),
+                                    error::url_not_https(loc.clone()),
                                     loc,
                                 )));
                             }
@@ -2564,6 +4457,15 @@ impl Future for PendingRequest {
                                 std::mem::replace(self.as_mut().headers(), HeaderMap::new());
 
                             remove_sensitive_headers(&mut headers, &self.url, &self.urls);
+                            // only if it wasn't stripped for leaving the host
+                            if headers.contains_key(AUTHORIZATION) {
+                                refresh_bearer_token(self.bearer_token.as_ref(), &mut headers);
+                            }
+                            let this = self.as_mut().project();
+                            if let Some(auth) = this.digest_auth {
+                                let nonces = &this.client.digest_nonces;
+                                auth.authorize(nonces, this.method, this.url, &mut headers);
+                            }
                             let uri = try_uri(&self.url)?;
                             let body = match self.body {
                                 Some(Some(ref body)) => Body::reusable(body.clone()),
@@ -2578,6 +4480,21 @@ impl Future for PendingRequest {
                                 }
                             }
 
+                            let body_bytes = match self.body {
+                                Some(Some(ref body)) => &body[..],
+                                _ => &[][..],
+                            };
+                            self.client
+                                .sign(&self.method, &self.url, &mut headers, body_bytes);
+
+                            if self.client.proxies_by_request {
+                                let mut req = Request::new(self.method.clone(), self.url.clone());
+                                *req.headers_mut() = headers.clone();
+                                let chosen = self.client.request_proxies(&req);
+                                request_proxies.set(chosen.clone());
+                                *self.as_mut().project().request_proxies = chosen;
+                            }
+
                             *self.as_mut().in_flight().get_mut() =
                                 match *self.as_mut().in_flight().as_ref() {
                                     #[cfg(feature = "http3")]
@@ -2602,7 +4519,7 @@ impl Future for PendingRequest {
                                             .expect("valid request parts");
                                         *req.headers_mut() = headers.clone();
                                         std::mem::swap(self.as_mut().headers(), &mut headers);
-                                        ResponseFuture::Default(self.client.hyper.request(req))
+                                        self.client.send(&self.url, req)
                                     }
                                 };
 
@@ -2618,12 +4535,21 @@ impl Future for PendingRequest {
                 }
             }
 
-            let res = Response::new(
+            if !self.history.0.is_empty() {
+                let history = std::mem::take(self.as_mut().project().history);
+                res.extensions_mut().insert(history);
+            }
+
+            let mut res = Response::new(
                 res,
                 self.url.clone(),
-                self.client.accepts,
+                self.accepts,
                 self.timeout.take(),
+                upload,
             );
+            if let Some(max) = self.client.max_response_size {
+                res = res.with_body_limit(max);
+            }
             return Poll::Ready(Ok(res));
         }
     }
@@ -2637,11 +4563,30 @@ impl fmt::Debug for Pending {
                 .field("method", &req.method)
                 .field("url", &req.url)
                 .finish(),
+            PendingInner::Middleware(_) => f.debug_struct("Pending").finish(),
             PendingInner::Error(ref err) => f.debug_struct("Pending").field("error", err).finish(),
         }
     }
 }
 
+/// Whether a request body would be compressed.
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+fn should_compress(body: &Body, headers: &HeaderMap) -> bool {
+    body.as_bytes() != Some(b"") && !headers.contains_key(CONTENT_ENCODING)
+}
+
+/// Compresses `body` with `encoding`, setting the headers describing it.
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+fn compress(encoding: ContentEncoding, body: Body, headers: &mut HeaderMap) -> Body {
+    headers.insert(CONTENT_ENCODING, encoding.header_value());
+    let body = encoding.encode(body);
+    match body.as_bytes() {
+        Some(bytes) => headers.insert(CONTENT_LENGTH, HeaderValue::from(bytes.len())),
+        None => headers.remove(CONTENT_LENGTH),
+    };
+    body
+}
+
 fn make_referer(next: &Url, previous: &Url) -> Option<HeaderValue> {
     if next.scheme() == "http" && previous.scheme() == "https" {
         return None;
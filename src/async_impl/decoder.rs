@@ -12,6 +12,9 @@ use async_compression::tokio::bufread::BrotliDecoder;
 #[cfg(feature = "deflate")]
 use async_compression::tokio::bufread::ZlibDecoder;
 
+#[cfg(feature = "zstd")]
+use async_compression::tokio::bufread::ZstdDecoder;
+
 use bytes::Bytes;
 use futures_core::Stream;
 use futures_util::stream::Peekable;
@@ -19,9 +22,9 @@ use http::HeaderMap;
 use hyper::body::Body as HttpBody;
 use hyper::body::Frame;
 
-#[cfg(any(feature = "gzip", feature = "brotli", feature = "deflate"))]
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "deflate", feature = "zstd"))]
 use tokio_util::codec::{BytesCodec, FramedRead};
-#[cfg(any(feature = "gzip", feature = "brotli", feature = "deflate"))]
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "deflate", feature = "zstd"))]
 use tokio_util::io::StreamReader;
 
 use super::body::ResponseBody;
@@ -35,6 +38,8 @@ pub(super) struct Accepts {
     pub(super) brotli: bool,
     #[cfg(feature = "deflate")]
     pub(super) deflate: bool,
+    #[cfg(feature = "zstd")]
+    pub(super) zstd: bool,
 }
 
 impl Accepts {
@@ -46,6 +51,8 @@ impl Accepts {
             brotli: false,
             #[cfg(feature = "deflate")]
             deflate: false,
+            #[cfg(feature = "zstd")]
+            zstd: false,
         }
     }
 }
@@ -59,7 +66,7 @@ pub(crate) struct Decoder {
 
 type PeekableIoStream = Peekable<IoStream>;
 
-#[cfg(any(feature = "gzip", feature = "brotli", feature = "deflate"))]
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "deflate", feature = "zstd"))]
 type PeekableIoStreamReader = StreamReader<PeekableIoStream, Bytes>;
 
 enum Inner {
@@ -78,8 +85,12 @@ enum Inner {
     #[cfg(feature = "deflate")]
     Deflate(Pin<Box<FramedRead<ZlibDecoder<PeekableIoStreamReader>, BytesCodec>>>),
 
+    /// A `Zstd` decoder will uncompress the zstd compressed response content before returning it.
+    #[cfg(feature = "zstd")]
+    Zstd(Pin<Box<FramedRead<ZstdDecoder<PeekableIoStreamReader>, BytesCodec>>>),
+
     /// A decoder that doesn't have a value yet.
-    #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate"))]
+    #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate", feature = "zstd"))]
     Pending(Pin<Box<Pending>>),
 }
 
@@ -95,6 +106,8 @@ enum DecoderType {
     Brotli,
     #[cfg(feature = "deflate")]
     Deflate,
+    #[cfg(feature = "zstd")]
+    Zstd,
 }
 
 impl fmt::Debug for Decoder {
@@ -111,11 +124,19 @@ impl Decoder {
         }
     }
 
-    #[cfg(feature = "blocking")]
+    #[cfg(any(feature = "blocking", feature = "stream"))]
     pub(crate) fn into_stream(self) -> IoStream<Self> {
         IoStream(self)
     }
 
+    /// Fails once more than `limit` bytes were decoded.
+    pub(super) fn limited(self, limit: u64) -> Decoder {
+        use http_body_util::BodyExt;
+
+        let body = super::body::limited(self, limit).map_err(Into::into).boxed();
+        Decoder::plain_text(body)
+    }
+
     /// A plain text decoder.
     ///
     /// This decoder will emit the underlying chunks as-is.
@@ -170,7 +191,22 @@ impl Decoder {
         }
     }
 
-    #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate"))]
+    /// A zstd decoder.
+    ///
+    /// This decoder will buffer and decompress chunks that are zstd compressed.
+    #[cfg(feature = "zstd")]
+    fn zstd(body: ResponseBody) -> Decoder {
+        use futures_util::StreamExt;
+
+        Decoder {
+            inner: Inner::Pending(Box::pin(Pending(
+                IoStream(body).peekable(),
+                DecoderType::Zstd,
+            ))),
+        }
+    }
+
+    #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate", feature = "zstd"))]
     fn detect_encoding(headers: &mut HeaderMap, encoding_str: &str) -> bool {
         use http::header::{CONTENT_ENCODING, CONTENT_LENGTH, TRANSFER_ENCODING};
         use log::warn;
@@ -232,6 +268,13 @@ impl Decoder {
             }
         }
 
+        #[cfg(feature = "zstd")]
+        {
+            if _accepts.zstd && Decoder::detect_encoding(_headers, "zstd") {
+                return Decoder::zstd(body);
+            }
+        }
+
         Decoder::plain_text(body)
     }
 }
@@ -245,7 +288,7 @@ impl HttpBody for Decoder {
         cx: &mut Context,
     ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
         match self.inner {
-            #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate"))]
+            #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate", feature = "zstd"))]
             Inner::Pending(ref mut future) => match Pin::new(future).poll(cx) {
                 Poll::Ready(Ok(inner)) => {
                     self.inner = inner;
@@ -257,7 +300,7 @@ impl HttpBody for Decoder {
             Inner::PlainText(ref mut body) => {
                 match futures_core::ready!(Pin::new(body).poll_frame(cx)) {
                     Some(Ok(frame)) => Poll::Ready(Some(Ok(frame))),
-                    Some(Err(err)) => Poll::Ready(Some(Err(crate::error::decode(err)))),
+                    Some(Err(err)) => Poll::Ready(Some(Err(crate::error::body_read(err)))),
                     None => Poll::Ready(None),
                 }
             }
@@ -285,6 +328,14 @@ impl HttpBody for Decoder {
                     None => Poll::Ready(None),
                 }
             }
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(ref mut decoder) => {
+                match futures_core::ready!(Pin::new(decoder).poll_next(cx)) {
+                    Some(Ok(bytes)) => Poll::Ready(Some(Ok(Frame::data(bytes.freeze())))),
+                    Some(Err(err)) => Poll::Ready(Some(Err(crate::error::decode_io(err)))),
+                    None => Poll::Ready(None),
+                }
+            }
         }
     }
 
@@ -292,7 +343,7 @@ impl HttpBody for Decoder {
         match self.inner {
             Inner::PlainText(ref body) => HttpBody::size_hint(body),
             // the rest are "unknown", so default
-            #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate"))]
+            #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate", feature = "zstd"))]
             _ => http_body::SizeHint::default(),
         }
     }
@@ -342,6 +393,11 @@ impl Future for Pending {
                 ZlibDecoder::new(StreamReader::new(_body)),
                 BytesCodec::new(),
             ))))),
+            #[cfg(feature = "zstd")]
+            DecoderType::Zstd => Poll::Ready(Ok(Inner::Zstd(Box::pin(FramedRead::new(
+                ZstdDecoder::new(StreamReader::new(_body)),
+                BytesCodec::new(),
+            ))))),
         }
     }
 }
@@ -364,7 +420,11 @@ where
                         continue;
                     }
                 }
-                Some(Err(err)) => Poll::Ready(Some(Err(error::into_io(err.into())))),
+                Some(Err(err)) => {
+                    // kept as a body error once the decoder returns it
+                    let err = error::body_read(err.into());
+                    Poll::Ready(Some(Err(error::into_io(err.into()))))
+                }
                 None => Poll::Ready(None),
             };
         }
@@ -388,15 +448,23 @@ impl Accepts {
     */
 
     pub(super) fn as_str(&self) -> Option<&'static str> {
-        match (self.is_gzip(), self.is_brotli(), self.is_deflate()) {
-            (true, true, true) => Some("gzip, br, deflate"),
-            (true, true, false) => Some("gzip, br"),
-            (true, false, true) => Some("gzip, deflate"),
-            (false, true, true) => Some("br, deflate"),
-            (true, false, false) => Some("gzip"),
-            (false, true, false) => Some("br"),
-            (false, false, true) => Some("deflate"),
-            (false, false, false) => None,
+        match (self.is_gzip(), self.is_brotli(), self.is_zstd(), self.is_deflate()) {
+            (true, true, true, true) => Some("gzip, br, zstd, deflate"),
+            (true, true, true, false) => Some("gzip, br, zstd"),
+            (true, true, false, true) => Some("gzip, br, deflate"),
+            (true, true, false, false) => Some("gzip, br"),
+            (true, false, true, true) => Some("gzip, zstd, deflate"),
+            (true, false, true, false) => Some("gzip, zstd"),
+            (true, false, false, true) => Some("gzip, deflate"),
+            (true, false, false, false) => Some("gzip"),
+            (false, true, true, true) => Some("br, zstd, deflate"),
+            (false, true, true, false) => Some("br, zstd"),
+            (false, true, false, true) => Some("br, deflate"),
+            (false, true, false, false) => Some("br"),
+            (false, false, true, true) => Some("zstd, deflate"),
+            (false, false, true, false) => Some("zstd"),
+            (false, false, false, true) => Some("deflate"),
+            (false, false, false, false) => None,
         }
     }
 
@@ -435,6 +503,18 @@ impl Accepts {
             false
         }
     }
+
+    fn is_zstd(&self) -> bool {
+        #[cfg(feature = "zstd")]
+        {
+            self.zstd
+        }
+
+        #[cfg(not(feature = "zstd"))]
+        {
+            false
+        }
+    }
 }
 
 impl Default for Accepts {
@@ -446,6 +526,8 @@ impl Default for Accepts {
             brotli: true,
             #[cfg(feature = "deflate")]
             deflate: true,
+            #[cfg(feature = "zstd")]
+            zstd: true,
         }
     }
 }
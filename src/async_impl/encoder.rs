@@ -0,0 +1,87 @@
+use std::io;
+
+#[cfg(feature = "brotli")]
+use async_compression::tokio::bufread::BrotliEncoder;
+#[cfg(feature = "gzip")]
+use async_compression::tokio::bufread::GzipEncoder;
+#[cfg(feature = "zstd")]
+use async_compression::tokio::bufread::ZstdEncoder;
+use bytes::Bytes;
+use futures_util::{future, FutureExt, TryStreamExt};
+use http::HeaderValue;
+use tokio_util::io::{ReaderStream, StreamReader};
+
+use super::body::Body;
+
+/// A compression format for request bodies.
+///
+/// Each format requires its optional feature, named after it, to be
+/// enabled. See `RequestBuilder::compress_body`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ContentEncoding {
+    /// `gzip`, requires the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// `br`, requires the `brotli` feature.
+    #[cfg(feature = "brotli")]
+    Brotli,
+    /// `zstd`, requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl ContentEncoding {
+    /// The `Content-Encoding` value for this format.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => "gzip",
+            #[cfg(feature = "brotli")]
+            ContentEncoding::Brotli => "br",
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => "zstd",
+        }
+    }
+
+    pub(crate) fn header_value(&self) -> HeaderValue {
+        HeaderValue::from_static(self.as_str())
+    }
+
+    /// Compresses `body`: at once if it is in memory, so its length is
+    /// known and it can be sent again, or else as it is sent.
+    pub(crate) fn encode(&self, body: Body) -> Body {
+        let bytes = match body.as_bytes() {
+            Some(bytes) => Bytes::copy_from_slice(bytes),
+            None => return self.encode_stream(body),
+        };
+        // reading from memory never waits
+        let compressed = self
+            .encode_stream(Body::reusable(bytes.clone()))
+            .into_stream()
+            .try_fold(Vec::new(), |mut compressed, chunk| {
+                compressed.extend_from_slice(&chunk);
+                future::ready(Ok(compressed))
+            })
+            .now_or_never();
+        match compressed {
+            Some(Ok(compressed)) => Body::reusable(compressed.into()),
+            _ => self.encode_stream(Body::reusable(bytes)),
+        }
+    }
+
+    fn encode_stream(&self, body: Body) -> Body {
+        let reader = StreamReader::new(
+            body.into_stream()
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err)),
+        );
+        match *self {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => Body::stream(ReaderStream::new(GzipEncoder::new(reader))),
+            #[cfg(feature = "brotli")]
+            ContentEncoding::Brotli => Body::stream(ReaderStream::new(BrotliEncoder::new(reader))),
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => Body::stream(ReaderStream::new(ZstdEncoder::new(reader))),
+        }
+    }
+}
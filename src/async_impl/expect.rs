@@ -0,0 +1,194 @@
+//! Support for `Expect: 100-continue` requests.
+//!
+//! The body of such a request is held back until the server answers with
+//! `100 Continue`, or until a timeout passes without any answer, since
+//! some servers never send one. If a final response arrives first, the
+//! body is never sent: it fails once that response is dropped, which
+//! closes the connection instead of uploading it.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use futures_util::task::AtomicWaker;
+use http::StatusCode;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use tokio::time::Sleep;
+
+use super::body::{Body, ResponseBody};
+use crate::error::BoxError;
+
+// waiting for `100 Continue`
+const WAITING: u8 = 0;
+// the body may be sent
+const CONTINUE: u8 = 1;
+// a final response arrived first, and is still in use
+const HELD: u8 = 2;
+// the body won't be sent
+const ABORTED: u8 = 3;
+
+struct Shared {
+    state: AtomicU8,
+    waker: AtomicWaker,
+}
+
+impl Shared {
+    fn transition(&self, from: u8, to: u8) -> bool {
+        let changed = self
+            .state
+            .compare_exchange(from, to, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok();
+        if changed {
+            self.waker.wake();
+        }
+        changed
+    }
+}
+
+/// The handle to a request body waiting for `100 Continue`.
+///
+/// Dropping it before the body may be sent aborts the body.
+pub(crate) struct ExpectContinue {
+    shared: Arc<Shared>,
+}
+
+struct WaitingBody {
+    inner: Body,
+    shared: Arc<Shared>,
+    timeout: Option<Pin<Box<Sleep>>>,
+}
+
+/// Wraps `body` so it isn't sent before `100 Continue`, or `timeout`.
+pub(crate) fn wait_for_continue(body: Body, timeout: Duration) -> (Body, ExpectContinue) {
+    let shared = Arc::new(Shared {
+        state: AtomicU8::new(WAITING),
+        waker: AtomicWaker::new(),
+    });
+    let body = Body::boxed(WaitingBody {
+        inner: body,
+        shared: shared.clone(),
+        timeout: Some(Box::pin(tokio::time::sleep(timeout))),
+    });
+    (body, ExpectContinue { shared })
+}
+
+impl ExpectContinue {
+    /// Lets the body be sent when the server answers `100 Continue`.
+    pub(crate) fn register<B>(&self, req: &mut http::Request<B>) {
+        let shared = self.shared.clone();
+        hyper::ext::on_informational(req, move |res| {
+            if res.status() == StatusCode::CONTINUE {
+                shared.transition(WAITING, CONTINUE);
+            }
+        });
+    }
+
+    /// Returns the handle if the response arrived before the body was
+    /// sent, in which case it must be kept as long as the response is in
+    /// use.
+    pub(crate) fn on_response(self, status: StatusCode) -> Option<ExpectContinue> {
+        if !status.is_informational() && self.shared.transition(WAITING, HELD) {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    /// Keeps the body from failing until `body` is dropped.
+    pub(crate) fn hold(self, body: ResponseBody) -> ResponseBody {
+        HeldBody {
+            inner: body,
+            _upload: self,
+        }
+        .boxed()
+    }
+}
+
+/// A response body that keeps a request body from being aborted.
+struct HeldBody {
+    inner: ResponseBody,
+    _upload: ExpectContinue,
+}
+
+impl Drop for ExpectContinue {
+    fn drop(&mut self) {
+        if !self.shared.transition(WAITING, ABORTED) {
+            self.shared.transition(HELD, ABORTED);
+        }
+    }
+}
+
+impl HttpBody for WaitingBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        loop {
+            let state = self.shared.state.load(Ordering::Acquire);
+            match state {
+                CONTINUE => {
+                    return Pin::new(&mut self.inner)
+                        .poll_frame(cx)
+                        .map_err(Into::into);
+                }
+                ABORTED => {
+                    return Poll::Ready(Some(Err(crate::error::body(
+                        "request body not sent after a final response",
+                    )
+                    .into())));
+                }
+                _ => {}
+            }
+
+            self.shared.waker.register(cx.waker());
+            if state == WAITING {
+                if let Some(timeout) = self.timeout.as_mut() {
+                    if timeout.as_mut().poll(cx).is_ready() {
+                        self.timeout = None;
+                        self.shared.transition(WAITING, CONTINUE);
+                        continue;
+                    }
+                }
+            }
+            if self.shared.state.load(Ordering::Acquire) == state {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl HttpBody for HeldBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -1,18 +1,30 @@
 pub use self::body::Body;
+pub use self::cancel::CancelHandle;
 pub use self::client::{Client, ClientBuilder};
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+pub use self::encoder::ContentEncoding;
 pub use self::request::{Request, RequestBuilder};
 pub use self::response::Response;
+#[cfg(feature = "sse")]
+pub use self::sse::SseEvent;
 pub use self::upgrade::Upgraded;
 
 #[cfg(feature = "blocking")]
 pub(crate) use self::decoder::Decoder;
 
 pub mod body;
+mod cancel;
 pub mod client;
 pub mod decoder;
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+mod encoder;
+mod expect;
 pub mod h3_client;
 #[cfg(feature = "multipart")]
 pub mod multipart;
 pub(crate) mod request;
 mod response;
+mod single_flight;
+#[cfg(feature = "sse")]
+mod sse;
 mod upgrade;
@@ -12,6 +12,8 @@ use futures_util::{future, stream, StreamExt};
 
 use super::Body;
 use crate::header::HeaderMap;
+#[cfg(feature = "stream")]
+use crate::header::CONTENT_LENGTH;
 
 /// An async multipart/form-data request.
 pub struct Form {
@@ -35,6 +37,7 @@ pub(crate) struct FormParts<P> {
 pub(crate) struct PartMetadata {
     mime: Option<Mime>,
     file_name: Option<Cow<'static, str>>,
+    pub(crate) content_length: Option<u64>,
     pub(crate) headers: HeaderMap,
 }
 
@@ -65,6 +68,30 @@ impl Form {
         self.inner.boundary()
     }
 
+    /// Replaces the randomly generated boundary with `boundary`.
+    ///
+    /// The boundary must be 1 to 70 characters from the set allowed by
+    /// RFC 2046: letters, digits, spaces and any of `'()+_,-./:=?`, not
+    /// ending with a space. It must also not appear in the content of any
+    /// part, which isn't checked.
+    ///
+    /// # Errors
+    ///
+    /// Fails with a builder error if `boundary` isn't a valid boundary.
+    ///
+    /// ```
+    /// let form = reqwest::multipart::Form::new()
+    ///     .set_boundary("fixed-boundary")?
+    ///     .text("username", "seanmonstar");
+    /// assert_eq!(form.boundary(), "fixed-boundary");
+    /// # Ok::<(), reqwest::Error>(())
+    /// ```
+    pub fn set_boundary<T: Into<String>>(self, boundary: T) -> crate::Result<Form> {
+        Ok(Form {
+            inner: self.inner.set_boundary(boundary.into())?,
+        })
+    }
+
     /// Add a data field with supplied name and value.
     ///
     /// # Examples
@@ -163,6 +190,10 @@ impl Form {
         self.inner.compute_length()
     }
 
+    pub(crate) fn content_type(&self) -> String {
+        self.inner.content_type()
+    }
+
     fn with_inner<F>(self, func: F) -> Self
     where
         F: FnOnce(FormParts<Part>) -> FormParts<Part>,
@@ -218,6 +249,39 @@ impl Part {
         Part::new(value.into(), Some(length))
     }
 
+    /// Makes a new parameter streamed from an `AsyncRead`, such as a
+    /// `tokio::fs::File`.
+    ///
+    /// The reader is only read as the request body is sent, so the part is
+    /// never buffered in full. If `content_length` is `Some`, the reader
+    /// must produce exactly that many bytes; the part gets a
+    /// `Content-Length` header, and if every part of the form has a known
+    /// length, so does the request. Otherwise the request is sent with
+    /// chunked transfer encoding.
+    ///
+    /// Setting `headers` on the part afterwards replaces its
+    /// `Content-Length` header.
+    ///
+    /// # Optional
+    ///
+    /// This requires the `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn reader<R>(reader: R, content_length: Option<u64>) -> Part
+    where
+        R: tokio::io::AsyncRead + Send + Sync + 'static,
+    {
+        let part = Part::new(Body::from_async_read(reader, content_length), content_length);
+        match content_length {
+            Some(len) => {
+                let mut headers = HeaderMap::new();
+                headers.insert(CONTENT_LENGTH, len.into());
+                part.headers(headers)
+            }
+            None => part,
+        }
+    }
+
     fn new(value: Body, body_length: Option<u64>) -> Part {
         Part {
             meta: PartMetadata::new(),
@@ -249,6 +313,16 @@ impl Part {
         self.with_inner(move |inner| inner.headers(headers))
     }
 
+    /// Annotates the part with the length of its body, whether or not the
+    /// body knows it.
+    ///
+    /// The part gets a `Content-Length` header, and the length is used to
+    /// compute the length of the form, without buffering the body. The
+    /// body must produce exactly `len` bytes.
+    pub fn with_content_length(self, len: u64) -> Part {
+        self.with_inner(move |inner| inner.content_length(len))
+    }
+
     fn with_inner<F>(self, func: F) -> Self
     where
         F: FnOnce(PartMetadata) -> PartMetadata,
@@ -271,11 +345,10 @@ impl fmt::Debug for Part {
 
 impl PartProps for Part {
     fn value_len(&self) -> Option<u64> {
-        if self.body_length.is_some() {
-            self.body_length
-        } else {
-            self.value.content_length()
-        }
+        self.meta
+            .content_length
+            .or(self.body_length)
+            .or_else(|| self.value.content_length())
     }
 
     fn metadata(&self) -> &PartMetadata {
@@ -299,6 +372,17 @@ impl<P: PartProps> FormParts<P> {
         &self.boundary
     }
 
+    pub(crate) fn set_boundary(mut self, boundary: String) -> crate::Result<Self> {
+        validate_boundary(&boundary)?;
+        self.boundary = boundary;
+        Ok(self)
+    }
+
+    /// The `Content-Type` of the form, with its boundary.
+    pub(crate) fn content_type(&self) -> String {
+        content_type(&self.boundary)
+    }
+
     /// Adds a customized Part.
     pub(crate) fn part<T>(mut self, name: T, part: P) -> Self
     where
@@ -383,6 +467,7 @@ impl PartMetadata {
         PartMetadata {
             mime: None,
             file_name: None,
+            content_length: None,
             headers: HeaderMap::default(),
         }
     }
@@ -400,6 +485,11 @@ impl PartMetadata {
         self
     }
 
+    pub(crate) fn content_length(mut self, len: u64) -> Self {
+        self.content_length = Some(len);
+        self
+    }
+
     pub(crate) fn headers<T>(mut self, headers: T) -> Self
     where
         T: Into<HeaderMap>,
@@ -417,6 +507,7 @@ impl PartMetadata {
         debug_struct
             .field("mime", &self.mime)
             .field("file_name", &self.file_name)
+            .field("content_length", &self.content_length)
             .field("headers", &self.headers)
     }
 }
@@ -492,6 +583,11 @@ impl PercentEncoding {
             buf.extend_from_slice(mime.as_ref().as_bytes());
         }
 
+        if let Some(len) = field.content_length {
+            buf.extend_from_slice(b"\r\nContent-Length: ");
+            buf.extend_from_slice(len.to_string().as_bytes());
+        }
+
         for (k, v) in field.headers.iter() {
             buf.extend_from_slice(b"\r\n");
             buf.extend_from_slice(k.as_str().as_bytes());
@@ -512,6 +608,35 @@ impl PercentEncoding {
     }
 }
 
+/// The `Content-Type` of a form with `boundary`.
+pub(crate) fn content_type(boundary: &str) -> String {
+    // a boundary with other characters must be quoted in the header
+    let is_token = boundary
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"'+_-.".contains(&b));
+    if is_token {
+        format!("multipart/form-data; boundary={boundary}")
+    } else {
+        format!("multipart/form-data; boundary=\"{boundary}\"")
+    }
+}
+
+/// Checks `boundary` against the `boundary` rule of RFC 2046.
+pub(crate) fn validate_boundary(boundary: &str) -> crate::Result<()> {
+    if boundary.is_empty() || boundary.len() > 70 {
+        return Err(crate::error::builder(
+            "multipart boundary must be 1 to 70 characters long",
+        ));
+    }
+    let allowed = |b: u8| b.is_ascii_alphanumeric() || b"'()+_,-./:=? ".contains(&b);
+    if !boundary.bytes().all(allowed) || boundary.ends_with(' ') {
+        return Err(crate::error::builder(format!(
+            "invalid multipart boundary: {boundary:?}"
+        )));
+    }
+    Ok(())
+}
+
 fn gen_boundary() -> String {
     use crate::util::fast_random as random;
 
@@ -656,6 +781,116 @@ mod tests {
         assert_eq!(body_part.value_len().unwrap(), bytes_len as u64);
     }
 
+    #[cfg(feature = "stream")]
+    #[test]
+    fn reader_content_length() {
+        let mut form = Form::new()
+            .part("sized", Part::reader(&b"sized data"[..], Some(10)))
+            .part("text", Part::text("value"));
+        form.inner.boundary = "boundary".to_string();
+        let expected = "--boundary\r\n\
+                        Content-Disposition: form-data; name=\"sized\"\r\n\
+                        content-length: 10\r\n\
+                        \r\n\
+                        sized data\r\n\
+                        --boundary\r\n\
+                        Content-Disposition: form-data; name=\"text\"\r\n\r\n\
+                        value\r\n\
+                        --boundary--\r\n";
+        assert_eq!(form.compute_length(), Some(expected.len() as u64));
+
+        let rt = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("new rt");
+        let body = form.stream().into_stream();
+        let s = body.map(|try_c| try_c.map(|r| r.to_vec())).try_concat();
+        let out = rt.block_on(s).unwrap();
+        assert_eq!(std::str::from_utf8(&out).unwrap(), expected);
+
+        let mut form = Form::new().part("unsized", Part::reader(&b"data"[..], None));
+        assert_eq!(form.compute_length(), None);
+    }
+
+    #[test]
+    fn part_with_content_length() {
+        let sized = || {
+            let data = stream::iter(vec![Ok::<_, crate::Error>("sized "), Ok("data")]);
+            Part::stream(Body::stream(data)).with_content_length(10)
+        };
+        let streamed = || {
+            let data = stream::once(future::ready(Ok::<_, crate::Error>("data")));
+            Part::stream(Body::stream(data))
+        };
+
+        let mut form = Form::new()
+            .part("sized", sized().mime_str("text/plain").unwrap())
+            .part("unsized", streamed())
+            .part("text", Part::text("value"));
+        form.inner.boundary = "boundary".to_string();
+        let expected = "--boundary\r\n\
+                        Content-Disposition: form-data; name=\"sized\"\r\n\
+                        Content-Type: text/plain\r\n\
+                        Content-Length: 10\r\n\
+                        \r\n\
+                        sized data\r\n\
+                        --boundary\r\n\
+                        Content-Disposition: form-data; name=\"unsized\"\r\n\r\n\
+                        data\r\n\
+                        --boundary\r\n\
+                        Content-Disposition: form-data; name=\"text\"\r\n\r\n\
+                        value\r\n\
+                        --boundary--\r\n";
+        assert_eq!(form.compute_length(), None);
+
+        let rt = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("new rt");
+        let body = form.stream().into_stream();
+        let s = body.map(|try_c| try_c.map(|r| r.to_vec())).try_concat();
+        let out = rt.block_on(s).unwrap();
+        assert_eq!(std::str::from_utf8(&out).unwrap(), expected);
+
+        let mut form = Form::new()
+            .part("sized", sized())
+            .part("text", Part::text("value"));
+        form.inner.boundary = "boundary".to_string();
+        let expected = "--boundary\r\n\
+                        Content-Disposition: form-data; name=\"sized\"\r\n\
+                        Content-Length: 10\r\n\
+                        \r\n\
+                        sized data\r\n\
+                        --boundary\r\n\
+                        Content-Disposition: form-data; name=\"text\"\r\n\r\n\
+                        value\r\n\
+                        --boundary--\r\n";
+        assert_eq!(form.compute_length(), Some(expected.len() as u64));
+    }
+
+    #[test]
+    fn set_boundary() {
+        let form = Form::new().set_boundary("simple-boundary.1").unwrap();
+        assert_eq!(form.boundary(), "simple-boundary.1");
+        assert_eq!(
+            form.content_type(),
+            "multipart/form-data; boundary=simple-boundary.1"
+        );
+
+        let form = Form::new().set_boundary("needs quotes=?").unwrap();
+        assert_eq!(
+            form.content_type(),
+            "multipart/form-data; boundary=\"needs quotes=?\""
+        );
+
+        let too_long = "a".repeat(71);
+        for invalid in ["", "trailing ", "semi;colon", "quote\"", "caf\u{e9}", &too_long] {
+            let err = Form::new().set_boundary(invalid).unwrap_err();
+            assert!(err.is_builder(), "{invalid:?}");
+        }
+        assert!(Form::new().set_boundary("a".repeat(70)).is_ok());
+    }
+
     #[test]
     fn header_percent_encoding() {
         let name = "start%'\"\r\nßend";
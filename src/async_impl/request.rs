@@ -1,6 +1,7 @@
 use std::convert::TryFrom;
 use std::fmt;
 use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
 use serde::Serialize;
@@ -8,14 +9,17 @@ use serde::Serialize;
 use serde_json;
 
 use super::body::Body;
+use super::cancel::{self, CancelHandle};
 use super::client::{Client, Pending};
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+use super::encoder::ContentEncoding;
 #[cfg(feature = "multipart")]
 use super::multipart;
 use super::response::Response;
 #[cfg(feature = "multipart")]
 use crate::header::CONTENT_LENGTH;
 use crate::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
-use crate::{Method, Url};
+use crate::{digest_auth, retry, LanguageTag, Method, Url};
 use http::{request::Parts, Request as HttpRequest, Version};
 
 /// A request which can be executed with `Client::execute()`.
@@ -25,9 +29,26 @@ pub struct Request {
     headers: HeaderMap,
     body: Option<Body>,
     timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    retry: Option<retry::Policy>,
+    retry_body_buffer_limit: Option<usize>,
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    compression: Option<ContentEncoding>,
+    decompress: bool,
+    expect_continue: bool,
+    bearer_token: Option<TokenFn>,
+    digest_auth: Option<digest_auth::Credentials>,
+    timeout_fn: Option<TimeoutFn>,
     version: Version,
+    extensions: http::Extensions,
 }
 
+/// Produces a fresh bearer token for every attempt of a request.
+pub(crate) type TokenFn = Arc<dyn Fn() -> String + Send + Sync>;
+
+/// Computes the timeout of a request just before it is sent.
+pub(crate) type TimeoutFn = Arc<dyn Fn() -> Option<Duration> + Send + Sync>;
+
 /// A builder to construct the properties of a `Request`.
 ///
 /// To construct a `RequestBuilder`, refer to the `Client` documentation.
@@ -35,6 +56,8 @@ pub struct Request {
 pub struct RequestBuilder {
     client: Client,
     request: crate::Result<Request>,
+    // the `Content-Type` came with the body, from `body_with_type`
+    typed_body: bool,
 }
 
 impl Request {
@@ -47,7 +70,18 @@ impl Request {
             headers: HeaderMap::new(),
             body: None,
             timeout: None,
+            connect_timeout: None,
+            retry: None,
+            retry_body_buffer_limit: None,
+            #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+            compression: None,
+            decompress: true,
+            expect_continue: false,
+            bearer_token: None,
+            digest_auth: None,
+            timeout_fn: None,
             version: Version::default(),
+            extensions: http::Extensions::new(),
         }
     }
 
@@ -111,6 +145,70 @@ impl Request {
         &mut self.timeout
     }
 
+    /// Get the connect timeout.
+    #[inline]
+    pub fn connect_timeout(&self) -> Option<&Duration> {
+        self.connect_timeout.as_ref()
+    }
+
+    /// Get a mutable reference to the connect timeout.
+    #[inline]
+    pub fn connect_timeout_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.connect_timeout
+    }
+
+    /// Get the retry policy.
+    #[inline]
+    pub fn retry(&self) -> Option<&retry::Policy> {
+        self.retry.as_ref()
+    }
+
+    /// Get a mutable reference to the retry policy.
+    #[inline]
+    pub fn retry_mut(&mut self) -> &mut Option<retry::Policy> {
+        &mut self.retry
+    }
+
+    /// Get the body compression.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    #[inline]
+    pub fn compression(&self) -> Option<ContentEncoding> {
+        self.compression
+    }
+
+    /// Get a mutable reference to the body compression.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    #[inline]
+    pub fn compression_mut(&mut self) -> &mut Option<ContentEncoding> {
+        &mut self.compression
+    }
+
+    /// Get whether the response body will be decompressed.
+    #[inline]
+    pub fn decompress(&self) -> bool {
+        self.decompress
+    }
+
+    /// Get a mutable reference to whether the response body will be
+    /// decompressed.
+    #[inline]
+    pub fn decompress_mut(&mut self) -> &mut bool {
+        &mut self.decompress
+    }
+
+    /// Get whether the body waits for `100 Continue` before being sent.
+    #[inline]
+    pub fn expect_continue(&self) -> bool {
+        self.expect_continue
+    }
+
+    /// Get a mutable reference to whether the body waits for
+    /// `100 Continue` before being sent.
+    #[inline]
+    pub fn expect_continue_mut(&mut self) -> &mut bool {
+        &mut self.expect_continue
+    }
+
     /// Get the http version.
     #[inline]
     pub fn version(&self) -> Version {
@@ -123,6 +221,18 @@ impl Request {
         &mut self.version
     }
 
+    /// Get the extensions.
+    #[inline]
+    pub fn extensions(&self) -> &http::Extensions {
+        &self.extensions
+    }
+
+    /// Get a mutable reference to the extensions.
+    #[inline]
+    pub fn extensions_mut(&mut self) -> &mut http::Extensions {
+        &mut self.extensions
+    }
+
     /// Attempt to clone the request.
     ///
     /// `None` is returned if the request can not be cloned, i.e. if the body is a stream.
@@ -133,12 +243,52 @@ impl Request {
         };
         let mut req = Request::new(self.method().clone(), self.url().clone());
         *req.timeout_mut() = self.timeout().copied();
+        *req.connect_timeout_mut() = self.connect_timeout().copied();
+        *req.retry_mut() = self.retry().cloned();
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        {
+            *req.compression_mut() = self.compression();
+        }
+        *req.decompress_mut() = self.decompress();
+        *req.expect_continue_mut() = self.expect_continue();
+        req.bearer_token = self.bearer_token.clone();
+        req.digest_auth = self.digest_auth.clone();
+        req.timeout_fn = self.timeout_fn.clone();
         *req.headers_mut() = self.headers().clone();
         *req.version_mut() = self.version();
+        req.extensions = self.extensions.clone();
         req.body = body;
         Some(req)
     }
 
+    pub(crate) fn retry_body_buffer_limit(&self) -> Option<usize> {
+        self.retry_body_buffer_limit
+    }
+
+    pub(crate) fn retry_body_buffer_limit_mut(&mut self) -> &mut Option<usize> {
+        &mut self.retry_body_buffer_limit
+    }
+
+    pub(crate) fn bearer_token_mut(&mut self) -> &mut Option<TokenFn> {
+        &mut self.bearer_token
+    }
+
+    pub(crate) fn digest_auth_mut(&mut self) -> &mut Option<digest_auth::Credentials> {
+        &mut self.digest_auth
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn timeout_fn_mut(&mut self) -> &mut Option<TimeoutFn> {
+        &mut self.timeout_fn
+    }
+
+    /// Replaces the timeout with the one computed by `timeout_fn`, if set.
+    pub(crate) fn resolve_timeout(&mut self) {
+        if let Some(timeout_fn) = self.timeout_fn.take() {
+            self.timeout = timeout_fn();
+        }
+    }
+
     pub(super) fn pieces(
         self,
     ) -> (
@@ -147,6 +297,7 @@ impl Request {
         HeaderMap,
         Option<Body>,
         Option<Duration>,
+        Option<Duration>,
         Version,
     ) {
         (
@@ -155,6 +306,7 @@ impl Request {
             self.headers,
             self.body,
             self.timeout,
+            self.connect_timeout,
             self.version,
         )
     }
@@ -162,7 +314,11 @@ impl Request {
 
 impl RequestBuilder {
     pub(super) fn new(client: Client, request: crate::Result<Request>) -> RequestBuilder {
-        let mut builder = RequestBuilder { client, request };
+        let mut builder = RequestBuilder {
+            client,
+            request,
+            typed_body: false,
+        };
 
         let auth = builder
             .request
@@ -182,10 +338,14 @@ impl RequestBuilder {
         RequestBuilder {
             client,
             request: crate::Result::Ok(request),
+            typed_body: false,
         }
     }
 
     /// Add a `Header` to this Request.
+    ///
+    /// This replaces any values the client has for `key` in its default
+    /// headers. Calling it again with the same `key` adds another value.
     pub fn header<K, V>(self, key: K, value: V) -> RequestBuilder
     where
         HeaderName: TryFrom<K>,
@@ -238,6 +398,58 @@ impl RequestBuilder {
         self
     }
 
+    /// Sets the `Host` header of this Request, replacing the one derived
+    /// from the URL.
+    ///
+    /// The connection is still made to the host of the URL, and TLS still
+    /// uses it for server name indication and to verify the certificate,
+    /// unless `ClientBuilder::tls_sni_override` is set for that host. This
+    /// is useful to reach a virtual host through an address or a load
+    /// balancer.
+    ///
+    /// This only applies to HTTP/1; HTTP/2 requests take their `:authority`
+    /// from the URL.
+    ///
+    /// # Security
+    ///
+    /// The server routes the request by this header, while the certificate
+    /// is checked against the URL host, so a valid connection doesn't prove
+    /// the request reached the named host. The header is dropped when a
+    /// redirect leads to another host.
+    ///
+    /// ```rust
+    /// # use reqwest::Error;
+    /// #
+    /// # async fn run() -> Result<(), Error> {
+    /// let client = reqwest::Client::new();
+    /// let res = client
+    ///     .get("http://127.0.0.1:8080/status")
+    ///     .host_header("internal.example")
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn host_header<V>(mut self, value: V) -> RequestBuilder
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match <HeaderValue as TryFrom<V>>::try_from(value) {
+                Ok(value) => {
+                    req.headers_mut().insert(crate::header::HOST, value);
+                }
+                Err(e) => error = Some(crate::error::builder(e.into())),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
     /// Enable HTTP basic authentication.
     ///
     /// ```rust
@@ -270,6 +482,158 @@ impl RequestBuilder {
         self.header_sensitive(crate::header::AUTHORIZATION, header_value, true)
     }
 
+    /// Enable HTTP bearer authentication, with a token fetched when the
+    /// request is sent.
+    ///
+    /// Unlike `bearer_auth`, the token isn't fixed when the request is
+    /// built. `token` is called once per attempt, right before the
+    /// `Authorization` header is written, so retries and redirects pick up
+    /// a refreshed token. It replaces any `Authorization` header set before.
+    ///
+    /// `token` is called from inside the client, and should return quickly,
+    /// such as by reading a cached token that is refreshed elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// Sending fails with a builder error if the token isn't a valid header
+    /// value.
+    ///
+    /// ```rust
+    /// # use std::sync::{Arc, RwLock};
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let token = Arc::new(RwLock::new(String::from("initial")));
+    ///
+    /// let cache = token.clone();
+    /// let res = reqwest::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .bearer_auth_fn(move || cache.read().unwrap().clone())
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bearer_auth_fn<F>(mut self, token: F) -> RequestBuilder
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            *req.bearer_token_mut() = Some(Arc::new(token));
+        }
+        self
+    }
+
+    /// Enable HTTP digest authentication.
+    ///
+    /// The request is first sent without credentials. If the server answers
+    /// `401 Unauthorized` with a `Digest` challenge, the request is sent
+    /// again with an `Authorization` header answering it. The `Client` keeps
+    /// the last challenge of each origin, so later requests to it with digest
+    /// authentication answer right away, and a request is sent again if the
+    /// server says the nonce it answered was stale.
+    ///
+    /// The `MD5` and `SHA-256` algorithms and their `-sess` variants are
+    /// supported, with a `qop` of `auth` or without one. The credentials are
+    /// never sent to another origin than the one of the request, even after
+    /// a redirect, and a request with a body can only be sent again if the
+    /// body isn't a stream.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let res = reqwest::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .digest_auth("Aladdin", "open sesame")
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn digest_auth<U, P>(mut self, username: U, password: P) -> RequestBuilder
+    where
+        U: fmt::Display,
+        P: fmt::Display,
+    {
+        if let Ok(ref mut req) = self.request {
+            *req.digest_auth_mut() = Some(digest_auth::Credentials::new(
+                username.to_string(),
+                password.to_string(),
+            ));
+        }
+        self
+    }
+
+    /// Asks for content in one of `languages`, most preferred first, with
+    /// an `Accept-Language` header.
+    ///
+    /// Each language gets a quality lower than the one before it, unless
+    /// set with `LanguageTag::with_quality`, so `en-US`, `en` and `fr` are
+    /// sent as `en-US,en;q=0.9,fr;q=0.8`. It replaces any `Accept-Language`
+    /// header set before. The language the server chose is given by
+    /// `Response::content_language`.
+    ///
+    /// # Errors
+    ///
+    /// Sending fails with a builder error if `languages` is empty, or has an
+    /// invalid tag or a quality outside of `0.0` to `1.0`.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let res = reqwest::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .accept_language(&["en-US".into(), "en".into(), "fr".into()])
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn accept_language(mut self, languages: &[LanguageTag]) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            match crate::language::accept_language(languages) {
+                Ok(value) => {
+                    req.headers_mut()
+                        .insert(crate::header::ACCEPT_LANGUAGE, value);
+                }
+                Err(err) => self.request = Err(err),
+            }
+        }
+        self
+    }
+
+    /// Asks for only part of the resource, with a `Range` header.
+    ///
+    /// The range covers bytes `start` to `end`, both included, or everything
+    /// from `start` on if `end` is `None`. It replaces any `Range` header set
+    /// before. Servers may ignore it and send the whole resource, which is
+    /// told apart with `Response::content_range`.
+    ///
+    /// # Errors
+    ///
+    /// Sending fails with a builder error if `end` is before `start`.
+    ///
+    /// ```rust
+    /// # use reqwest::Error;
+    /// #
+    /// # async fn run() -> Result<(), Error> {
+    /// let client = reqwest::Client::new();
+    /// // sends `Range: bytes=500-999`
+    /// let res = client.get("http://httpbin.org/range/2048")
+    ///     .range(500, Some(999))
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn range(mut self, start: u64, end: Option<u64>) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            match crate::range::range_header(start, end) {
+                Ok(value) => {
+                    req.headers_mut().insert(crate::header::RANGE, value);
+                }
+                Err(err) => self.request = Err(err),
+            }
+        }
+        self
+    }
+
     /// Set the request body.
     pub fn body<T: Into<Body>>(mut self, body: T) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
@@ -278,14 +642,228 @@ impl RequestBuilder {
         self
     }
 
+    /// Set the request body, and its `Content-Type`.
+    ///
+    /// `mime` is a `mime::Mime`, or a string that must parse as one. It
+    /// replaces any `Content-Type` set before, while `form`, `json` or
+    /// `ndjson_body` called afterwards still replace it along with the
+    /// body.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let res = reqwest::Client::new()
+    ///     .post("http://httpbin.org/post")
+    ///     .body_with_type(r#"{"answer":42}"#, mime::APPLICATION_JSON)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn body_with_type<T, M>(mut self, body: T, mime: M) -> RequestBuilder
+    where
+        T: Into<Body>,
+        M: AsRef<str>,
+    {
+        if let Ok(ref mut req) = self.request {
+            match content_type(mime.as_ref()) {
+                Ok(value) => {
+                    req.headers_mut().insert(CONTENT_TYPE, value);
+                    *req.body_mut() = Some(body.into());
+                    self.typed_body = true;
+                }
+                Err(err) => self.request = Err(err),
+            }
+        }
+        self
+    }
+
     /// Enables a request timeout.
     ///
     /// The timeout is applied from when the request starts connecting until the
     /// response body has finished. It affects only this request and overrides
     /// the timeout configured using `ClientBuilder::timeout()`.
+    ///
+    /// This replaces any function set with `timeout_fn`.
     pub fn timeout(mut self, timeout: Duration) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
             *req.timeout_mut() = Some(timeout);
+            req.timeout_fn = None;
+        }
+        self
+    }
+
+    /// Computes the request timeout with a function, called just before the
+    /// request is sent.
+    ///
+    /// This is useful when the time left for an attempt depends on a
+    /// deadline shared with other work. The function is called again each
+    /// time a middleware sends the request. Returning `None` leaves only the
+    /// timeout configured using `ClientBuilder::timeout()`.
+    ///
+    /// This replaces any timeout set with `timeout`, and `timeout` replaces
+    /// this in turn: whichever is called last applies.
+    ///
+    /// ```rust
+    /// # use std::time::{Duration, Instant};
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let deadline = Instant::now() + Duration::from_secs(10);
+    /// let res = reqwest::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .timeout_fn(move || deadline.checked_duration_since(Instant::now()))
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn timeout_fn<F>(mut self, timeout: F) -> RequestBuilder
+    where
+        F: Fn() -> Option<Duration> + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            *req.timeout_mut() = None;
+            req.timeout_fn = Some(Arc::new(timeout));
+        }
+        self
+    }
+
+    /// Set a timeout for only the connect phase of this request.
+    ///
+    /// The timeout covers resolving the host, establishing the TCP
+    /// connection, and completing any proxy tunnel and TLS handshake. It
+    /// affects only this request and overrides the timeout configured using
+    /// `ClientBuilder::connect_timeout()`.
+    ///
+    /// If an idle pooled connection is reused, no connect happens and this
+    /// timeout does not apply. The overall `timeout` still covers the
+    /// connect phase, so use a shorter duration here for it to fire first.
+    ///
+    /// An error caused by this timeout returns `true` for both
+    /// `Error::is_connect()` and `Error::is_timeout()`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.connect_timeout_mut() = Some(timeout);
+        }
+        self
+    }
+
+    /// Set a policy for retrying this request.
+    ///
+    /// The request is sent again, after the policy's backoff, when it fails
+    /// with a status or error the policy retries. A request with a streaming
+    /// body can't be replayed, so it is only ever sent once. The `timeout`
+    /// covers all attempts together.
+    ///
+    /// ```
+    /// # use reqwest::{Error, retry};
+    /// #
+    /// # async fn run() -> Result<(), Error> {
+    /// let client = reqwest::Client::new();
+    /// let res = client
+    ///     .get("http://httpbin.org/status/503")
+    ///     .retry(retry::Policy::exponential(3))
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn retry(mut self, policy: retry::Policy) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.retry_mut() = Some(policy);
+        }
+        self
+    }
+
+    /// Read a streaming body into memory, up to `limit` bytes, so the
+    /// `RetryPolicy` set with `ClientBuilder::with_retry` can send it again.
+    ///
+    /// The body is read before the first attempt. If it turns out longer
+    /// than `limit`, it is sent as it is read, and only once. Without this,
+    /// a request with a streaming body is never retried.
+    pub fn retry_body_buffer_limit(mut self, limit: usize) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.retry_body_buffer_limit_mut() = Some(limit);
+        }
+        self
+    }
+
+    /// Compress the request body with `encoding`.
+    ///
+    /// `Content-Encoding` is set, and a body in memory is compressed at
+    /// once, with its compressed length as `Content-Length`. A streaming
+    /// body is compressed as it is sent, so its compressed length isn't
+    /// known up front: any `Content-Length` is removed, and over HTTP/1.1
+    /// the body is sent with `Transfer-Encoding: chunked`. This overrides
+    /// the default set with `ClientBuilder::compress_body`.
+    ///
+    /// Requests without a body, with an empty one, or that already have a
+    /// `Content-Encoding` header are sent as is, so nothing is compressed
+    /// twice. A compressed streaming body can't be sent again for a retry
+    /// or a `307`/`308` redirect.
+    ///
+    /// ```rust
+    /// # use reqwest::Error;
+    /// #
+    /// # #[cfg(feature = "gzip")]
+    /// # async fn run() -> Result<(), Error> {
+    /// let client = reqwest::Client::new();
+    /// let res = client.post("http://httpbin.org/post")
+    ///     .body("a large payload")
+    ///     .compress_body(reqwest::ContentEncoding::Gzip)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `gzip`, `brotli` or `zstd` feature to be
+    /// enabled, for the matching `ContentEncoding`.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "gzip", feature = "brotli", feature = "zstd")))
+    )]
+    pub fn compress_body(mut self, encoding: ContentEncoding) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.compression_mut() = Some(encoding);
+        }
+        self
+    }
+
+    /// Disable automatic decompression of the response body, for this
+    /// request only.
+    ///
+    /// The body is returned as it was sent by the server, and the
+    /// `Content-Encoding` and `Content-Length` headers are kept. No
+    /// `Accept-Encoding` header is added either, though one set on the
+    /// request is still sent. A server that compresses the body anyway has
+    /// its bytes passed through untouched.
+    ///
+    /// This is useful to forward a response as is, for instance in a proxy.
+    pub fn no_decompress(mut self) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.decompress_mut() = false;
+        }
+        self
+    }
+
+    /// Send `Expect: 100-continue`, and wait for the server to answer
+    /// `100 Continue` before sending the body.
+    ///
+    /// If the server answers with a final response instead, such as `401`
+    /// or `413`, that response is returned and the body is never sent; the
+    /// connection is closed once the response is dropped. If the server
+    /// doesn't answer within `ClientBuilder::expect_continue_timeout`, the
+    /// body is sent anyway.
+    ///
+    /// This avoids uploading a large body the server would reject. It only
+    /// applies to HTTP/1.1 requests with a body; over HTTP/2 the body is
+    /// only sent once the timeout passes. Redirects and retries send the
+    /// body without waiting.
+    pub fn expect_continue(mut self, enabled: bool) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.expect_continue_mut() = enabled;
         }
         self
     }
@@ -314,7 +892,7 @@ impl RequestBuilder {
     pub fn multipart(self, mut multipart: multipart::Form) -> RequestBuilder {
         let mut builder = self.header(
             CONTENT_TYPE,
-            format!("multipart/form-data; boundary={}", multipart.boundary()).as_str(),
+            multipart.content_type().as_str(),
         );
 
         builder = match multipart.compute_length() {
@@ -328,6 +906,60 @@ impl RequestBuilder {
         builder
     }
 
+    /// Sends a multipart/form-data body encoded elsewhere, with `boundary`.
+    ///
+    /// The stream is sent as is, so it must already be a whole multipart
+    /// body using `boundary`, ending with its closing delimiter. Only the
+    /// `Content-Type` is set. The boundary must follow RFC 2046: 1 to 70
+    /// digits, letters or characters of `'()+_,-./:=? `, not ending with a
+    /// space. If a `Content-Length` was set on the request before, it must
+    /// be at least that of the shortest multipart body, `--boundary--`.
+    ///
+    /// ```
+    /// # use reqwest::Error;
+    ///
+    /// # async fn run() -> Result<(), Error> {
+    /// let parts: Vec<Result<_, std::io::Error>> = vec![
+    ///     Ok("--b0undary\r\nContent-Disposition: form-data; name=\"key\"\r\n\r\n"),
+    ///     Ok("value\r\n--b0undary--\r\n"),
+    /// ];
+    ///
+    /// let response = reqwest::Client::new()
+    ///     .post("your url")
+    ///     .multipart_raw(futures_util::stream::iter(parts), "b0undary")
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "multipart")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+    pub fn multipart_raw<S>(mut self, body: S, boundary: &str) -> RequestBuilder
+    where
+        S: futures_core::stream::TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        bytes::Bytes: From<S::Ok>,
+    {
+        if let Err(err) = multipart::validate_boundary(boundary) {
+            self.request = Err(err);
+            return self;
+        }
+        if let Ok(ref req) = self.request {
+            let shortest = boundary.len() as u64 + 4;
+            let len = req.headers().get(CONTENT_LENGTH);
+            let len = len.and_then(|len| len.to_str().ok()?.parse::<u64>().ok());
+            if len.map_or(false, |len| len < shortest) {
+                self.request = Err(crate::error::builder(format!(
+                    "multipart body shorter than the {shortest} bytes of its closing delimiter"
+                )));
+                return self;
+            }
+        }
+
+        let builder = self.header(CONTENT_TYPE, multipart::content_type(boundary));
+        builder.body(Body::stream(body))
+    }
+
     /// Modify the query string of the URL.
     ///
     /// Modifies the URL of this request, adding the parameters provided.
@@ -368,6 +1000,47 @@ impl RequestBuilder {
         self
     }
 
+    /// Sets a query parameter of the URL, replacing any it already has.
+    ///
+    /// If `key` is already in the query string, its first occurrence gets
+    /// `value` and any others are removed. Otherwise, `key=value` is
+    /// appended. Keys are compared after percent-decoding, so `a%20b` and
+    /// `a+b` both match `"a b"`.
+    ///
+    /// The query string is re-encoded, so other parameters keep their
+    /// values but may be written differently, such as `+` for `%20`.
+    ///
+    /// ```rust
+    /// # use reqwest::Error;
+    /// #
+    /// # async fn run() -> Result<(), Error> {
+    /// let client = reqwest::Client::new();
+    /// // sends `?page=2&key=secret`
+    /// let res = client.get("http://httpbin.org/get?page=1&key=secret")
+    ///     .query_replace("page", "2")
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_replace(mut self, key: &str, value: &str) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            replace_query_pair(req.url_mut(), key, Some(value));
+        }
+        self
+    }
+
+    /// Removes every occurrence of a query parameter from the URL.
+    ///
+    /// Keys are compared after percent-decoding, as with `query_replace`.
+    /// Nothing changes if `key` isn't in the query string.
+    pub fn query_remove(mut self, key: &str) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            replace_query_pair(req.url_mut(), key, None);
+        }
+        self
+    }
+
     /// Set HTTP version
     pub fn version(mut self, version: Version) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
@@ -382,6 +1055,9 @@ impl RequestBuilder {
     /// and also sets the `Content-Type: application/x-www-form-urlencoded`
     /// header.
     ///
+    /// The value can be anything `serde_urlencoded` can serialize, such as a
+    /// slice of pairs, a map, or a struct whose fields are all scalars.
+    ///
     /// ```rust
     /// # use reqwest::Error;
     /// # use std::collections::HashMap;
@@ -402,7 +1078,8 @@ impl RequestBuilder {
     /// # Errors
     ///
     /// This method fails if the passed value cannot be serialized into
-    /// url encoded format
+    /// url encoded format, such as a struct with a nested struct, sequence
+    /// or map as a field.
     pub fn form<T: Serialize + ?Sized>(mut self, form: &T) -> RequestBuilder {
         let mut error = None;
         if let Ok(ref mut req) = self.request {
@@ -423,6 +1100,45 @@ impl RequestBuilder {
         self
     }
 
+    /// Send a form body, choosing how sequences and nested values are
+    /// encoded.
+    ///
+    /// Like `form`, this sets the body to the url encoded serialization of
+    /// the passed value, and the `Content-Type:
+    /// application/x-www-form-urlencoded` header. Fields may also be
+    /// sequences, encoded as `options` says, and nested structs or maps,
+    /// encoded with brackets as `key[field]=value`. Fields that are `None`
+    /// are left out. See the [`form`](crate::form) module for an example.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the passed value isn't a map, a struct or a
+    /// sequence of pairs, or if a nested value contains bytes or a map key
+    /// that isn't a string or a number.
+    pub fn form_with<T: Serialize + ?Sized>(
+        mut self,
+        form: &T,
+        options: crate::form::FormOptions,
+    ) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match crate::form::to_string(form, &options) {
+                Ok(body) => {
+                    req.headers_mut().insert(
+                        CONTENT_TYPE,
+                        HeaderValue::from_static("application/x-www-form-urlencoded"),
+                    );
+                    *req.body_mut() = Some(body.into());
+                }
+                Err(err) => error = Some(crate::error::builder(err)),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
     /// Send a JSON body.
     ///
     /// # Optional
@@ -440,7 +1156,7 @@ impl RequestBuilder {
         if let Ok(ref mut req) = self.request {
             match serde_json::to_vec(json) {
                 Ok(body) => {
-                    if !req.headers().contains_key(CONTENT_TYPE) {
+                    if self.typed_body || !req.headers().contains_key(CONTENT_TYPE) {
                         req.headers_mut()
                             .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
                     }
@@ -455,6 +1171,73 @@ impl RequestBuilder {
         self
     }
 
+    /// Send a newline-delimited JSON body, with one line for each item of
+    /// `items`.
+    ///
+    /// The items are serialized one at a time, as the body is sent, so the
+    /// whole body is never held in memory. It is sent with chunked
+    /// transfer encoding, and an empty iterator sends an empty body. The
+    /// `Content-Type` is set to `application/x-ndjson`, unless one was
+    /// already set. The body is a stream, so it can't be sent again for a
+    /// retry or a `307`/`308` redirect.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let docs = (0..3).map(|id| serde_json::json!({ "id": id }));
+    /// let res = reqwest::Client::new()
+    ///     .post("http://localhost:9200/_bulk")
+    ///     .ndjson_body(docs)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// If an item fails to serialize, sending the request fails with a
+    /// body error.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn ndjson_body<I, T>(mut self, items: I) -> RequestBuilder
+    where
+        I: Iterator<Item = T> + Send + 'static,
+        T: Serialize,
+    {
+        if let Ok(ref mut req) = self.request {
+            if self.typed_body || !req.headers().contains_key(CONTENT_TYPE) {
+                req.headers_mut().insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/x-ndjson"),
+                );
+            }
+            *req.body_mut() = Some(Body::boxed(super::body::JsonLinesBody::new(items)));
+        }
+        self
+    }
+
+    /// Add a typed value to the request's extensions.
+    ///
+    /// Extensions aren't sent: they carry context for middleware, which can
+    /// read them with `Request::extensions`. They aren't copied to the
+    /// `Response` either, it is up to a middleware to do so where needed.
+    /// See the [`middleware`](crate::middleware) module for an example.
+    ///
+    /// A value of the same type added before is replaced.
+    pub fn extension<T>(mut self, value: T) -> RequestBuilder
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            req.extensions_mut().insert(value);
+        }
+        self
+    }
+
     /// Disable CORS on fetching the request.
     ///
     /// # WASM
@@ -511,6 +1294,78 @@ impl RequestBuilder {
         }
     }
 
+    /// Constructs the Request and sends it like `send`, also returning a
+    /// handle to abort it.
+    ///
+    /// Dropping the future cancels the request too, but the handle can be
+    /// kept by another task: calling `CancelHandle::abort` makes the future
+    /// resolve with an error for which `Error::is_aborted` is true.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() {
+    /// let (res, cancel) = reqwest::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .send_cancelable();
+    ///
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    ///     cancel.abort();
+    /// });
+    ///
+    /// match res.await {
+    ///     Ok(res) => println!("status: {}", res.status()),
+    ///     Err(err) if err.is_aborted() => println!("aborted"),
+    ///     Err(err) => println!("error: {err}"),
+    /// }
+    /// # }
+    /// ```
+    pub fn send_cancelable(
+        self,
+    ) -> (
+        impl Future<Output = Result<Response, crate::Error>>,
+        CancelHandle,
+    ) {
+        let url = self.request.as_ref().ok().map(|req| req.url().clone());
+        cancel::cancelable(self.send(), url)
+    }
+
+    /// Constructs the Request and sends it to the target URL, returning an
+    /// error if the response status is a client or server error.
+    ///
+    /// Unlike `Response::error_for_status`, the error keeps the start of the
+    /// response body, which is often where the server says what went wrong.
+    /// See `Error::response_body_snippet` and
+    /// `ClientBuilder::error_body_limit`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails for the same reasons as `send`, and if the status
+    /// is between 400 and 599.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() {
+    /// let res = reqwest::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .send_success()
+    ///     .await;
+    ///
+    /// if let Err(err) = res {
+    ///     if let Some(body) = err.response_body_snippet() {
+    ///         println!("{}: {}", err, String::from_utf8_lossy(body));
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn send_success(self) -> impl Future<Output = Result<Response, crate::Error>> {
+        let limit = self.client.error_body_limit();
+        let pending = self.send();
+        async move { pending.await?.error_for_status_with_body(limit).await }
+    }
+
     /// Attempt to clone the RequestBuilder.
     ///
     /// `None` is returned if the RequestBuilder can not be cloned,
@@ -538,10 +1393,17 @@ impl RequestBuilder {
             .map(|req| RequestBuilder {
                 client: self.client.clone(),
                 request: Ok(req),
+                typed_body: self.typed_body,
             })
     }
 }
 
+/// Checks that `mime` is a media type, for a `Content-Type` header.
+pub(crate) fn content_type(mime: &str) -> crate::Result<HeaderValue> {
+    mime.parse::<mime::Mime>().map_err(crate::error::builder)?;
+    HeaderValue::from_str(mime).map_err(crate::error::builder)
+}
+
 impl fmt::Debug for Request {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt_request_fields(&mut f.debug_struct("Request"), self).finish()
@@ -567,6 +1429,44 @@ fn fmt_request_fields<'a, 'b>(
         .field("headers", &req.headers)
 }
 
+/// Sets the first `key` pair of the query to `value`, removing the others,
+/// or appends it if there's none. With no `value`, all of them are removed.
+pub(crate) fn replace_query_pair(url: &mut Url, key: &str, value: Option<&str>) {
+    if !url.query_pairs().any(|(k, _)| k == key) {
+        if let Some(value) = value {
+            url.query_pairs_mut().append_pair(key, value);
+        }
+        return;
+    }
+
+    let mut value = value;
+    let pairs = url
+        .query_pairs()
+        .filter_map(|(k, v)| {
+            if k != key {
+                Some((k.into_owned(), v.into_owned()))
+            } else {
+                value.take().map(|value| (k.into_owned(), value.to_owned()))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+}
+
+/// Calls `token` and sets the `Authorization` header from it.
+pub(crate) fn set_bearer_token(headers: &mut HeaderMap, token: &TokenFn) -> crate::Result<()> {
+    let mut value =
+        HeaderValue::try_from(format!("Bearer {}", token())).map_err(crate::error::builder)?;
+    value.set_sensitive(true);
+    headers.insert(crate::header::AUTHORIZATION, value);
+    Ok(())
+}
+
 /// Check the request URL for a "username:password" type authority, and if
 /// found, remove it from the URL and return it.
 pub(crate) fn extract_authority(url: &mut Url) -> Option<(String, Option<String>)> {
@@ -608,6 +1508,7 @@ where
             uri,
             headers,
             version,
+            extensions,
             ..
         } = parts;
         let url = Url::parse(&uri.to_string()).map_err(crate::error::builder)?;
@@ -617,7 +1518,18 @@ where
             headers,
             body: Some(body.into()),
             timeout: None,
+            connect_timeout: None,
+            retry: None,
+            retry_body_buffer_limit: None,
+            #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+            compression: None,
+            decompress: true,
+            expect_continue: false,
+            bearer_token: None,
+            digest_auth: None,
+            timeout_fn: None,
             version,
+            extensions,
         })
     }
 }
@@ -632,6 +1544,7 @@ impl TryFrom<Request> for HttpRequest<Body> {
             headers,
             body,
             version,
+            extensions,
             ..
         } = req;
 
@@ -643,6 +1556,7 @@ impl TryFrom<Request> for HttpRequest<Body> {
             .map_err(crate::error::builder)?;
 
         *req.headers_mut() = headers;
+        *req.extensions_mut() = extensions;
         Ok(req)
     }
 }
@@ -719,6 +1633,113 @@ mod tests {
         assert_eq!(req.url().query(), Some("foo=bar&qux=three"));
     }
 
+    #[test]
+    fn add_form_struct() {
+        #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+        struct Login {
+            user: String,
+            remember: bool,
+            tries: Option<u32>,
+        }
+
+        let login = Login {
+            user: "a b&c".into(),
+            remember: true,
+            tries: Some(2),
+        };
+        let req = Client::new()
+            .post("https://google.com/")
+            .form(&login)
+            .build()
+            .expect("request is valid");
+
+        assert_eq!(
+            req.headers()["content-type"],
+            "application/x-www-form-urlencoded"
+        );
+        let body = req.body().unwrap().as_bytes().unwrap();
+        assert_eq!(body, b"user=a+b%26c&remember=true&tries=2");
+        assert_eq!(serde_urlencoded::from_bytes::<Login>(body).unwrap(), login);
+    }
+
+    #[test]
+    fn body_with_type_override_order() {
+        use crate::header::CONTENT_TYPE;
+
+        let client = Client::new();
+        let url = "https://google.com/";
+
+        let req = client
+            .post(url)
+            .header(CONTENT_TYPE, "text/plain")
+            .body_with_type("<a/>", mime::TEXT_XML)
+            .build()
+            .expect("request is valid");
+        assert_eq!(req.headers().get_all(CONTENT_TYPE).iter().count(), 1);
+        assert_eq!(req.headers()[CONTENT_TYPE], "text/xml");
+        assert_eq!(req.body().unwrap().as_bytes(), Some(&b"<a/>"[..]));
+
+        let req = client
+            .post(url)
+            .body_with_type("a", "text/plain")
+            .form(&[("a", "b")])
+            .build()
+            .expect("request is valid");
+        assert_eq!(
+            req.headers()[CONTENT_TYPE],
+            "application/x-www-form-urlencoded"
+        );
+
+        #[cfg(feature = "json")]
+        {
+            let req = client
+                .post(url)
+                .body_with_type("a", "text/plain")
+                .json(&1)
+                .build()
+                .expect("request is valid");
+            assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+
+            // a header set on its own is still kept by `json`
+            let req = client
+                .post(url)
+                .header(CONTENT_TYPE, "application/vnd.api+json")
+                .json(&1)
+                .build()
+                .expect("request is valid");
+            assert_eq!(req.headers()[CONTENT_TYPE], "application/vnd.api+json");
+        }
+
+        let err = client
+            .post(url)
+            .body_with_type("a", "not a mime")
+            .build()
+            .unwrap_err();
+        assert!(err.is_builder());
+    }
+
+    #[test]
+    fn add_form_nested_struct_is_error() {
+        #[derive(Serialize)]
+        struct Inner {
+            a: u32,
+        }
+
+        #[derive(Serialize)]
+        struct Outer {
+            inner: Inner,
+        }
+
+        let err = Client::new()
+            .post("https://google.com/")
+            .form(&Outer {
+                inner: Inner { a: 1 },
+            })
+            .build()
+            .unwrap_err();
+        assert!(err.is_builder());
+    }
+
     #[test]
     fn test_replace_headers() {
         use http::HeaderMap;
@@ -744,6 +1765,70 @@ mod tests {
         assert_eq!(foo[1], "baz");
     }
 
+    #[test]
+    fn query_replace_and_remove() {
+        let client = Client::new();
+        let some_url = "https://google.com/?key=a&page=1&key=b";
+
+        let req = client
+            .get(some_url)
+            .query_replace("key", "c")
+            .build()
+            .expect("request is valid");
+        assert_eq!(req.url().query(), Some("key=c&page=1"));
+
+        let req = client
+            .get(some_url)
+            .query_replace("new", "d")
+            .build()
+            .expect("request is valid");
+        assert_eq!(req.url().query(), Some("key=a&page=1&key=b&new=d"));
+
+        let req = client
+            .get(some_url)
+            .query_remove("key")
+            .build()
+            .expect("request is valid");
+        assert_eq!(req.url().query(), Some("page=1"));
+
+        let req = client
+            .get("https://google.com/?key=a")
+            .query_remove("key")
+            .query_remove("missing")
+            .build()
+            .expect("request is valid");
+        assert_eq!(req.url().as_str(), "https://google.com/");
+    }
+
+    #[test]
+    fn query_replace_encoded_keys() {
+        let client = Client::new();
+        let some_url = "https://google.com/?api%20key=a&q=x+y&api+key=b";
+
+        let req = client
+            .get(some_url)
+            .query_replace("api key", "c&d")
+            .build()
+            .expect("request is valid");
+        assert_eq!(req.url().query(), Some("api+key=c%26d&q=x+y"));
+    }
+
+    #[test]
+    fn query_replace_after_query() {
+        let client = Client::new();
+        let some_url = "https://google.com/";
+
+        let req = client
+            .get(some_url)
+            .query(&[("foo", "a"), ("foo", "b"), ("bar", "1")])
+            .query_replace("foo", "c")
+            .query(&[("foo", "d")])
+            .query_remove("bar")
+            .build()
+            .expect("request is valid");
+        assert_eq!(req.url().query(), Some("foo=c&foo=d"));
+    }
+
     #[test]
     fn normalize_empty_query() {
         let client = Client::new();
@@ -15,7 +15,11 @@ use url::Url;
 
 use super::body::Body;
 use super::decoder::{Accepts, Decoder};
+use super::expect::ExpectContinue;
 use crate::async_impl::body::ResponseBody;
+use crate::connect::ConnectionReused;
+use crate::redirect;
+use crate::{ContentRange, Timings};
 #[cfg(feature = "cookies")]
 use crate::cookie;
 
@@ -34,17 +38,18 @@ pub struct Response {
 
 impl Response {
     pub(super) fn new(
-        res: hyper::Response<hyper::body::Incoming>,
+        res: hyper::Response<ResponseBody>,
         url: Url,
         accepts: Accepts,
         timeout: Option<Pin<Box<Sleep>>>,
+        upload: Option<ExpectContinue>,
     ) -> Response {
         let (mut parts, body) = res.into_parts();
-        let decoder = Decoder::detect(
-            &mut parts.headers,
-            super::body::response(body, timeout),
-            accepts,
-        );
+        let mut body = super::body::response(body, timeout);
+        if let Some(upload) = upload {
+            body = upload.hold(body);
+        }
+        let decoder = Decoder::detect(&mut parts.headers, body, accepts);
         let res = hyper::Response::from_parts(parts, decoder);
 
         Response {
@@ -53,13 +58,49 @@ impl Response {
         }
     }
 
+    /// Turns a response to a single hop back into the response it wraps.
+    pub(super) fn into_hop(self) -> hyper::Response<ResponseBody> {
+        self.res.map(|body| body.map_err(Into::into).boxed())
+    }
+
+    /// Replaces the body of a response to a single hop.
+    pub(crate) fn map_hop_body<F>(self, f: F) -> Response
+    where
+        F: FnOnce(ResponseBody) -> ResponseBody,
+    {
+        let url = *self.url.clone();
+        let res = self.into_hop().map(f);
+        Response::new(res, url, Accepts::none(), None, None)
+    }
+
     /// Get the `StatusCode` of this `Response`.
     #[inline]
     pub fn status(&self) -> StatusCode {
         self.res.status()
     }
 
+    /// Get the reason phrase of the status line of this `Response`.
+    ///
+    /// This is the phrase the server sent, like `Not Found` in
+    /// `HTTP/1.1 404 Not Found`, even one that isn't the usual phrase for
+    /// the status. It is `None` for HTTP/2 and HTTP/3, which have no reason
+    /// phrase, and when the phrase isn't valid UTF-8.
+    pub fn reason(&self) -> Option<&str> {
+        match self.res.version() {
+            Version::HTTP_10 | Version::HTTP_11 => {}
+            _ => return None,
+        }
+        // hyper only keeps the phrase when it isn't the canonical one
+        match self.res.extensions().get::<hyper::ext::ReasonPhrase>() {
+            Some(reason) => std::str::from_utf8(reason.as_bytes()).ok(),
+            None => self.res.status().canonical_reason(),
+        }
+    }
+
     /// Get the HTTP `Version` of this `Response`.
+    ///
+    /// This is the protocol the response was actually received with, not the
+    /// one asked for. Over TLS, it is the one negotiated with ALPN.
     #[inline]
     pub fn version(&self) -> Version {
         self.res.version()
@@ -90,6 +131,32 @@ impl Response {
         Body::size_hint(self.res.body()).exact()
     }
 
+    /// Get the value of the `content-length` header, if the response has
+    /// one and isn't chunked.
+    ///
+    /// This is only a hint, such as for preallocating a buffer before
+    /// calling `bytes()`, since it is what the server claims. It is the
+    /// length of the body as sent, so if automatic decompression was
+    /// turned off, it isn't the decompressed length. When the body is
+    /// decompressed automatically, the header is removed, and this
+    /// returns `None`.
+    pub fn content_length_hint(&self) -> Option<u64> {
+        use http::header::{CONTENT_LENGTH, TRANSFER_ENCODING};
+
+        let headers = self.headers();
+        let chunked = headers.get_all(TRANSFER_ENCODING).iter().any(|value| {
+            value.to_str().map_or(false, |value| {
+                value
+                    .split(',')
+                    .any(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+            })
+        });
+        if chunked {
+            return None;
+        }
+        headers.get(CONTENT_LENGTH)?.to_str().ok()?.trim().parse().ok()
+    }
+
     /// Retrieve the cookies contained in the response.
     ///
     /// Note that invalid 'Set-Cookie' headers will be ignored.
@@ -110,6 +177,15 @@ impl Response {
     }
 
     /// Get the remote address used to get this `Response`.
+    ///
+    /// This is the TCP peer of the connection the response came over, as
+    /// captured when that connection was opened, so it stays the same for
+    /// every response pooled or multiplexed over HTTP/2 on it. Through a
+    /// proxy, including a tunnel opened with `CONNECT` for HTTPS, it is the
+    /// proxy's address, not the origin's.
+    ///
+    /// Responses that were not received over TCP, like those over a Unix
+    /// socket or built from an `http::Response`, return `None`.
     pub fn remote_addr(&self) -> Option<SocketAddr> {
         self.res
             .extensions()
@@ -117,6 +193,84 @@ impl Response {
             .map(|info| info.remote_addr())
     }
 
+    /// Returns whether this `Response` came over a connection that had
+    /// already been used.
+    ///
+    /// This is `false` for the first response on a newly opened connection,
+    /// and `true` when the connection was taken from the pool. For HTTP/2,
+    /// a new stream on an already open connection counts as reused. Among
+    /// concurrent streams opened on a new connection, only the first
+    /// response to arrive reports `false`.
+    ///
+    /// Responses that were not received from a connection, like those built
+    /// from an `http::Response`, report `false`.
+    pub fn connection_reused(&self) -> bool {
+        self.res
+            .extensions()
+            .get::<ConnectionReused>()
+            .map_or(false, |reused| reused.0)
+    }
+
+    /// Returns the redirects followed to get this `Response`, in order.
+    ///
+    /// Each `Hop` has the URL that was requested and the redirect status it
+    /// answered with. The final URL, given by `url`, is not included. This
+    /// is empty if no redirect was followed.
+    pub fn redirect_history(&self) -> &[redirect::Hop] {
+        self.res
+            .extensions()
+            .get::<redirect::History>()
+            .map_or(&[], |history| &history.0)
+    }
+
+    /// Returns how long each phase of this request took.
+    ///
+    /// This is `None` unless timings were enabled with
+    /// `ClientBuilder::collect_timings`.
+    pub fn timings(&self) -> Option<Timings> {
+        self.res.extensions().get::<Timings>().copied()
+    }
+
+    /// Returns the language of the content, from its `Content-Language`
+    /// header.
+    ///
+    /// This is the header as sent by the server, which may list several
+    /// languages, such as `de, en`. It is `None` if there is no such header,
+    /// or it isn't valid UTF-8.
+    pub fn content_language(&self) -> Option<&str> {
+        self.headers()
+            .get(crate::header::CONTENT_LANGUAGE)?
+            .to_str()
+            .ok()
+    }
+
+    /// Returns the byte range this `Response` holds, from its
+    /// `Content-Range` header.
+    ///
+    /// This is `None` unless the status is `206 Partial Content`. A server
+    /// that ignored a `Range` and answered `200 OK` sent the whole resource,
+    /// even if it also set a `Content-Range`.
+    pub fn content_range(&self) -> Option<ContentRange> {
+        ContentRange::from_response(self.status(), self.headers())
+    }
+
+    /// Returns the TLS details of the connection this `Response` came over.
+    ///
+    /// This is `None` for plain HTTP, and unless enabled with
+    /// `ClientBuilder::tls_info`.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_info(&self) -> Option<&crate::tls::TlsInfo> {
+        self.res.extensions().get::<crate::tls::TlsInfo>()
+    }
+
     /// Returns a reference to the associated extensions.
     pub fn extensions(&self) -> &http::Extensions {
         self.res.extensions()
@@ -127,6 +281,14 @@ impl Response {
         self.res.extensions_mut()
     }
 
+    /// Returns the extension of type `T`, if there is one.
+    ///
+    /// Extensions of the `Request` aren't copied to the response by
+    /// reqwest: a middleware has to insert them with `extensions_mut`.
+    pub fn extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.res.extensions().get::<T>()
+    }
+
     // body methods
 
     /// Get the full response text.
@@ -291,6 +453,102 @@ impl Response {
             .map(|buf| buf.to_bytes())
     }
 
+    /// Get the full response body as `Bytes`, failing if it is larger than
+    /// `max` bytes.
+    ///
+    /// A `Content-Length` above `max` fails before anything is read.
+    /// Otherwise, such as for chunked or decompressed bodies, the bytes are
+    /// counted as they arrive, and reading stops as soon as there are too
+    /// many, so no more than `max` bytes are ever buffered. The error is
+    /// one for which `Error::is_body_too_large` is true.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let bytes = reqwest::get("http://httpbin.org/ip")
+    ///     .await?
+    ///     .bytes_limited(64 * 1024)
+    ///     .await?;
+    ///
+    /// println!("bytes: {bytes:?}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn bytes_limited(self, max: usize) -> crate::Result<Bytes> {
+        self.with_body_limit(max as u64).bytes().await
+    }
+
+    /// Makes reading the body fail once more than `limit` bytes were read.
+    pub(super) fn with_body_limit(mut self, limit: u64) -> Response {
+        self.res = self.res.map(|body| body.limited(limit));
+        self
+    }
+
+    /// Get the trailer headers of the response.
+    ///
+    /// Trailers are sent after the body, so this reads whatever is left of
+    /// the response body and discards it before returning them. Call it once
+    /// you are done with the body. A response without trailers returns an
+    /// empty `HeaderMap`.
+    ///
+    /// Trailers are not available for a body that was decompressed because
+    /// of the `gzip`, `brotli`, `zstd` or `deflate` features.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut res = reqwest::get("https://hyper.rs").await?;
+    ///
+    /// let trailers = res.trailer_headers().await?;
+    /// if let Some(status) = trailers.get("grpc-status") {
+    ///     println!("grpc-status: {status:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn trailer_headers(&mut self) -> crate::Result<HeaderMap> {
+        Ok(self.read_trailers().await?.unwrap_or_default())
+    }
+
+    /// Consume the response, returning its trailer headers, or `None` if
+    /// the server sent none.
+    ///
+    /// Trailers only arrive once the body is exhausted, so whatever is
+    /// left of the body is read and discarded first, and an error reading
+    /// it is returned instead. This is how the `grpc-status` and
+    /// `grpc-message` of a gRPC response are read.
+    ///
+    /// Trailers are not available for a body that was decompressed because
+    /// of the `gzip`, `brotli`, `zstd` or `deflate` features.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::get("https://hyper.rs").await?;
+    ///
+    /// if let Some(trailers) = res.trailers().await? {
+    ///     println!("grpc-status: {:?}", trailers.get("grpc-status"));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn trailers(mut self) -> crate::Result<Option<HeaderMap>> {
+        self.read_trailers().await
+    }
+
+    async fn read_trailers(&mut self) -> crate::Result<Option<HeaderMap>> {
+        let mut trailers: Option<HeaderMap> = None;
+        while let Some(frame) = self.res.body_mut().frame().await {
+            if let Ok(more) = frame?.into_trailers() {
+                trailers.get_or_insert_with(HeaderMap::new).extend(more);
+            }
+        }
+        Ok(trailers)
+    }
+
     /// Stream a chunk of the response body.
     ///
     /// When the response body has been exhausted, this will return `None`.
@@ -324,6 +582,58 @@ impl Response {
         }
     }
 
+    /// Copy the response body into an async writer.
+    ///
+    /// The body is written chunk by chunk as it arrives, decompressed if
+    /// automatic decompression applies, and the writer is flushed at the
+    /// end. On success, the total number of bytes written is returned. The
+    /// request's timeout covers the whole copy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::get("http://httpbin.org/range/26").await?;
+    /// let mut file = tokio::io::sink();
+    /// let written = res.copy_to(&mut file).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error reading the body is returned as from `chunk`. An error
+    /// from `writer` is returned as a body error, for which
+    /// `Error::is_body` is true, with the `io::Error` as its source.
+    pub async fn copy_to<W>(mut self, writer: &mut W) -> crate::Result<u64>
+    where
+        W: tokio::io::AsyncWrite + Unpin + ?Sized,
+    {
+        use futures_util::future::poll_fn;
+
+        let url = self.url().clone();
+        let write_error =
+            |e: std::io::Error| crate::error::body(e).with_url(url.clone());
+        let mut written = 0;
+        while let Some(chunk) = self.chunk().await? {
+            let mut buf = &chunk[..];
+            while !buf.is_empty() {
+                let n = poll_fn(|cx| Pin::new(&mut *writer).poll_write(cx, buf))
+                    .await
+                    .map_err(write_error)?;
+                if n == 0 {
+                    return Err(write_error(std::io::ErrorKind::WriteZero.into()));
+                }
+                buf = &buf[n..];
+                written += n as u64;
+            }
+        }
+        poll_fn(|cx| Pin::new(&mut *writer).poll_flush(cx))
+            .await
+            .map_err(write_error)?;
+        Ok(written)
+    }
+
     /// Convert the response into a `Stream` of `Bytes` from the body.
     ///
     /// # Example
@@ -352,6 +662,171 @@ impl Response {
         super::body::DataStream(self.res.into_body())
     }
 
+    /// Convert the response into a `Stream` of `Bytes` from the body, with
+    /// chunks of at most `max_buffered` bytes.
+    ///
+    /// The body is only read from the connection as the stream is polled,
+    /// and no more than `max_buffered` bytes received from it are held by
+    /// the stream at once. A consumer that falls behind, like a slow disk,
+    /// leaves the rest in the socket, which shrinks the TCP window and
+    /// slows the server down instead of growing memory use.
+    ///
+    /// The connection keeps its own read buffer, of a few hundred KB at
+    /// most, on top of `max_buffered`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut stream = reqwest::get("http://httpbin.org/stream-bytes/100000")
+    ///     .await?
+    ///     .bytes_stream_capped(16 * 1024);
+    ///
+    /// while let Some(chunk) = stream.next().await {
+    ///     assert!(chunk?.len() <= 16 * 1024);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max_buffered` is zero.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn bytes_stream_capped(
+        self,
+        max_buffered: usize,
+    ) -> impl futures_core::Stream<Item = crate::Result<Bytes>> {
+        super::body::CappedDataStream::new(self.res.into_body(), max_buffered)
+    }
+
+    /// Convert the response into a `Stream` of values, deserialized from
+    /// each line of a newline-delimited JSON body.
+    ///
+    /// Lines are read as the body arrives, so they may span any number of
+    /// chunks. Blank lines are skipped. A line that fails to deserialize is
+    /// returned as an error item, and the lines after it are still read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    /// # use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Event {
+    ///     id: u64,
+    /// }
+    ///
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let mut events = reqwest::get("http://httpbin.org/stream/20")
+    ///     .await?
+    ///     .json_lines_stream::<Event>();
+    ///
+    /// while let Some(event) = events.next().await {
+    ///     println!("event: {}", event?.id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json_lines_stream<T: DeserializeOwned>(
+        self,
+    ) -> impl futures_core::Stream<Item = crate::Result<T>> {
+        super::body::JsonLines::new(super::body::DataStream(self.res.into_body()))
+    }
+
+    /// Convert the response into a `Stream` of values, deserialized from an
+    /// `application/x-ndjson` body.
+    ///
+    /// This is the same as `json_lines_stream`, under the name of the media
+    /// type. An empty body ends the stream without any item.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn ndjson_stream<T: DeserializeOwned>(
+        self,
+    ) -> impl futures_core::Stream<Item = crate::Result<T>> {
+        self.json_lines_stream()
+    }
+
+    /// Convert the response into a `Stream` of server-sent events, parsed
+    /// from a `text/event-stream` body.
+    ///
+    /// Events are read as the body arrives. Multiple `data` lines are joined
+    /// with `\n`, comment lines are skipped, and both `\n` and `\r\n` line
+    /// endings are accepted. If the body ends before the blank line that
+    /// finishes an event, that event is returned as a decode error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let mut events = reqwest::get("http://example.com/events")
+    ///     .await?
+    ///     .sse_stream();
+    ///
+    /// while let Some(event) = events.next().await {
+    ///     println!("data: {}", event?.data);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `sse` feature enabled.
+    #[cfg(feature = "sse")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sse")))]
+    pub fn sse_stream(self) -> impl futures_core::Stream<Item = crate::Result<super::SseEvent>> {
+        super::sse::SseStream::new(super::body::DataStream(self.res.into_body()))
+    }
+
+    /// Convert the response into an `AsyncRead` of the body.
+    ///
+    /// The body is read as it arrives, without being buffered in full. Any
+    /// error is returned as an `io::Error` wrapping the `reqwest::Error`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut body = reqwest::get("http://httpbin.org/ip")
+    ///     .await?
+    ///     .into_async_read();
+    ///
+    /// let mut file = tokio::io::sink();
+    /// tokio::io::copy(&mut body, &mut file).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn into_async_read(self) -> impl tokio::io::AsyncRead + Send + Sync + Unpin {
+        tokio_util::io::StreamReader::new(self.res.into_body().into_stream())
+    }
+
     // util methods
 
     /// Turn a response into an error if the server returned an error.
@@ -416,6 +891,32 @@ impl Response {
 
     // private
 
+    /// Like `error_for_status`, but the error keeps up to `limit` bytes of
+    /// the body.
+    pub(crate) async fn error_for_status_with_body(mut self, limit: usize) -> crate::Result<Self> {
+        let status = self.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(self);
+        }
+
+        let mut snippet = bytes::BytesMut::new();
+        // a body that fails part way still gives what was read
+        while snippet.len() < limit {
+            match self.chunk().await {
+                Ok(Some(chunk)) => {
+                    let n = chunk.len().min(limit - snippet.len());
+                    snippet.extend_from_slice(&chunk[..n]);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+        Err(crate::error::status_code_with_body(
+            *self.url,
+            status,
+            snippet.freeze(),
+        ))
+    }
+
     // The Response's body is an implementation detail.
     // You no longer need to get a reference to it, there are async methods
     // on the `Response` itself.
@@ -499,5 +1000,6 @@ mod tests {
 
         assert_eq!(response.status(), 200);
         assert_eq!(*response.url(), url);
+        assert_eq!(response.remote_addr(), None);
     }
 }
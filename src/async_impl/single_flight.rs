@@ -0,0 +1,272 @@
+//! Sharing one request between identical concurrent ones, as enabled with
+//! `ClientBuilder::single_flight`.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use http::header::{HeaderMap, HeaderName, ACCEPT, ACCEPT_LANGUAGE, AUTHORIZATION, COOKIE, RANGE};
+use http::{Extensions, Method, StatusCode, Version};
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use http_body_util::{BodyExt, Full};
+use tokio::sync::watch;
+use url::Url;
+
+use super::body::ResponseBody;
+use super::decoder::Accepts;
+use super::{Request, Response};
+use crate::error::BoxError;
+
+/// Computes the key of a request, requests with the same one being shared.
+pub(crate) type KeyFn = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>;
+
+/// The settings of `ClientBuilder`.
+pub(crate) struct Config {
+    pub(crate) enabled: bool,
+    pub(crate) headers: Vec<HeaderName>,
+    pub(crate) max_body: usize,
+    pub(crate) key: Option<KeyFn>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            enabled: false,
+            headers: vec![ACCEPT, ACCEPT_LANGUAGE, AUTHORIZATION, COOKIE, RANGE],
+            max_body: 1024 * 1024,
+            key: None,
+        }
+    }
+}
+
+/// The requests of a `Client` in flight, by key.
+pub(crate) struct SingleFlight {
+    headers: Vec<HeaderName>,
+    max_body: usize,
+    key: Option<KeyFn>,
+    flights: Mutex<HashMap<String, watch::Receiver<Option<Landed>>>>,
+}
+
+/// How the request of a flight ended, for those waiting on it.
+#[derive(Clone)]
+enum Landed {
+    Response(Arc<Buffered>),
+    // the response wasn't buffered, or there was none
+    Alone,
+}
+
+struct Buffered {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    extensions: Extensions,
+    url: Url,
+    body: Bytes,
+}
+
+impl Buffered {
+    fn response(&self) -> Response {
+        let mut res = hyper::Response::new(Full::new(self.body.clone()).map_err(Into::into).boxed());
+        *res.status_mut() = self.status;
+        *res.version_mut() = self.version;
+        *res.headers_mut() = self.headers.clone();
+        *res.extensions_mut() = self.extensions.clone();
+        Response::new(res, self.url.clone(), Accepts::none(), None, None)
+    }
+}
+
+impl SingleFlight {
+    pub(crate) fn new(config: Config) -> Option<SingleFlight> {
+        if !config.enabled {
+            return None;
+        }
+        Some(SingleFlight {
+            headers: config.headers,
+            max_body: config.max_body,
+            key: config.key,
+            flights: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The key of a request that can be shared, or `None` if it is sent on
+    /// its own.
+    pub(crate) fn key(&self, req: &Request) -> Option<String> {
+        let safe = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+        if !safe || req.body().is_some() {
+            return None;
+        }
+        if let Some(ref key) = self.key {
+            return key(req);
+        }
+
+        let mut key = format!("{} {}", req.method(), req.url());
+        for name in &self.headers {
+            for value in req.headers().get_all(name) {
+                key.push('\n');
+                key.push_str(name.as_str());
+                key.push(':');
+                key.push_str(&String::from_utf8_lossy(value.as_bytes()));
+            }
+        }
+        Some(key)
+    }
+
+    /// Sends `req` with `send`, unless a request with the same key is in
+    /// flight, whose response is then shared.
+    pub(crate) async fn run<F, Fut>(&self, key: String, req: Request, send: F) -> crate::Result<Response>
+    where
+        F: FnOnce(Request) -> Fut,
+        Fut: Future<Output = crate::Result<Response>>,
+    {
+        let joined = {
+            let mut flights = self.flights.lock().unwrap();
+            match flights.get(&key) {
+                Some(flight) => Ok(flight.clone()),
+                None => {
+                    let (tx, rx) = watch::channel(None);
+                    flights.insert(key.clone(), rx);
+                    Err(tx)
+                }
+            }
+        };
+
+        let tx = match joined {
+            Ok(flight) => {
+                return match wait(flight).await {
+                    Some(Landed::Response(buffered)) => Ok(buffered.response()),
+                    _ => send(req).await,
+                };
+            }
+            Err(tx) => tx,
+        };
+
+        // a new request with this key is its own flight, even if this one
+        // is dropped before landing
+        let _leaving = Leaving {
+            flights: &self.flights,
+            key,
+        };
+        let (res, landed) = match send(req).await {
+            Ok(res) => self.buffer(res).await,
+            Err(err) => (Err(err), Landed::Alone),
+        };
+        let _ = tx.send(Some(landed));
+        res
+    }
+
+    /// Reads the body of `res`, if it isn't larger than allowed.
+    async fn buffer(&self, res: Response) -> (crate::Result<Response>, Landed) {
+        let too_large = res.content_length().map_or(false, |len| len > self.max_body as u64);
+        if too_large {
+            return (Ok(res), Landed::Alone);
+        }
+
+        let url = res.url().clone();
+        let (parts, mut body) = res.into_hop().into_parts();
+        let mut read = BytesMut::new();
+        let error = loop {
+            match body.frame().await {
+                Some(Ok(frame)) => {
+                    if let Ok(data) = frame.into_data() {
+                        read.extend_from_slice(&data);
+                    }
+                    if read.len() > self.max_body {
+                        break None;
+                    }
+                }
+                Some(Err(err)) => break Some(err),
+                None => {
+                    let buffered = Buffered {
+                        status: parts.status,
+                        version: parts.version,
+                        headers: parts.headers.clone(),
+                        extensions: parts.extensions.clone(),
+                        url,
+                        body: read.freeze(),
+                    };
+                    let res = buffered.response();
+                    return (Ok(res), Landed::Response(Arc::new(buffered)));
+                }
+            }
+        };
+
+        // the caller gets the rest of the body as if nothing was read
+        let resumed = Resumed {
+            read: Some(read.freeze()).filter(|read| !read.is_empty()),
+            error,
+            rest: body,
+        };
+        let res = hyper::Response::from_parts(parts, resumed.boxed());
+        (
+            Ok(Response::new(res, url, Accepts::none(), None, None)),
+            Landed::Alone,
+        )
+    }
+}
+
+/// Waits for the request of a flight, or `None` if it was dropped.
+async fn wait(mut flight: watch::Receiver<Option<Landed>>) -> Option<Landed> {
+    loop {
+        if let Some(ref landed) = *flight.borrow() {
+            return Some(landed.clone());
+        }
+        if flight.changed().await.is_err() {
+            return flight.borrow().clone();
+        }
+    }
+}
+
+struct Leaving<'a> {
+    flights: &'a Mutex<HashMap<String, watch::Receiver<Option<Landed>>>>,
+    key: String,
+}
+
+impl Drop for Leaving<'_> {
+    fn drop(&mut self) {
+        let mut flights = self.flights.lock().unwrap_or_else(|e| e.into_inner());
+        flights.remove(&self.key);
+    }
+}
+
+/// A response body of which the start was read already.
+struct Resumed {
+    read: Option<Bytes>,
+    error: Option<BoxError>,
+    rest: ResponseBody,
+}
+
+impl HttpBody for Resumed {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(read) = self.read.take() {
+            return Poll::Ready(Some(Ok(Frame::data(read))));
+        }
+        if let Some(error) = self.error.take() {
+            return Poll::Ready(Some(Err(error)));
+        }
+        Pin::new(&mut self.rest).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.read.is_none() && self.error.is_none() && self.rest.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let read = self.read.as_ref().map_or(0, |read| read.len() as u64);
+        let rest = self.rest.size_hint();
+        let mut hint = SizeHint::new();
+        hint.set_lower(rest.lower() + read);
+        if let Some(upper) = rest.upper() {
+            hint.set_upper(upper + read);
+        }
+        hint
+    }
+}
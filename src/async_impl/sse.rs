@@ -0,0 +1,241 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+
+/// An event read from a `text/event-stream` response.
+///
+/// See `Response::sse_stream`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SseEvent {
+    /// The `data` field. Multiple `data` lines are joined with `\n`.
+    pub data: String,
+    /// The `event` field, if one was sent.
+    pub event: Option<String>,
+    /// The `id` field, if one was sent.
+    pub id: Option<String>,
+    /// The `retry` field, if one was sent and is a valid number of
+    /// milliseconds.
+    pub retry: Option<Duration>,
+}
+
+/// Parses a stream of chunks as server-sent events.
+pub(crate) struct SseStream<S> {
+    chunks: Option<S>,
+    buf: BytesMut,
+    // how much of `buf` is known to have no newline
+    scanned: usize,
+    // whether the leading byte order mark has been checked for
+    started: bool,
+    // the event being built, if any field has been seen yet
+    pending: Option<Pending>,
+}
+
+#[derive(Default)]
+struct Pending {
+    event: SseEvent,
+    has_data: bool,
+}
+
+impl<S> SseStream<S> {
+    pub(crate) fn new(chunks: S) -> SseStream<S> {
+        SseStream {
+            chunks: Some(chunks),
+            buf: BytesMut::new(),
+            scanned: 0,
+            started: false,
+            pending: None,
+        }
+    }
+
+    /// Takes the next complete line out of the buffer, without its line
+    /// ending.
+    fn next_line(&mut self) -> Option<BytesMut> {
+        if !self.started {
+            // wait to decide until a BOM could be told apart from the data
+            if self.buf.len() < 3 && self.chunks.is_some() {
+                return None;
+            }
+            if self.buf.starts_with(b"\xEF\xBB\xBF") {
+                let _ = self.buf.split_to(3);
+            }
+            self.started = true;
+        }
+
+        match self.buf[self.scanned..].iter().position(|&b| b == b'\n') {
+            Some(pos) => {
+                let mut line = self.buf.split_to(self.scanned + pos + 1);
+                self.scanned = 0;
+                line.truncate(line.len() - 1);
+                if line.ends_with(b"\r") {
+                    line.truncate(line.len() - 1);
+                }
+                Some(line)
+            }
+            None => {
+                self.scanned = self.buf.len();
+                None
+            }
+        }
+    }
+
+    /// Applies a line to the pending event, returning the event if the line
+    /// ended it.
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.pending.take().map(|pending| pending.event);
+        }
+        // comment
+        if line[0] == b':' {
+            return None;
+        }
+
+        let (name, value) = match line.iter().position(|&b| b == b':') {
+            Some(pos) => {
+                let value = &line[pos + 1..];
+                (&line[..pos], value.strip_prefix(b" ").unwrap_or(value))
+            }
+            None => (line, &[][..]),
+        };
+        let value = String::from_utf8_lossy(value);
+
+        let pending = self.pending.get_or_insert_with(Pending::default);
+        match name {
+            b"data" => {
+                if pending.has_data {
+                    pending.event.data.push('\n');
+                }
+                pending.event.data.push_str(&value);
+                pending.has_data = true;
+            }
+            b"event" => pending.event.event = Some(value.into_owned()),
+            b"id" if !value.contains('\0') => pending.event.id = Some(value.into_owned()),
+            b"retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(ms) = value.parse() {
+                    pending.event.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            // unknown fields, and invalid `id` or `retry` values, are ignored
+            _ => (),
+        }
+        None
+    }
+}
+
+impl<S> futures_core::Stream for SseStream<S>
+where
+    S: futures_core::Stream<Item = crate::Result<Bytes>> + Unpin,
+{
+    type Item = crate::Result<SseEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            while let Some(line) = self.next_line() {
+                if let Some(event) = self.process_line(&line) {
+                    return Poll::Ready(Some(Ok(event)));
+                }
+            }
+
+            let chunks = match self.chunks.as_mut() {
+                Some(chunks) => chunks,
+                None => {
+                    // an event (or line) that was never ended by a blank line
+                    if self.pending.take().is_some() || !self.buf.is_empty() {
+                        self.buf.clear();
+                        self.scanned = 0;
+                        return Poll::Ready(Some(Err(crate::error::decode(
+                            "event stream ended in the middle of an event",
+                        ))));
+                    }
+                    return Poll::Ready(None);
+                }
+            };
+            match futures_core::ready!(Pin::new(chunks).poll_next(cx)) {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => self.chunks = None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures_util::{stream, FutureExt, StreamExt};
+
+    use super::{SseEvent, SseStream};
+
+    fn parse(chunks: &[&'static str]) -> Vec<crate::Result<SseEvent>> {
+        let chunks = chunks
+            .iter()
+            .map(|s| Ok(bytes::Bytes::from_static(s.as_bytes())))
+            .collect::<Vec<_>>();
+        SseStream::new(stream::iter(chunks))
+            .collect::<Vec<_>>()
+            .now_or_never()
+            .unwrap()
+    }
+
+    fn event(data: &str) -> SseEvent {
+        SseEvent {
+            data: data.to_owned(),
+            ..SseEvent::default()
+        }
+    }
+
+    #[test]
+    fn fields() {
+        let events = parse(&[
+            ": a comment\n",
+            "event: update\nid: 7\nretry: 1500\ndata: {\"a\":1}\n\n",
+            "data:no space\n\n",
+        ]);
+        let events = events.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                SseEvent {
+                    data: "{\"a\":1}".to_owned(),
+                    event: Some("update".to_owned()),
+                    id: Some("7".to_owned()),
+                    retry: Some(Duration::from_millis(1500)),
+                },
+                event("no space"),
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_line_data_across_chunks() {
+        let events = parse(&["\u{feff}da", "ta: one\r\ndata: two\r", "\n\r\ndata\n\n"]);
+        let events = events.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(events, [event("one\ntwo"), event("")]);
+    }
+
+    #[test]
+    fn invalid_retry_is_ignored() {
+        let events = parse(&["retry: soon\ndata: x\n\n"]);
+        assert_eq!(events[0].as_ref().unwrap(), &event("x"));
+    }
+
+    #[test]
+    fn unterminated_event_is_error() {
+        let events = parse(&["data: one\n\ndata: two\n"]);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_ref().unwrap(), &event("one"));
+        assert!(events[1].as_ref().unwrap_err().is_decode());
+
+        let events = parse(&["data: one\n\ndata: tw"]);
+        assert!(events[1].as_ref().unwrap_err().is_decode());
+    }
+
+    #[test]
+    fn empty_stream() {
+        assert!(parse(&[]).is_empty());
+        assert!(parse(&[": keep-alive\n\n"]).is_empty());
+    }
+}
@@ -3,6 +3,8 @@ use std::task::{self, Poll};
 use std::{fmt, io};
 
 use futures_util::TryFutureExt;
+use http::header::{HeaderMap, CONNECTION, UPGRADE};
+use http::StatusCode;
 use hyper_util::rt::TokioIo;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
@@ -66,11 +68,94 @@ impl From<hyper::upgrade::Upgraded> for Upgraded {
 }
 
 impl super::response::Response {
-    /// Consumes the response and returns a future for a possible HTTP upgrade.
+    /// Consumes the response and returns the connection, once the server
+    /// has switched to the protocol asked for with an `Upgrade` header.
+    ///
+    /// The response must be a `101 Switching Protocols`, with a
+    /// `Connection: upgrade` header and an `Upgrade` header naming the new
+    /// protocol, or an error is returned. The connection then belongs to the
+    /// new protocol: it isn't read as a response body, nor returned to the
+    /// pool. Only HTTP/1.1 connections can be upgraded.
+    ///
+    /// # Example
+    ///
+    /// The HTTP part of a WebSocket handshake; framing is left to the
+    /// caller.
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// use reqwest::header::{CONNECTION, UPGRADE};
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// let res = reqwest::Client::new()
+    ///     .get("http://127.0.0.1:9001/chat")
+    ///     .header(CONNECTION, "upgrade")
+    ///     .header(UPGRADE, "websocket")
+    ///     .header("sec-websocket-version", "13")
+    ///     .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+    ///     .send()
+    ///     .await?;
+    ///
+    /// // a client should check `sec-websocket-accept` before going on
+    /// let accept = res.headers().get("sec-websocket-accept").cloned();
+    /// assert_eq!(accept.unwrap(), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    ///
+    /// let mut socket = res.upgrade().await?;
+    /// // a masked, empty text frame
+    /// socket.write_all(&[0x81, 0x80, 0, 0, 0, 0]).await.ok();
+    /// # Ok(())
+    /// # }
+    /// ```
     pub async fn upgrade(self) -> crate::Result<Upgraded> {
+        if let Err(msg) = check_upgrade(self.status(), self.headers()) {
+            return Err(crate::error::upgrade(msg).with_url(self.url().clone()));
+        }
         hyper::upgrade::on(self.res)
             .map_ok(Upgraded::from)
             .map_err(crate::error::upgrade)
             .await
     }
 }
+
+/// Checks that a response switches protocols.
+fn check_upgrade(status: StatusCode, headers: &HeaderMap) -> Result<(), &'static str> {
+    if status != StatusCode::SWITCHING_PROTOCOLS {
+        return Err("response status is not 101 Switching Protocols");
+    }
+    let connection_upgrade = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+    if !connection_upgrade {
+        return Err("response has no `Connection: upgrade` header");
+    }
+    match headers.get(UPGRADE) {
+        Some(protocol) if !protocol.is_empty() => Ok(()),
+        _ => Err("response has no `Upgrade` header"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_upgrade;
+    use http::header::{HeaderMap, HeaderValue, CONNECTION, UPGRADE};
+    use http::StatusCode;
+
+    #[test]
+    fn upgrade_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONNECTION, HeaderValue::from_static("keep-alive, Upgrade"));
+        headers.insert(UPGRADE, HeaderValue::from_static("websocket"));
+        assert!(check_upgrade(StatusCode::SWITCHING_PROTOCOLS, &headers).is_ok());
+        assert!(check_upgrade(StatusCode::OK, &headers).is_err());
+
+        let mut no_upgrade = headers.clone();
+        no_upgrade.remove(UPGRADE);
+        assert!(check_upgrade(StatusCode::SWITCHING_PROTOCOLS, &no_upgrade).is_err());
+
+        headers.insert(CONNECTION, HeaderValue::from_static("keep-alive"));
+        assert!(check_upgrade(StatusCode::SWITCHING_PROTOCOLS, &headers).is_err());
+    }
+}
@@ -3,7 +3,7 @@ use std::any::Any;
 use std::convert::TryInto;
 use std::fmt;
 use std::future::Future;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::thread;
@@ -22,7 +22,8 @@ use crate::tls;
 use crate::Certificate;
 #[cfg(any(feature = "native-tls", feature = "__rustls"))]
 use crate::Identity;
-use crate::{async_impl, header, redirect, IntoUrl, Method, Proxy};
+use crate::pool::PoolCounters;
+use crate::{async_impl, header, redirect, IntoUrl, Method, PoolStats, Proxy, Url};
 
 /// A `Client` to make Requests with.
 ///
@@ -69,6 +70,7 @@ pub struct Client {
 pub struct ClientBuilder {
     inner: async_impl::ClientBuilder,
     timeout: Timeout,
+    error_body_limit: usize,
 }
 
 impl Default for ClientBuilder {
@@ -85,6 +87,7 @@ impl ClientBuilder {
         ClientBuilder {
             inner: async_impl::ClientBuilder::new(),
             timeout: Timeout::default(),
+            error_body_limit: DEFAULT_ERROR_BODY_LIMIT,
         }
     }
 
@@ -135,6 +138,9 @@ impl ClientBuilder {
 
     /// Sets the default headers for every request.
     ///
+    /// Any header set on a request replaces all the default values of the
+    /// same name, instead of being added to them.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -262,6 +268,28 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.deflate(enable))
     }
 
+    /// Enable auto zstd decompression by checking the `Content-Encoding` response header.
+    ///
+    /// If auto zstd decompression is turned on:
+    ///
+    /// - When sending a request and if the request's headers do not already contain
+    ///   an `Accept-Encoding` **and** `Range` values, the `Accept-Encoding` header is set to `zstd`.
+    ///   The request body is **not** automatically compressed.
+    /// - When receiving a response, if its headers contain a `Content-Encoding` value of
+    ///   `zstd`, both `Content-Encoding` and `Content-Length` are removed from the
+    ///   headers' set. The response body is automatically decompressed.
+    ///
+    /// If the `zstd` feature is turned on, the default option is enabled.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `zstd` feature to be enabled
+    #[cfg(feature = "zstd")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+    pub fn zstd(self, enable: bool) -> ClientBuilder {
+        self.with_inner(|inner| inner.zstd(enable))
+    }
+
     /// Disable auto response body gzip decompression.
     ///
     /// This method exists even if the optional `gzip` feature is not enabled.
@@ -289,6 +317,36 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.no_deflate())
     }
 
+    /// Disable auto response body zstd decompression.
+    ///
+    /// This method exists even if the optional `zstd` feature is not enabled.
+    /// This can be used to ensure a `Client` doesn't use zstd decompression
+    /// even if another dependency were to enable the optional `zstd` feature.
+    pub fn no_zstd(self) -> ClientBuilder {
+        self.with_inner(|inner| inner.no_zstd())
+    }
+
+    /// Compress request bodies with `encoding` by default.
+    ///
+    /// This applies to every request with a body, unless the request sets
+    /// its own with `RequestBuilder::compress_body`, or already has a
+    /// `Content-Encoding` header.
+    ///
+    /// Default is to not compress request bodies.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `gzip`, `brotli` or `zstd` feature to be
+    /// enabled, for the matching `ContentEncoding`.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "gzip", feature = "brotli", feature = "zstd")))
+    )]
+    pub fn compress_body(self, encoding: crate::ContentEncoding) -> ClientBuilder {
+        self.with_inner(move |inner| inner.compress_body(encoding))
+    }
+
     // Redirect options
 
     /// Set a `redirect::Policy` for this client.
@@ -327,6 +385,13 @@ impl ClientBuilder {
         self.with_inner(move |inner| inner.no_proxy())
     }
 
+    /// Sets a `No Proxy` exclusion list for every proxy that has none.
+    ///
+    /// See `reqwest::ClientBuilder::no_proxy_list` for details.
+    pub fn no_proxy_list(self, no_proxy: crate::NoProxy) -> ClientBuilder {
+        self.with_inner(move |inner| inner.no_proxy_list(no_proxy))
+    }
+
     // Timeout options
 
     /// Set a timeout for connect, read and write operations of a `Client`.
@@ -342,6 +407,22 @@ impl ClientBuilder {
         self
     }
 
+    /// Set how many bytes of the body `RequestBuilder::send_success` keeps
+    /// in its error, when the response is a client or server error.
+    ///
+    /// Default is 8 KiB.
+    pub fn error_body_limit(mut self, limit: usize) -> ClientBuilder {
+        self.error_body_limit = limit;
+        self
+    }
+
+    /// Set the largest response body, in bytes, that can be read.
+    ///
+    /// See `reqwest::ClientBuilder::max_response_size` for details.
+    pub fn max_response_size(self, max: usize) -> ClientBuilder {
+        self.with_inner(move |inner| inner.max_response_size(max))
+    }
+
     /// Set a timeout for only the connect phase of a `Client`.
     ///
     /// Default is `None`.
@@ -357,16 +438,65 @@ impl ClientBuilder {
         }
     }
 
+    /// Set how long a request sent with `RequestBuilder::expect_continue`
+    /// waits for `100 Continue` before sending its body anyway.
+    ///
+    /// Default is 1 second.
+    pub fn expect_continue_timeout(self, timeout: Duration) -> ClientBuilder {
+        self.with_inner(|inner| inner.expect_continue_timeout(timeout))
+    }
+
     /// Set whether connections should emit verbose logs.
     ///
     /// Enabling this option will emit [log][] messages at the `TRACE` level
     /// for read and write operations on connections.
     ///
+    /// It also emits [tracing][] events at the `TRACE` level, with the
+    /// target `reqwest::connection`, as each connection goes through DNS
+    /// resolution, the TCP connect and the TLS handshake, each with a
+    /// started and a completed event, sends its first byte, receives its
+    /// first byte, and is closed, and for each response, as a connection
+    /// pool hit or miss. The events have the fields `url.host`,
+    /// `remote.addr`, `duration_ms` and `http.version`, once they are known.
+    ///
     /// [log]: https://crates.io/crates/log
+    /// [tracing]: https://crates.io/crates/tracing
     pub fn connection_verbose(self, verbose: bool) -> ClientBuilder {
         self.with_inner(move |inner| inner.connection_verbose(verbose))
     }
 
+    /// Set whether to measure how long each phase of a request takes.
+    ///
+    /// When enabled, `Response::timings` reports the time spent on DNS,
+    /// connecting, the TLS handshake and waiting for the response.
+    ///
+    /// Default is `false`.
+    pub fn collect_timings(self, enabled: bool) -> ClientBuilder {
+        self.with_inner(move |inner| inner.collect_timings(enabled))
+    }
+
+    /// Sets a function to call each time the client opens a connection.
+    ///
+    /// It runs on the client's background runtime, and should return
+    /// quickly. See `reqwest::ClientBuilder::on_new_connection` for details.
+    pub fn on_new_connection<F>(self, hook: F) -> ClientBuilder
+    where
+        F: Fn(crate::ConnectionInfo) + Send + Sync + 'static,
+    {
+        self.with_inner(move |inner| inner.on_new_connection(hook))
+    }
+
+    /// Sets a function to call each time a request is sent over a pooled
+    /// connection that was opened earlier.
+    ///
+    /// See `reqwest::ClientBuilder::on_connection_reuse` for details.
+    pub fn on_connection_reuse<F>(self, hook: F) -> ClientBuilder
+    where
+        F: Fn(crate::ConnectionInfo) + Send + Sync + 'static,
+    {
+        self.with_inner(move |inner| inner.on_connection_reuse(hook))
+    }
+
     // HTTP options
 
     /// Set an optional timeout for idle sockets being kept-alive.
@@ -381,11 +511,37 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.pool_idle_timeout(val))
     }
 
+    /// Set the timeout for idle sockets to one host, overriding
+    /// `pool_idle_timeout` for it.
+    ///
+    /// `host` must match the request URL's host and port exactly, such as
+    /// `"example.com:443"`. Connections to `host` are kept in a pool of
+    /// their own.
+    pub fn pool_idle_timeout_for(self, host: &str, timeout: Duration) -> ClientBuilder {
+        self.with_inner(move |inner| inner.pool_idle_timeout_for(host, timeout))
+    }
+
     /// Sets the maximum idle connection per host allowed in the pool.
     pub fn pool_max_idle_per_host(self, max: usize) -> ClientBuilder {
         self.with_inner(move |inner| inner.pool_max_idle_per_host(max))
     }
 
+    /// Sets the maximum number of connections open to a host at once.
+    ///
+    /// Once `max` connections to a host are open, idle or not, a request
+    /// that needs a new one waits until a connection to that host is
+    /// closed, or one becomes idle for it to reuse. HTTP/2 connections
+    /// count once however many requests they carry.
+    ///
+    /// Default is no limit.
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if `max` is 0.
+    pub fn pool_max_connections_per_host(self, max: usize) -> ClientBuilder {
+        self.with_inner(move |inner| inner.pool_max_connections_per_host(max))
+    }
+
     /// Send headers as title case instead of lowercase.
     pub fn http1_title_case_headers(self) -> ClientBuilder {
         self.with_inner(|inner| inner.http1_title_case_headers())
@@ -414,6 +570,28 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.http1_allow_spaces_after_header_name_in_responses(value))
     }
 
+    /// Sets the maximum number of headers of an HTTP/1 response.
+    ///
+    /// Default is 100, the default of hyper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn http1_max_headers_count(self, max: usize) -> ClientBuilder {
+        self.with_inner(|inner| inner.http1_max_headers_count(max))
+    }
+
+    /// Sets the maximum size of the head of an HTTP/1 response, in bytes.
+    ///
+    /// Default is 417,792 (about 400KB), the default of hyper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is less than 8,192, the minimum of hyper.
+    pub fn http1_max_header_size(self, max: usize) -> ClientBuilder {
+        self.with_inner(|inner| inner.http1_max_header_size(max))
+    }
+
     /// Only use HTTP/1.
     pub fn http1_only(self) -> ClientBuilder {
         self.with_inner(|inner| inner.http1_only())
@@ -468,6 +646,28 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.http2_max_frame_size(sz))
     }
 
+    /// Sets the `SETTINGS_MAX_HEADER_LIST_SIZE` option for HTTP2.
+    ///
+    /// Default is 16,384 (16KB), the default of hyper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_max_header_list_size(self, max: usize) -> ClientBuilder {
+        self.with_inner(|inner| inner.http2_max_header_list_size(max))
+    }
+
+    /// Sets the maximum write buffer size for each HTTP2 stream.
+    ///
+    /// Default is currently 1MB, but may change.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_max_send_buf_size(self, max: usize) -> ClientBuilder {
+        self.with_inner(|inner| inner.http2_max_send_buf_size(max))
+    }
+
     /// This requires the optional `http3` feature to be
     /// enabled.
     #[cfg(feature = "http3")]
@@ -487,6 +687,10 @@ impl ClientBuilder {
 
     /// Bind to a local IP Address.
     ///
+    /// Connections are only made to remote addresses of the same family,
+    /// IPv4 or IPv6, as `addr`. Requests to a host with none fail with a
+    /// connect error saying so.
+    ///
     /// # Example
     ///
     /// ```
@@ -503,8 +707,33 @@ impl ClientBuilder {
         self.with_inner(move |inner| inner.local_address(addr))
     }
 
+    /// Bind to a local IPv4 address, an IPv6 address, or both, using the
+    /// one of the same family as the address being connected to.
+    ///
+    /// Connections to a family without an address given here bind to the
+    /// unspecified address of that family, leaving the choice to the OS.
+    /// This replaces any address set with `local_address`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::net::{Ipv4Addr, Ipv6Addr};
+    /// let client = reqwest::blocking::Client::builder()
+    ///     .local_addresses(
+    ///         Some(Ipv4Addr::new(12, 4, 1, 8)),
+    ///         Some(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+    ///     )
+    ///     .build().unwrap();
+    /// ```
+    pub fn local_addresses(self, ipv4: Option<Ipv4Addr>, ipv6: Option<Ipv6Addr>) -> ClientBuilder {
+        self.with_inner(move |inner| inner.local_addresses(ipv4, ipv6))
+    }
+
     /// Bind to an interface by `SO_BINDTODEVICE`.
     ///
+    /// This requires elevated privileges, see
+    /// `reqwest::ClientBuilder::interface` for details.
+    ///
     /// # Example
     ///
     /// ```
@@ -514,10 +743,26 @@ impl ClientBuilder {
     ///     .build().unwrap();
     /// ```
     #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
-    pub fn interface(self, interface: &str) -> ClientBuilder {
+    pub fn interface(self, interface: impl Into<String>) -> ClientBuilder {
+        let interface = interface.into();
         self.with_inner(move |inner| inner.interface(interface))
     }
 
+    /// Send all requests through the Unix domain socket at `path`.
+    ///
+    /// Every connection is made to the socket, whatever the host of the
+    /// request URL, which is only used for the `Host` header. Proxies are not
+    /// used, and `https` URLs fail.
+    ///
+    /// This requires the optional `unix-socket` feature to be enabled, and is
+    /// only available on Unix.
+    #[cfg(all(unix, feature = "unix-socket"))]
+    #[cfg_attr(docsrs, doc(cfg(all(unix, feature = "unix-socket"))))]
+    pub fn unix_socket<P: AsRef<std::path::Path>>(self, path: P) -> ClientBuilder {
+        let path = path.as_ref().to_owned();
+        self.with_inner(move |inner| inner.unix_socket(path))
+    }
+
     /// Set that all sockets have `SO_KEEPALIVE` set with the supplied duration.
     ///
     /// If `None`, the option will not be set.
@@ -528,6 +773,55 @@ impl ClientBuilder {
         self.with_inner(move |inner| inner.tcp_keepalive(val))
     }
 
+    /// Set the time between TCP keepalive probes, once the peer stopped
+    /// answering.
+    ///
+    /// This sets `TCP_KEEPINTVL` on Linux, Android, Fuchsia, macOS, iOS and
+    /// the BSDs, and the probe interval on Windows. Other platforms ignore
+    /// it.
+    ///
+    /// If `None`, the system default is used.
+    pub fn tcp_keepalive_interval<D>(self, val: D) -> ClientBuilder
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.with_inner(move |inner| inner.tcp_keepalive_interval(val))
+    }
+
+    /// Set how many TCP keepalive probes are sent without an answer before
+    /// the connection is dropped.
+    ///
+    /// This sets `TCP_KEEPCNT` on Linux, Android, Fuchsia, macOS, iOS and
+    /// the BSDs. Windows and other platforms ignore it.
+    ///
+    /// If `None`, the system default is used.
+    pub fn tcp_keepalive_retries<C>(self, retries: C) -> ClientBuilder
+    where
+        C: Into<Option<u32>>,
+    {
+        self.with_inner(move |inner| inner.tcp_keepalive_retries(retries))
+    }
+
+    /// Set the size of the send buffer of every new socket, `SO_SNDBUF`.
+    ///
+    /// See `reqwest::ClientBuilder::tcp_send_buffer_size` for details.
+    pub fn tcp_send_buffer_size<S>(self, size: S) -> ClientBuilder
+    where
+        S: Into<Option<usize>>,
+    {
+        self.with_inner(move |inner| inner.tcp_send_buffer_size(size))
+    }
+
+    /// Set the size of the receive buffer of every new socket, `SO_RCVBUF`.
+    ///
+    /// See `reqwest::ClientBuilder::tcp_recv_buffer_size` for details.
+    pub fn tcp_recv_buffer_size<S>(self, size: S) -> ClientBuilder
+    where
+        S: Into<Option<usize>>,
+    {
+        self.with_inner(move |inner| inner.tcp_recv_buffer_size(size))
+    }
+
     // TLS options
 
     /// Add a custom root certificate.
@@ -625,6 +919,20 @@ impl ClientBuilder {
         self.with_inner(move |inner| inner.identity(identity))
     }
 
+    /// Sets the identity to be used for client certificate authentication
+    /// with one host, instead of the one set with `identity`.
+    ///
+    /// See `reqwest::ClientBuilder::identity_for_host` for details.
+    ///
+    /// # Optional
+    ///
+    /// This requires the rustls TLS backend.
+    #[cfg(feature = "__rustls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+    pub fn identity_for_host(self, host: &str, identity: Identity) -> ClientBuilder {
+        self.with_inner(move |inner| inner.identity_for_host(host, identity))
+    }
+
     /// Controls the use of hostname verification.
     ///
     /// Defaults to `false`.
@@ -669,6 +977,34 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.danger_accept_invalid_certs(accept_invalid_certs))
     }
 
+    /// Disables certificate validation for the hosts matching
+    /// `host_pattern`, only.
+    ///
+    /// The pattern is either a host name, matched exactly, or a wildcard
+    /// like `*.example.com`, matching any subdomain of `example.com` but
+    /// not `example.com` itself. The certificates of all other hosts are
+    /// still validated. Calling this again adds another pattern.
+    ///
+    /// # Warning
+    ///
+    /// You should think very carefully before using this method. If
+    /// invalid certificates are trusted, *any* certificate for the
+    /// matching hosts will be trusted for use, so anybody able to
+    /// intercept connections to them can impersonate them. This should
+    /// only be used as a last resort.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn danger_accept_invalid_certs_for(self, host_pattern: &str) -> ClientBuilder {
+        self.with_inner(|inner| inner.danger_accept_invalid_certs_for(host_pattern))
+    }
+
     /// Controls the use of TLS server name indication.
     ///
     /// Defaults to `true`.
@@ -685,6 +1021,40 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.tls_sni(tls_sni))
     }
 
+    /// Sets the server name to use with TLS when connecting to `host`,
+    /// instead of `host` itself.
+    ///
+    /// See `reqwest::ClientBuilder::tls_sni_override` for details and
+    /// security implications.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_sni_override(self, host: &str, server_name: &str) -> ClientBuilder {
+        self.with_inner(move |inner| inner.tls_sni_override(host, server_name))
+    }
+
+    /// Pins the public key of the certificates of `host`.
+    ///
+    /// See `reqwest::ClientBuilder::add_pinned_spki_sha256` for details.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn add_pinned_spki_sha256(self, host: &str, spki_sha256: [u8; 32]) -> ClientBuilder {
+        self.with_inner(move |inner| inner.add_pinned_spki_sha256(host, spki_sha256))
+    }
+
     /// Set the minimum required TLS version for connections.
     ///
     /// By default the TLS backend's own default is used.
@@ -860,6 +1230,10 @@ impl ClientBuilder {
 
     /// Restrict the Client to be used with HTTPS only requests.
     ///
+    /// A request to a URL that isn't `https` fails before anything is sent,
+    /// and so does a redirect to one, whatever the redirect policy. Either
+    /// error returns `true` for `Error::is_not_https`.
+    ///
     /// Defaults to false.
     pub fn https_only(self, enabled: bool) -> ClientBuilder {
         self.with_inner(|inner| inner.https_only(enabled))
@@ -889,6 +1263,129 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.resolve_to_addrs(domain, addrs))
     }
 
+    /// Adds a middleware to run around every request this client sends.
+    ///
+    /// Middleware are asynchronous and run on the client's background
+    /// runtime. See `reqwest::ClientBuilder::with_middleware` for details.
+    pub fn with_middleware<M>(self, middleware: M) -> ClientBuilder
+    where
+        M: crate::middleware::Middleware + 'static,
+    {
+        self.with_inner(move |inner| inner.with_middleware(middleware))
+    }
+
+    /// Adds a middleware to run around every hop of the requests this
+    /// client sends, inside the redirect loop.
+    ///
+    /// See `reqwest::ClientBuilder::with_hop_middleware` for details.
+    pub fn with_hop_middleware<M>(self, middleware: M) -> ClientBuilder
+    where
+        M: crate::middleware::Middleware + 'static,
+    {
+        self.with_inner(move |inner| inner.with_hop_middleware(middleware))
+    }
+
+    /// Shares one request between identical requests sent at the same time,
+    /// from different threads.
+    ///
+    /// See `reqwest::ClientBuilder::single_flight` for details.
+    pub fn single_flight(self, enabled: bool) -> ClientBuilder {
+        self.with_inner(move |inner| inner.single_flight(enabled))
+    }
+
+    /// Sets the request headers that must be the same for requests to be
+    /// shared with `single_flight`.
+    ///
+    /// See `reqwest::ClientBuilder::single_flight_headers` for details.
+    pub fn single_flight_headers<I>(self, headers: I) -> ClientBuilder
+    where
+        I: IntoIterator<Item = header::HeaderName>,
+    {
+        self.with_inner(move |inner| inner.single_flight_headers(headers))
+    }
+
+    /// Sets the largest response body shared with `single_flight`, in bytes.
+    ///
+    /// See `reqwest::ClientBuilder::single_flight_max_body` for details.
+    pub fn single_flight_max_body(self, max: usize) -> ClientBuilder {
+        self.with_inner(move |inner| inner.single_flight_max_body(max))
+    }
+
+    /// Sets how requests are found identical with `single_flight`.
+    ///
+    /// `key` is given the request as the async `Client` sends it. See
+    /// `reqwest::ClientBuilder::single_flight_key` for details.
+    pub fn single_flight_key<F>(self, key: F) -> ClientBuilder
+    where
+        F: Fn(&async_impl::Request) -> Option<String> + Send + Sync + 'static,
+    {
+        self.with_inner(move |inner| inner.single_flight_key(key))
+    }
+
+    /// Records every request this client sends, with its response, to a
+    /// cassette file at `path`.
+    ///
+    /// See `reqwest::ClientBuilder::record_to` for details.
+    #[cfg(feature = "cassette")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cassette")))]
+    pub fn record_to(self, path: impl Into<std::path::PathBuf>) -> ClientBuilder {
+        self.with_inner(move |inner| inner.record_to(path))
+    }
+
+    /// Answers every request this client sends with a response recorded in
+    /// the cassette file at `path`, without sending it.
+    ///
+    /// See `reqwest::ClientBuilder::replay_from` for details.
+    #[cfg(feature = "cassette")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cassette")))]
+    pub fn replay_from(self, path: impl Into<std::path::PathBuf>) -> ClientBuilder {
+        self.with_inner(move |inner| inner.replay_from(path))
+    }
+
+    /// Sets which parts of requests must match recorded ones to be
+    /// replayed with `replay_from`.
+    ///
+    /// See `reqwest::ClientBuilder::cassette_match` for details.
+    #[cfg(feature = "cassette")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cassette")))]
+    pub fn cassette_match(self, matching: crate::cassette::Match) -> ClientBuilder {
+        self.with_inner(move |inner| inner.cassette_match(matching))
+    }
+
+    /// Calls back with every request sent and every response received,
+    /// as they go over the wire.
+    ///
+    /// See `reqwest::ClientBuilder::wire_debug` for details.
+    pub fn wire_debug(self, config: crate::wire::WireDebugConfig) -> ClientBuilder {
+        self.with_inner(move |inner| inner.wire_debug(config))
+    }
+
+    /// Sets a policy to send requests again when they fail with an error.
+    ///
+    /// The retries wait on the client's background runtime. See
+    /// `reqwest::ClientBuilder::with_retry` for details.
+    pub fn with_retry<P>(self, policy: P) -> ClientBuilder
+    where
+        P: crate::retry::RetryPolicy + 'static,
+    {
+        self.with_inner(move |inner| inner.with_retry(policy))
+    }
+
+    /// Sets a signer to sign every request this client sends.
+    ///
+    /// See `reqwest::ClientBuilder::sign_with` for details.
+    pub fn sign_with(self, signer: Arc<dyn crate::sign::RequestSigner>) -> ClientBuilder {
+        self.with_inner(move |inner| inner.sign_with(signer))
+    }
+
+    /// Set whether streaming request bodies, such as those read from a
+    /// file, are read into memory so they can be signed.
+    ///
+    /// Default is `false`.
+    pub fn buffer_signed_bodies(self, enabled: bool) -> ClientBuilder {
+        self.with_inner(move |inner| inner.buffer_signed_bodies(enabled))
+    }
+
     // private
 
     fn with_inner<F>(mut self, func: F) -> ClientBuilder
@@ -905,6 +1402,7 @@ impl From<async_impl::ClientBuilder> for ClientBuilder {
         Self {
             inner: builder,
             timeout: Timeout::default(),
+            error_body_limit: DEFAULT_ERROR_BODY_LIMIT,
         }
     }
 }
@@ -1021,6 +1519,32 @@ impl Client {
     pub fn execute(&self, request: Request) -> crate::Result<Response> {
         self.inner.execute_request(request)
     }
+
+    pub(crate) fn error_body_limit(&self) -> usize {
+        self.inner.error_body_limit
+    }
+
+    /// Returns the connections of this `Client` and its clones, by host.
+    ///
+    /// See `reqwest::Client::pool_stats` for details.
+    pub fn pool_stats(&self) -> PoolStats {
+        self.inner.pool.snapshot()
+    }
+
+    /// Executes a `Request`, also returning the URLs it was redirected to.
+    ///
+    /// Redirects are followed as with `execute`, and the URLs are returned
+    /// in the order they were followed. The last one is the final URL of the
+    /// `Response`. It is empty if no redirect was followed.
+    ///
+    /// # Errors
+    ///
+    /// This method fails in the same cases as `execute`.
+    pub fn execute_with_history(&self, request: Request) -> crate::Result<(Response, Vec<Url>)> {
+        let res = self.inner.execute_request(request)?;
+        let redirects = redirect::redirect_targets(res.redirect_history(), res.url());
+        Ok((res, redirects))
+    }
 }
 
 impl fmt::Debug for Client {
@@ -1042,9 +1566,13 @@ impl fmt::Debug for ClientBuilder {
 #[derive(Clone)]
 struct ClientHandle {
     timeout: Timeout,
+    error_body_limit: usize,
+    pool: PoolCounters,
     inner: Arc<InnerClientHandle>,
 }
 
+const DEFAULT_ERROR_BODY_LIMIT: usize = 8 * 1024;
+
 type OneshotResponse = oneshot::Sender<crate::Result<async_impl::Response>>;
 type ThreadSender = mpsc::UnboundedSender<(async_impl::Request, OneshotResponse)>;
 
@@ -1072,9 +1600,10 @@ impl Drop for InnerClientHandle {
 impl ClientHandle {
     fn new(builder: ClientBuilder) -> crate::Result<ClientHandle> {
         let timeout = builder.timeout;
+        let error_body_limit = builder.error_body_limit;
         let builder = builder.inner;
         let (tx, rx) = mpsc::unbounded_channel::<(async_impl::Request, OneshotResponse)>();
-        let (spawn_tx, spawn_rx) = oneshot::channel::<crate::Result<()>>();
+        let (spawn_tx, spawn_rx) = oneshot::channel::<crate::Result<PoolCounters>>();
         let handle = thread::Builder::new()
             .name("reqwest-internal-sync-runtime".into())
             .spawn(move || {
@@ -1103,7 +1632,7 @@ impl ClientHandle {
                         }
                         Ok(v) => v,
                     };
-                    if let Err(e) = spawn_tx.send(Ok(())) {
+                    if let Err(e) = spawn_tx.send(Ok(client.pool_counters())) {
                         error!("Failed to communicate successful startup: {e:?}");
                         return;
                     }
@@ -1127,11 +1656,16 @@ impl ClientHandle {
             .map_err(crate::error::builder)?;
 
         // Wait for the runtime thread to start up...
-        match wait::timeout(spawn_rx, None) {
-            Ok(Ok(())) => (),
+        let pool = match wait::timeout(spawn_rx, None) {
+            Ok(Ok(pool)) => pool,
             Ok(Err(err)) => return Err(err),
+            Err(wait::Waited::CurrentThread) => {
+                return Err(crate::error::builder(
+                    crate::error::BlockingOnCurrentThread,
+                ))
+            }
             Err(_canceled) => event_loop_panicked(),
-        }
+        };
 
         let inner_handle = Arc::new(InnerClientHandle {
             tx: Some(tx),
@@ -1140,13 +1674,16 @@ impl ClientHandle {
 
         Ok(ClientHandle {
             timeout,
+            error_body_limit,
+            pool,
             inner: inner_handle,
         })
     }
 
     fn execute_request(&self, req: Request) -> crate::Result<Response> {
         let (tx, rx) = oneshot::channel();
-        let (req, body) = req.into_async();
+        let (mut req, body) = req.into_async();
+        req.resolve_timeout();
         let url = req.url().clone();
         let timeout = req.timeout().copied().or(self.timeout.0);
 
@@ -1176,7 +1713,13 @@ impl ClientHandle {
                 timeout,
                 KeepCoreThreadAlive(Some(self.inner.clone())),
             )),
-            Err(wait::Waited::TimedOut(e)) => Err(crate::error::request(e).with_url(url)),
+            Err(wait::Waited::TimedOut { elapsed }) => {
+                trace!("request to {url} timed out after {elapsed:?}");
+                Err(crate::error::request(crate::error::TimedOut).with_url(url))
+            }
+            Err(wait::Waited::CurrentThread) => {
+                Err(crate::error::request(crate::error::BlockingOnCurrentThread).with_url(url))
+            }
             Err(wait::Waited::Inner(err)) => Err(err.with_url(url)),
         }
     }
@@ -79,6 +79,22 @@ impl Form {
         self.inner.boundary()
     }
 
+    /// Replaces the randomly generated boundary with `boundary`.
+    ///
+    /// The boundary must be 1 to 70 characters from the set allowed by
+    /// RFC 2046: letters, digits, spaces and any of `'()+_,-./:=?`, not
+    /// ending with a space. It must also not appear in the content of any
+    /// part, which isn't checked.
+    ///
+    /// # Errors
+    ///
+    /// Fails with a builder error if `boundary` isn't a valid boundary.
+    pub fn set_boundary<T: Into<String>>(self, boundary: T) -> crate::Result<Form> {
+        Ok(Form {
+            inner: self.inner.set_boundary(boundary.into())?,
+        })
+    }
+
     /// Add a data field with supplied name and value.
     ///
     /// # Examples
@@ -148,6 +164,10 @@ impl Form {
         Reader::new(self)
     }
 
+    pub(crate) fn content_type(&self) -> String {
+        self.inner.content_type()
+    }
+
     // If predictable, computes the length the request will have
     // The length should be preditable if only String and file fields have been added,
     // but not if a generic reader has been added;
@@ -262,6 +282,16 @@ impl Part {
         self.with_inner(move |inner| inner.headers(headers))
     }
 
+    /// Annotates the part with the length of its body, whether or not the
+    /// body knows it.
+    ///
+    /// The part gets a `Content-Length` header, and the length is used to
+    /// compute the length of the form, without buffering the body. The
+    /// body must produce exactly `len` bytes.
+    pub fn with_content_length(self, len: u64) -> Part {
+        self.with_inner(move |inner| inner.content_length(len))
+    }
+
     fn with_inner<F>(self, func: F) -> Self
     where
         F: FnOnce(PartMetadata) -> PartMetadata,
@@ -284,7 +314,7 @@ impl fmt::Debug for Part {
 
 impl PartProps for Part {
     fn value_len(&self) -> Option<u64> {
-        self.value.len()
+        self.meta.content_length.or_else(|| self.value.len())
     }
 
     fn metadata(&self) -> &PartMetadata {
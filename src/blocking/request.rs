@@ -1,5 +1,6 @@
 use std::convert::TryFrom;
 use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
 
 use http::{request::Parts, Request as HttpRequest, Version};
@@ -13,7 +14,7 @@ use super::body::{self, Body};
 use super::multipart;
 use super::Client;
 use crate::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
-use crate::{async_impl, Method, Url};
+use crate::{async_impl, retry, Method, Url};
 
 /// A request which can be executed with `Client::execute()`.
 pub struct Request {
@@ -29,6 +30,8 @@ pub struct Request {
 pub struct RequestBuilder {
     client: Client,
     request: crate::Result<Request>,
+    // the `Content-Type` came with the body, from `body_with_type`
+    typed_body: bool,
 }
 
 impl Request {
@@ -113,6 +116,82 @@ impl Request {
         self.inner.timeout_mut()
     }
 
+    /// Get the connect timeout.
+    #[inline]
+    pub fn connect_timeout(&self) -> Option<&Duration> {
+        self.inner.connect_timeout()
+    }
+
+    /// Get a mutable reference to the connect timeout.
+    #[inline]
+    pub fn connect_timeout_mut(&mut self) -> &mut Option<Duration> {
+        self.inner.connect_timeout_mut()
+    }
+
+    /// Get the retry policy.
+    #[inline]
+    pub fn retry(&self) -> Option<&retry::Policy> {
+        self.inner.retry()
+    }
+
+    /// Get a mutable reference to the retry policy.
+    #[inline]
+    pub fn retry_mut(&mut self) -> &mut Option<retry::Policy> {
+        self.inner.retry_mut()
+    }
+
+    /// Get whether the response body will be decompressed.
+    #[inline]
+    pub fn decompress(&self) -> bool {
+        self.inner.decompress()
+    }
+
+    /// Get a mutable reference to whether the response body will be
+    /// decompressed.
+    #[inline]
+    pub fn decompress_mut(&mut self) -> &mut bool {
+        self.inner.decompress_mut()
+    }
+
+    /// Get the extensions.
+    #[inline]
+    pub fn extensions(&self) -> &http::Extensions {
+        self.inner.extensions()
+    }
+
+    /// Get a mutable reference to the extensions.
+    #[inline]
+    pub fn extensions_mut(&mut self) -> &mut http::Extensions {
+        self.inner.extensions_mut()
+    }
+
+    /// Get whether the body waits for `100 Continue` before being sent.
+    #[inline]
+    pub fn expect_continue(&self) -> bool {
+        self.inner.expect_continue()
+    }
+
+    /// Get a mutable reference to whether the body waits for
+    /// `100 Continue` before being sent.
+    #[inline]
+    pub fn expect_continue_mut(&mut self) -> &mut bool {
+        self.inner.expect_continue_mut()
+    }
+
+    /// Get the body compression.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    #[inline]
+    pub fn compression(&self) -> Option<crate::ContentEncoding> {
+        self.inner.compression()
+    }
+
+    /// Get a mutable reference to the body compression.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    #[inline]
+    pub fn compression_mut(&mut self) -> &mut Option<crate::ContentEncoding> {
+        self.inner.compression_mut()
+    }
+
     /// Attempts to clone the `Request`.
     ///
     /// None is returned if a body is which can not be cloned. This can be because the body is a
@@ -130,6 +209,7 @@ impl Request {
         let mut req = Request::new(self.method().clone(), self.url().clone());
         *req.headers_mut() = self.headers().clone();
         *req.version_mut() = self.version().clone();
+        *req.extensions_mut() = self.extensions().clone();
         req.body = body;
         Some(req)
     }
@@ -152,7 +232,11 @@ impl Request {
 
 impl RequestBuilder {
     pub(crate) fn new(client: Client, request: crate::Result<Request>) -> RequestBuilder {
-        let mut builder = RequestBuilder { client, request };
+        let mut builder = RequestBuilder {
+            client,
+            request,
+            typed_body: false,
+        };
 
         let auth = builder
             .request
@@ -169,6 +253,9 @@ impl RequestBuilder {
 
     /// Add a `Header` to this Request.
     ///
+    /// This replaces any values the client has for `key` in its default
+    /// headers. Calling it again with the same `key` adds another value.
+    ///
     /// ```rust
     /// use reqwest::header::USER_AGENT;
     ///
@@ -249,6 +336,57 @@ impl RequestBuilder {
         self
     }
 
+    /// Sets the `Host` header of this Request, replacing the one derived
+    /// from the URL.
+    ///
+    /// The connection is still made to the host of the URL, and TLS still
+    /// uses it for server name indication and to verify the certificate,
+    /// unless `ClientBuilder::tls_sni_override` is set for that host. This
+    /// is useful to reach a virtual host through an address or a load
+    /// balancer.
+    ///
+    /// This only applies to HTTP/1; HTTP/2 requests take their `:authority`
+    /// from the URL.
+    ///
+    /// # Security
+    ///
+    /// The server routes the request by this header, while the certificate
+    /// is checked against the URL host, so a valid connection doesn't prove
+    /// the request reached the named host. The header is dropped when a
+    /// redirect leads to another host.
+    ///
+    /// ```rust
+    /// # use reqwest::Error;
+    /// #
+    /// # fn run() -> Result<(), Error> {
+    /// let client = reqwest::blocking::Client::new();
+    /// let res = client
+    ///     .get("http://127.0.0.1:8080/status")
+    ///     .host_header("internal.example")
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn host_header<V>(mut self, value: V) -> RequestBuilder
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match <HeaderValue as TryFrom<V>>::try_from(value) {
+                Ok(value) => {
+                    req.headers_mut().insert(crate::header::HOST, value);
+                }
+                Err(e) => error = Some(crate::error::builder(e.into())),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
     /// Enable HTTP basic authentication.
     ///
     /// ```rust
@@ -288,6 +426,139 @@ impl RequestBuilder {
         self.header_sensitive(crate::header::AUTHORIZATION, &*header_value, true)
     }
 
+    /// Enable HTTP bearer authentication, with a token fetched when the
+    /// request is sent.
+    ///
+    /// Unlike `bearer_auth`, the token isn't fixed when the request is
+    /// built. `token` is called once per attempt, right before the
+    /// `Authorization` header is written, so retries and redirects pick up
+    /// a refreshed token. It replaces any `Authorization` header set before.
+    ///
+    /// # Errors
+    ///
+    /// Sending fails with a builder error if the token isn't a valid header
+    /// value.
+    ///
+    /// ```rust
+    /// # use std::sync::{Arc, RwLock};
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let token = Arc::new(RwLock::new(String::from("initial")));
+    ///
+    /// let cache = token.clone();
+    /// let res = reqwest::blocking::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .bearer_auth_fn(move || cache.read().unwrap().clone())
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bearer_auth_fn<F>(mut self, token: F) -> RequestBuilder
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            *req.inner.bearer_token_mut() = Some(Arc::new(token));
+        }
+        self
+    }
+
+    /// Enable HTTP digest authentication.
+    ///
+    /// The request is sent again to answer a `Digest` challenge of a
+    /// `401 Unauthorized` response, as described by the async
+    /// `RequestBuilder::digest_auth`.
+    ///
+    /// ```rust
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let resp = reqwest::blocking::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .digest_auth("Aladdin", "open sesame")
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn digest_auth<U, P>(mut self, username: U, password: P) -> RequestBuilder
+    where
+        U: fmt::Display,
+        P: fmt::Display,
+    {
+        if let Ok(ref mut req) = self.request {
+            *req.inner.digest_auth_mut() = Some(crate::digest_auth::Credentials::new(
+                username.to_string(),
+                password.to_string(),
+            ));
+        }
+        self
+    }
+
+    /// Asks for content in one of `languages`, most preferred first, with
+    /// an `Accept-Language` header.
+    ///
+    /// See the async `RequestBuilder::accept_language` for the qualities
+    /// given to each language.
+    ///
+    /// # Errors
+    ///
+    /// Sending fails with a builder error if `languages` is empty, or has an
+    /// invalid tag or a quality outside of `0.0` to `1.0`.
+    ///
+    /// ```rust
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use reqwest::LanguageTag;
+    ///
+    /// let resp = reqwest::blocking::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .accept_language(&[LanguageTag::new("de"), LanguageTag::new("en").with_quality(0.2)])
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn accept_language(mut self, languages: &[crate::LanguageTag]) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            match crate::language::accept_language(languages) {
+                Ok(value) => {
+                    req.headers_mut()
+                        .insert(crate::header::ACCEPT_LANGUAGE, value);
+                }
+                Err(err) => self.request = Err(err),
+            }
+        }
+        self
+    }
+
+    /// Asks for only part of the resource, with a `Range` header.
+    ///
+    /// The range covers bytes `start` to `end`, both included, or everything
+    /// from `start` on if `end` is `None`. It replaces any `Range` header set
+    /// before. Servers may ignore it and send the whole resource, which is
+    /// told apart with `Response::content_range`.
+    ///
+    /// # Errors
+    ///
+    /// Sending fails with a builder error if `end` is before `start`.
+    ///
+    /// ```rust
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = reqwest::blocking::Client::new();
+    /// // sends `Range: bytes=500-`
+    /// let res = client.get("http://httpbin.org/range/2048")
+    ///     .range(500, None)
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn range(mut self, start: u64, end: Option<u64>) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            match crate::range::range_header(start, end) {
+                Ok(value) => {
+                    req.headers_mut().insert(crate::header::RANGE, value);
+                }
+                Err(err) => self.request = Err(err),
+            }
+        }
+        self
+    }
+
     /// Set the request body.
     ///
     /// # Examples
@@ -338,14 +609,164 @@ impl RequestBuilder {
         self
     }
 
+    /// Set the request body, and its `Content-Type`.
+    ///
+    /// `mime` is a `mime::Mime`, or a string that must parse as one. It
+    /// replaces any `Content-Type` set before, while `form` or `json`
+    /// called afterwards still replace it along with the body.
+    ///
+    /// ```rust
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::blocking::Client::new()
+    ///     .post("http://httpbin.org/post")
+    ///     .body_with_type("<answer>42</answer>", "application/xml")
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn body_with_type<T, M>(mut self, body: T, mime: M) -> RequestBuilder
+    where
+        T: Into<Body>,
+        M: AsRef<str>,
+    {
+        if let Ok(ref mut req) = self.request {
+            match async_impl::request::content_type(mime.as_ref()) {
+                Ok(value) => {
+                    req.headers_mut().insert(CONTENT_TYPE, value);
+                    *req.body_mut() = Some(body.into());
+                    self.typed_body = true;
+                }
+                Err(err) => self.request = Err(err),
+            }
+        }
+        self
+    }
+
     /// Enables a request timeout.
     ///
     /// The timeout is applied from when the request starts connecting until the
     /// response body has finished. It affects only this request and overrides
     /// the timeout configured using `ClientBuilder::timeout()`.
+    ///
+    /// This replaces any function set with `timeout_fn`.
     pub fn timeout(mut self, timeout: Duration) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
             *req.timeout_mut() = Some(timeout);
+            *req.inner.timeout_fn_mut() = None;
+        }
+        self
+    }
+
+    /// Computes the request timeout with a function, called just before the
+    /// request is sent.
+    ///
+    /// Returning `None` leaves only the timeout configured using
+    /// `ClientBuilder::timeout()`. This replaces any timeout set with
+    /// `timeout`, and `timeout` replaces this in turn: whichever is called
+    /// last applies.
+    ///
+    /// See `reqwest::RequestBuilder::timeout_fn` for an example.
+    pub fn timeout_fn<F>(mut self, timeout: F) -> RequestBuilder
+    where
+        F: Fn() -> Option<Duration> + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            *req.timeout_mut() = None;
+            *req.inner.timeout_fn_mut() = Some(Arc::new(timeout));
+        }
+        self
+    }
+
+    /// Set a timeout for only the connect phase of this request.
+    ///
+    /// It affects only this request and overrides the timeout configured
+    /// using `ClientBuilder::connect_timeout()`. Reusing an idle pooled
+    /// connection skips the connect phase, and with it this timeout.
+    pub fn connect_timeout(mut self, timeout: Duration) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.connect_timeout_mut() = Some(timeout);
+        }
+        self
+    }
+
+    /// Set a policy for retrying this request.
+    ///
+    /// A request whose body is read from a `Read` can't be replayed, so it
+    /// is only ever sent once. The `timeout` covers all attempts together.
+    pub fn retry(mut self, policy: retry::Policy) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.retry_mut() = Some(policy);
+        }
+        self
+    }
+
+    /// Add a typed value to the request's extensions, for middleware.
+    ///
+    /// See `reqwest::RequestBuilder::extension` for details.
+    pub fn extension<T>(mut self, value: T) -> RequestBuilder
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            req.extensions_mut().insert(value);
+        }
+        self
+    }
+
+    /// Read a streaming body into memory, up to `limit` bytes, so the
+    /// `RetryPolicy` set with `ClientBuilder::with_retry` can send it again.
+    ///
+    /// See `reqwest::RequestBuilder::retry_body_buffer_limit` for details.
+    pub fn retry_body_buffer_limit(mut self, limit: usize) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.inner.retry_body_buffer_limit_mut() = Some(limit);
+        }
+        self
+    }
+
+    /// Disable automatic decompression of the response body, for this
+    /// request only.
+    ///
+    /// The body is returned as it was sent by the server, and the
+    /// `Content-Encoding` and `Content-Length` headers are kept. No
+    /// `Accept-Encoding` header is added either.
+    pub fn no_decompress(mut self) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.decompress_mut() = false;
+        }
+        self
+    }
+
+    /// Send `Expect: 100-continue`, and wait for the server to answer
+    /// `100 Continue` before sending the body.
+    ///
+    /// See `reqwest::RequestBuilder::expect_continue` for details.
+    pub fn expect_continue(mut self, enabled: bool) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.expect_continue_mut() = enabled;
+        }
+        self
+    }
+
+    /// Compress the request body with `encoding`.
+    ///
+    /// `Content-Encoding` is set, and the body is compressed at once if it
+    /// is in memory, or else as it is sent. This overrides the default set with
+    /// `ClientBuilder::compress_body`. See the async
+    /// `RequestBuilder::compress_body` for details.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `gzip`, `brotli` or `zstd` feature to be
+    /// enabled, for the matching `ContentEncoding`.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "gzip", feature = "brotli", feature = "zstd")))
+    )]
+    pub fn compress_body(mut self, encoding: crate::ContentEncoding) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.compression_mut() = Some(encoding);
         }
         self
     }
@@ -402,6 +823,31 @@ impl RequestBuilder {
         self
     }
 
+    /// Sets a query parameter of the URL, replacing any it already has.
+    ///
+    /// If `key` is already in the query string, its first occurrence gets
+    /// `value` and any others are removed. Otherwise, `key=value` is
+    /// appended. Keys are compared after percent-decoding.
+    ///
+    /// The query string is re-encoded, so other parameters keep their
+    /// values but may be written differently, such as `+` for `%20`.
+    pub fn query_replace(mut self, key: &str, value: &str) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            async_impl::request::replace_query_pair(req.url_mut(), key, Some(value));
+        }
+        self
+    }
+
+    /// Removes every occurrence of a query parameter from the URL.
+    ///
+    /// Keys are compared after percent-decoding, as with `query_replace`.
+    pub fn query_remove(mut self, key: &str) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            async_impl::request::replace_query_pair(req.url_mut(), key, None);
+        }
+        self
+    }
+
     /// Set HTTP version
     pub fn version(mut self, version: Version) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
@@ -435,7 +881,8 @@ impl RequestBuilder {
     /// # Errors
     ///
     /// This method fails if the passed value cannot be serialized into
-    /// url encoded format
+    /// url encoded format, such as a struct with a nested struct, sequence
+    /// or map as a field.
     pub fn form<T: Serialize + ?Sized>(mut self, form: &T) -> RequestBuilder {
         let mut error = None;
         if let Ok(ref mut req) = self.request {
@@ -456,6 +903,51 @@ impl RequestBuilder {
         self
     }
 
+    /// Send a form body, choosing how sequences and nested values are
+    /// encoded.
+    ///
+    /// See `reqwest::RequestBuilder::form_with` for details.
+    ///
+    /// ```rust
+    /// use reqwest::form::{ArrayFormat, FormOptions};
+    ///
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let ids = [("id", vec![1, 2])];
+    /// let res = reqwest::blocking::Client::new()
+    ///     .post("http://httpbin.org/post")
+    ///     .form_with(&ids, FormOptions::new().arrays(ArrayFormat::Brackets))
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails for the same reasons as the async `form_with`.
+    pub fn form_with<T: Serialize + ?Sized>(
+        mut self,
+        form: &T,
+        options: crate::form::FormOptions,
+    ) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match crate::form::to_string(form, &options) {
+                Ok(body) => {
+                    req.headers_mut().insert(
+                        CONTENT_TYPE,
+                        HeaderValue::from_static("application/x-www-form-urlencoded"),
+                    );
+                    *req.body_mut() = Some(body.into());
+                }
+                Err(err) => error = Some(crate::error::builder(err)),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
     /// Send a JSON body.
     ///
     /// Sets the body to the JSON serialization of the passed value, and
@@ -494,7 +986,7 @@ impl RequestBuilder {
         if let Ok(ref mut req) = self.request {
             match serde_json::to_vec(json) {
                 Ok(body) => {
-                    if !req.headers().contains_key(CONTENT_TYPE) {
+                    if self.typed_body || !req.headers().contains_key(CONTENT_TYPE) {
                         req.headers_mut()
                             .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
                     }
@@ -533,7 +1025,7 @@ impl RequestBuilder {
     pub fn multipart(self, mut multipart: multipart::Form) -> RequestBuilder {
         let mut builder = self.header(
             CONTENT_TYPE,
-            format!("multipart/form-data; boundary={}", multipart.boundary()).as_str(),
+            multipart.content_type().as_str(),
         );
         if let Ok(ref mut req) = builder.request {
             *req.body_mut() = Some(match multipart.compute_length() {
@@ -560,6 +1052,22 @@ impl RequestBuilder {
         self.client.execute(self.request?)
     }
 
+    /// Constructs the Request and sends it the target URL, returning an error
+    /// if the response status is a client or server error.
+    ///
+    /// Unlike `Response::error_for_status`, the error keeps the start of the
+    /// response body. See `Error::response_body_snippet` and
+    /// `ClientBuilder::error_body_limit`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails for the same reasons as `send`, and if the status
+    /// is between 400 and 599.
+    pub fn send_success(self) -> crate::Result<super::Response> {
+        let limit = self.client.error_body_limit();
+        self.send()?.error_for_status_with_body(limit)
+    }
+
     /// Attempts to clone the `RequestBuilder`.
     ///
     /// None is returned if a body is which can not be cloned. This can be because the body is a
@@ -612,6 +1120,7 @@ impl RequestBuilder {
             .map(|req| RequestBuilder {
                 client: self.client.clone(),
                 request: Ok(req),
+                typed_body: self.typed_body,
             })
     }
 }
@@ -628,11 +1137,13 @@ where
             method,
             uri,
             headers,
+            extensions,
             ..
         } = parts;
         let url = Url::parse(&uri.to_string()).map_err(crate::error::builder)?;
         let mut inner = async_impl::Request::new(method, url);
         crate::util::replace_headers(inner.headers_mut(), headers);
+        *inner.extensions_mut() = extensions;
         Ok(Request {
             body: Some(body.into()),
             inner,
@@ -864,6 +1375,32 @@ mod tests {
         assert_eq!(req.url().query(), Some("foo=bar&qux=three"));
     }
 
+    #[test]
+    fn add_body_with_type() {
+        let client = Client::new();
+        let some_url = "https://google.com/";
+
+        let r = client
+            .post(some_url)
+            .header(CONTENT_TYPE, "text/plain")
+            .body_with_type("<a/>", "text/xml")
+            .build()
+            .unwrap();
+        assert_eq!(r.headers().get_all(CONTENT_TYPE).iter().count(), 1);
+        assert_eq!(r.headers().get(CONTENT_TYPE).unwrap(), &"text/xml");
+
+        let r = client
+            .post(some_url)
+            .body_with_type("a", "text/plain")
+            .form(&[("a", "b")])
+            .build()
+            .unwrap();
+        assert_eq!(
+            r.headers().get(CONTENT_TYPE).unwrap(),
+            &"application/x-www-form-urlencoded"
+        );
+    }
+
     #[test]
     fn add_form() {
         let client = Client::new();
@@ -958,6 +1495,20 @@ mod tests {
         assert_eq!(foo[1], "baz");
     }
 
+    #[test]
+    fn query_replace_and_remove() {
+        let client = Client::new();
+        let some_url = "https://google.com/?key=a&page=1&key=b";
+
+        let req = client
+            .get(some_url)
+            .query_replace("key", "c")
+            .query_remove("page")
+            .build()
+            .expect("request is valid");
+        assert_eq!(req.url().query(), Some("key=c"));
+    }
+
     #[test]
     fn normalize_empty_query() {
         let client = Client::new();
@@ -1,7 +1,9 @@
 use std::fmt;
+use std::fs::File;
 use std::io::{self, Read};
 use std::mem;
 use std::net::SocketAddr;
+use std::path::Path;
 use std::pin::Pin;
 use std::time::Duration;
 
@@ -93,6 +95,13 @@ impl Response {
         self.inner.status()
     }
 
+    /// Get the reason phrase of the status line of this `Response`.
+    ///
+    /// See `reqwest::Response::reason` for details.
+    pub fn reason(&self) -> Option<&str> {
+        self.inner.reason()
+    }
+
     /// Get the `Headers` of this `Response`.
     ///
     /// # Example
@@ -142,6 +151,9 @@ impl Response {
     }
 
     /// Get the HTTP `Version` of this `Response`.
+    ///
+    /// This is the protocol the response was actually received with, not the
+    /// one asked for. Over TLS, it is the one negotiated with ALPN.
     #[inline]
     pub fn version(&self) -> Version {
         self.inner.version()
@@ -165,6 +177,9 @@ impl Response {
 
     /// Get the remote address used to get this `Response`.
     ///
+    /// Through a proxy, this is the proxy's address. See
+    /// `reqwest::Response::remote_addr` for details.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -178,6 +193,62 @@ impl Response {
         self.inner.remote_addr()
     }
 
+    /// Returns whether this `Response` came over a connection that had
+    /// already been used.
+    ///
+    /// This is `false` for the first response on a newly opened connection,
+    /// and `true` when the connection was taken from the pool.
+    pub fn connection_reused(&self) -> bool {
+        self.inner.connection_reused()
+    }
+
+    /// Returns the redirects followed to get this `Response`, in order.
+    ///
+    /// The final URL, given by `url`, is not included. This is empty if no
+    /// redirect was followed.
+    pub fn redirect_history(&self) -> &[crate::redirect::Hop] {
+        self.inner.redirect_history()
+    }
+
+    /// Returns how long each phase of this request took.
+    ///
+    /// This is `None` unless timings were enabled with
+    /// `ClientBuilder::collect_timings`.
+    pub fn timings(&self) -> Option<crate::Timings> {
+        self.inner.timings()
+    }
+
+    /// Returns the TLS details of the connection this `Response` came over.
+    ///
+    /// This is `None` for plain HTTP, and unless enabled with
+    /// `ClientBuilder::tls_info`.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_info(&self) -> Option<&crate::tls::TlsInfo> {
+        self.inner.tls_info()
+    }
+
+    /// Returns the language of the content, from its `Content-Language`
+    /// header, as sent by the server.
+    pub fn content_language(&self) -> Option<&str> {
+        self.inner.content_language()
+    }
+
+    /// Returns the byte range this `Response` holds, from its
+    /// `Content-Range` header.
+    ///
+    /// This is `None` unless the status is `206 Partial Content`.
+    pub fn content_range(&self) -> Option<crate::ContentRange> {
+        self.inner.content_range()
+    }
+
     /// Returns a reference to the associated extensions.
     pub fn extensions(&self) -> &http::Extensions {
         self.inner.extensions()
@@ -188,6 +259,13 @@ impl Response {
         self.inner.extensions_mut()
     }
 
+    /// Returns the extension of type `T`, if there is one.
+    ///
+    /// See `reqwest::Response::extension` for details.
+    pub fn extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.inner.extension::<T>()
+    }
+
     /// Get the content-length of the response, if it is known.
     ///
     /// Reasons it may not be known:
@@ -199,6 +277,19 @@ impl Response {
         self.inner.content_length()
     }
 
+    /// Get the value of the `content-length` header, if the response has
+    /// one and isn't chunked.
+    ///
+    /// This is only a hint, such as for preallocating a buffer before
+    /// calling `bytes()`, since it is what the server claims. It is the
+    /// length of the body as sent, so if automatic decompression was
+    /// turned off, it isn't the decompressed length. When the body is
+    /// decompressed automatically, the header is removed, and this
+    /// returns `None`.
+    pub fn content_length_hint(&self) -> Option<u64> {
+        self.inner.content_length_hint()
+    }
+
     /// Try and deserialize the response body as JSON using `serde`.
     ///
     /// # Optional
@@ -239,7 +330,10 @@ impl Response {
     #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
     pub fn json<T: DeserializeOwned>(self) -> crate::Result<T> {
         wait::timeout(self.inner.json(), self.timeout).map_err(|e| match e {
-            wait::Waited::TimedOut(e) => crate::error::decode(e),
+            wait::Waited::TimedOut { .. } => crate::error::decode(crate::error::TimedOut),
+            wait::Waited::CurrentThread => {
+                crate::error::decode(crate::error::BlockingOnCurrentThread)
+            }
             wait::Waited::Inner(e) => e,
         })
     }
@@ -258,7 +352,35 @@ impl Response {
     /// ```
     pub fn bytes(self) -> crate::Result<Bytes> {
         wait::timeout(self.inner.bytes(), self.timeout).map_err(|e| match e {
-            wait::Waited::TimedOut(e) => crate::error::decode(e),
+            wait::Waited::TimedOut { .. } => crate::error::decode(crate::error::TimedOut),
+            wait::Waited::CurrentThread => {
+                crate::error::decode(crate::error::BlockingOnCurrentThread)
+            }
+            wait::Waited::Inner(e) => e,
+        })
+    }
+
+    /// Get the full response body as `Bytes`, failing if it is larger than
+    /// `max` bytes.
+    ///
+    /// See `reqwest::Response::bytes_limited` for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let bytes = reqwest::blocking::get("http://httpbin.org/ip")?.bytes_limited(64 * 1024)?;
+    ///
+    /// println!("bytes: {bytes:?}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bytes_limited(self, max: usize) -> crate::Result<Bytes> {
+        wait::timeout(self.inner.bytes_limited(max), self.timeout).map_err(|e| match e {
+            wait::Waited::TimedOut { .. } => crate::error::decode(crate::error::TimedOut),
+            wait::Waited::CurrentThread => {
+                crate::error::decode(crate::error::BlockingOnCurrentThread)
+            }
             wait::Waited::Inner(e) => e,
         })
     }
@@ -286,7 +408,10 @@ impl Response {
     /// ```
     pub fn text(self) -> crate::Result<String> {
         wait::timeout(self.inner.text(), self.timeout).map_err(|e| match e {
-            wait::Waited::TimedOut(e) => crate::error::decode(e),
+            wait::Waited::TimedOut { .. } => crate::error::decode(crate::error::TimedOut),
+            wait::Waited::CurrentThread => {
+                crate::error::decode(crate::error::BlockingOnCurrentThread)
+            }
             wait::Waited::Inner(e) => e,
         })
     }
@@ -320,7 +445,10 @@ impl Response {
     pub fn text_with_charset(self, default_encoding: &str) -> crate::Result<String> {
         wait::timeout(self.inner.text_with_charset(default_encoding), self.timeout).map_err(|e| {
             match e {
-                wait::Waited::TimedOut(e) => crate::error::decode(e),
+                wait::Waited::TimedOut { .. } => crate::error::decode(crate::error::TimedOut),
+                wait::Waited::CurrentThread => {
+                    crate::error::decode(crate::error::BlockingOnCurrentThread)
+                }
                 wait::Waited::Inner(e) => e,
             }
         })
@@ -353,6 +481,29 @@ impl Response {
         io::copy(self, w).map_err(crate::error::decode_io)
     }
 
+    /// Copy the response body into a file, created or truncated at `path`.
+    ///
+    /// The body is streamed as with `copy_to`, and the file is synced to
+    /// disk before returning the number of bytes written. If an error
+    /// happens part way, the file is left with what was written so far.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut resp = reqwest::blocking::get("http://httpbin.org/range/5")?;
+    /// let written = resp.copy_to_file("range.txt")?;
+    /// assert_eq!(written, 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn copy_to_file(&mut self, path: impl AsRef<Path>) -> crate::Result<u64> {
+        let mut file = File::create(path).map_err(crate::error::decode_io)?;
+        let written = self.copy_to(&mut file)?;
+        file.sync_all().map_err(crate::error::decode_io)?;
+        Ok(written)
+    }
+
     /// Turn a response into an error if the server returned an error.
     ///
     /// # Example
@@ -406,6 +557,26 @@ impl Response {
 
     // private
 
+    pub(crate) fn error_for_status_with_body(self, limit: usize) -> crate::Result<Self> {
+        if self.error_for_status_ref().is_ok() {
+            return Ok(self);
+        }
+        let url = self.url().clone();
+        let status = self.status();
+        let Response {
+            inner,
+            timeout,
+            _thread_handle,
+            ..
+        } = self;
+        match wait::timeout(inner.error_for_status_with_body(limit), timeout) {
+            Ok(_) => unreachable!("status was checked to be an error"),
+            Err(wait::Waited::Inner(err)) => Err(err),
+            // the status is still known, just not the body
+            Err(_) => Err(crate::error::status_code(url, status)),
+        }
+    }
+
     fn body_mut(&mut self) -> Pin<&mut dyn futures_util::io::AsyncRead> {
         use futures_util::TryStreamExt;
         if self.body.is_none() {
@@ -419,18 +590,45 @@ impl Response {
     }
 }
 
+/// Streams the body as it arrives, without buffering all of it.
+///
+/// Each call to `read` returns at most one chunk's worth of data, keeping
+/// any rest of that chunk for the next call. The client's timeout applies
+/// to each call, and a timeout is returned as an [`io::Error`] of kind
+/// [`io::ErrorKind::TimedOut`], wrapping a [`crate::Error`] for which
+/// `is_timeout` is true.
 impl Read for Response {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         use futures_util::io::AsyncReadExt;
 
         let timeout = self.timeout;
         wait::timeout(self.body_mut().read(buf), timeout).map_err(|e| match e {
-            wait::Waited::TimedOut(e) => crate::error::decode(e).into_io(),
-            wait::Waited::Inner(e) => e,
+            wait::Waited::TimedOut { .. } => io::Error::new(
+                io::ErrorKind::TimedOut,
+                crate::error::body(crate::error::TimedOut),
+            ),
+            wait::Waited::CurrentThread => {
+                crate::error::decode(crate::error::BlockingOnCurrentThread).into_io()
+            }
+            wait::Waited::Inner(e) => timed_out_kind(e),
         })
     }
 }
 
+// A timeout from the async body, like the total request timeout, is
+// wrapped as `ErrorKind::Other` by the time it gets here.
+fn timed_out_kind(e: io::Error) -> io::Error {
+    let timed_out = e
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<crate::Error>())
+        .map_or(false, crate::Error::is_timeout);
+    if timed_out && e.kind() != io::ErrorKind::TimedOut {
+        io::Error::new(io::ErrorKind::TimedOut, e.into_inner().expect("checked get_ref"))
+    } else {
+        e
+    }
+}
+
 impl<T: Into<async_impl::body::Body>> From<http::Response<T>> for Response {
     fn from(r: http::Response<T>) -> Response {
         let response = async_impl::Response::from(r);
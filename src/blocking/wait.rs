@@ -1,26 +1,51 @@
 use std::future::Future;
 use std::sync::Arc;
-use std::task::{Context, Poll};
+use std::task::{Context, Poll, Waker};
 use std::thread::{self, Thread};
 use std::time::Duration;
 
+use tokio::runtime::RuntimeFlavor;
 use tokio::time::Instant;
 
 pub(crate) fn timeout<F, I, E>(fut: F, timeout: Option<Duration>) -> Result<I, Waited<E>>
 where
     F: Future<Output = Result<I, E>>,
 {
+    timeout_with_waker(fut, timeout, |thread| {
+        // Arc shouldn't be necessary, since `Thread` is reference counted internally,
+        // but let's just stay safe for now.
+        futures_util::task::waker(Arc::new(ThreadWaker(thread)))
+    })
+}
+
+/// Like `timeout`, but the `Waker` given to `fut` is built by `waker_fn`.
+///
+/// `waker_fn` receives the thread that will park while waiting on `fut`.
+/// The returned `Waker` can notify whatever else it needs to, but it must
+/// still unpark that thread when woken, or the wait only ends at the
+/// deadline (or never, without a timeout).
+pub(crate) fn timeout_with_waker<F, I, E, W>(
+    fut: F,
+    timeout: Option<Duration>,
+    waker_fn: W,
+) -> Result<I, Waited<E>>
+where
+    F: Future<Output = Result<I, E>>,
+    W: FnOnce(Thread) -> Waker,
+{
+    if on_current_thread_runtime() {
+        return Err(Waited::CurrentThread);
+    }
+
     enter();
 
+    let start = Instant::now();
     let deadline = timeout.map(|d| {
         log::trace!("wait at most {d:?}");
-        Instant::now() + d
+        start + d
     });
 
-    let thread = ThreadWaker(thread::current());
-    // Arc shouldn't be necessary, since `Thread` is reference counted internally,
-    // but let's just stay safe for now.
-    let waker = futures_util::task::waker(Arc::new(thread));
+    let waker = waker_fn(thread::current());
     let mut cx = Context::from_waker(&waker);
 
     futures_util::pin_mut!(fut);
@@ -35,8 +60,9 @@ where
         if let Some(deadline) = deadline {
             let now = Instant::now();
             if now >= deadline {
-                log::trace!("wait timeout exceeded");
-                return Err(Waited::TimedOut(crate::error::TimedOut));
+                let elapsed = now - start;
+                log::trace!("wait timeout exceeded after {elapsed:?}");
+                return Err(Waited::TimedOut { elapsed });
             }
 
             log::trace!(
@@ -54,7 +80,14 @@ where
 
 #[derive(Debug)]
 pub(crate) enum Waited<E> {
-    TimedOut(crate::error::TimedOut),
+    /// The deadline passed before the future completed.
+    TimedOut {
+        /// How long the future was polled for before giving up.
+        elapsed: Duration,
+    },
+    /// Waiting would block the only thread of a `current_thread` runtime,
+    /// which the future itself may need to make progress.
+    CurrentThread,
     Inner(E),
 }
 
@@ -66,6 +99,11 @@ impl futures_util::task::ArcWake for ThreadWaker {
     }
 }
 
+fn on_current_thread_runtime() -> bool {
+    tokio::runtime::Handle::try_current()
+        .map_or(false, |handle| handle.runtime_flavor() == RuntimeFlavor::CurrentThread)
+}
+
 fn enter() {
     // Check we aren't already in a runtime
     #[cfg(debug_assertions)]
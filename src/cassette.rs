@@ -0,0 +1,478 @@
+//! Recording and replaying HTTP interactions
+//!
+//! A `Client` built with `ClientBuilder::record_to` sends its requests as
+//! usual, and writes each request and its response to a cassette file. One
+//! built with `ClientBuilder::replay_from` reads such a file, and answers
+//! each request with a recorded response, without sending anything, so
+//! tests using it need no network nor server.
+//!
+//! The cassette is a JSON file, written again after every interaction in
+//! record mode. It holds requests as they were built, before default headers
+//! and cookies were added, and the final responses, after redirects were
+//! followed and bodies decompressed. Bodies are stored as text if they are
+//! valid UTF-8, and in base64 otherwise. The values of the `Authorization`,
+//! `Proxy-Authorization` and `Cookie` headers of requests are redacted, but
+//! responses are stored whole.
+//!
+//! In replay mode, a request is answered by the first recorded interaction
+//! it matches that wasn't replayed yet, or else by the last one it matches,
+//! so a request repeated more times than recorded gets the last response.
+//! A request matching none fails with an error naming it. By default,
+//! requests match on their method and URL, which `Match` can extend to their
+//! body and some headers.
+//!
+//! # Example
+//!
+//! ```rust
+//! use reqwest::cassette::Match;
+//!
+//! # fn run() -> Result<(), reqwest::Error> {
+//! let path = "tests/cassettes/login.json";
+//! let client = if std::env::var_os("RECORD").is_some() {
+//!     reqwest::Client::builder().record_to(path)
+//! } else {
+//!     reqwest::Client::builder().replay_from(path)
+//! }
+//! .cassette_match(Match::new().body(true))
+//! .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use base64::Engine;
+use bytes::Bytes;
+use serde_json::{json, Map, Value};
+
+use crate::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION,
+};
+use crate::middleware::{Handling, Middleware, Next};
+use crate::{Method, Request, Response, ResponseBuilderExt, StatusCode, Url};
+
+const REDACTED: &str = "[redacted]";
+
+/// Which parts of a request must be the same as those of a recorded one for
+/// it to be replayed.
+///
+/// The method and URL always have to match.
+#[derive(Clone, Debug, Default)]
+pub struct Match {
+    body: bool,
+    headers: Vec<HeaderName>,
+}
+
+impl Match {
+    /// Matches requests on their method and URL.
+    pub fn new() -> Match {
+        Match::default()
+    }
+
+    /// Sets whether the bodies must be the same too.
+    ///
+    /// A streaming body is never recorded, so it only matches a recorded
+    /// request without a body.
+    pub fn body(mut self, enabled: bool) -> Match {
+        self.body = enabled;
+        self
+    }
+
+    /// Adds a header whose values must be the same too.
+    ///
+    /// For a header that is redacted in the cassette, only whether it is
+    /// there is compared.
+    pub fn header(mut self, name: HeaderName) -> Match {
+        self.headers.push(name);
+        self
+    }
+}
+
+/// The `Client` settings of a cassette.
+#[derive(Debug)]
+pub(crate) enum Mode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Records or replays the requests of a `Client`, as its outermost
+/// middleware.
+pub(crate) struct Cassette {
+    path: PathBuf,
+    matching: Match,
+    state: Mutex<State>,
+}
+
+enum State {
+    Recording(Vec<Interaction>),
+    Replaying(Vec<(Interaction, bool)>),
+}
+
+struct Interaction {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<Bytes>,
+    status: u16,
+    response_url: String,
+    response_headers: Vec<(String, String)>,
+    response_body: Bytes,
+}
+
+impl Cassette {
+    /// Starts recording, or reads the file to replay.
+    pub(crate) fn new(mode: Mode, matching: Match) -> crate::Result<Cassette> {
+        let (path, state) = match mode {
+            Mode::Record(path) => (path, State::Recording(Vec::new())),
+            Mode::Replay(path) => {
+                let interactions = read(&path).map_err(|err| {
+                    crate::error::builder(format!("cassette {}: {err}", path.display()))
+                })?;
+                let state = State::Replaying(interactions.into_iter().map(|i| (i, false)).collect());
+                (path, state)
+            }
+        };
+        Ok(Cassette {
+            path,
+            matching,
+            state: Mutex::new(state),
+        })
+    }
+
+    fn replay(&self, req: &Request) -> crate::Result<Response> {
+        let body = req.body().and_then(|body| body.as_bytes());
+        let headers = redacted(req.headers());
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let interactions = match *state {
+            State::Replaying(ref mut interactions) => interactions,
+            State::Recording(_) => unreachable!("replaying a recording cassette"),
+        };
+
+        let mut matching = interactions
+            .iter_mut()
+            .filter(|(recorded, _)| self.matches(recorded, req, &headers, body));
+        let mut last = None;
+        let found = loop {
+            match matching.next() {
+                Some((recorded, played)) if !*played => {
+                    *played = true;
+                    break Some(&*recorded);
+                }
+                Some((recorded, _)) => last = Some(&*recorded),
+                None => break last,
+            }
+        };
+        match found {
+            Some(recorded) => recorded.response(),
+            None => Err(crate::error::request(NotRecorded {
+                method: req.method().clone(),
+                path: self.path.clone(),
+            })
+            .with_url(req.url().clone())),
+        }
+    }
+
+    fn matches(
+        &self,
+        recorded: &Interaction,
+        req: &Request,
+        headers: &[(String, String)],
+        body: Option<&[u8]>,
+    ) -> bool {
+        if recorded.method != req.method().as_str() || recorded.url != req.url().as_str() {
+            return false;
+        }
+        if self.matching.body && recorded.body.as_deref() != body {
+            return false;
+        }
+        self.matching.headers.iter().all(|name| {
+            let values = |headers: &[(String, String)]| -> Vec<String> {
+                headers
+                    .iter()
+                    .filter(|(key, _)| key == name.as_str())
+                    .map(|(_, value)| value.clone())
+                    .collect()
+            };
+            values(&recorded.headers) == values(headers)
+        })
+    }
+
+    /// Reads the response to `req`, and writes both to the cassette.
+    async fn record(&self, req: Request, next: Next<'_>) -> crate::Result<Response> {
+        let method = req.method().to_string();
+        let url = req.url().clone();
+        let headers = redacted(req.headers());
+        let body = req
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(Bytes::copy_from_slice);
+
+        let res = next.run(req).await?;
+        let status = res.status().as_u16();
+        let response_url = res.url().to_string();
+        let response_headers = pairs(res.headers());
+        let response_body = res.bytes().await?;
+        let recorded = Interaction {
+            method,
+            url: url.to_string(),
+            headers,
+            body,
+            status,
+            response_url,
+            response_headers,
+            response_body,
+        };
+        let res = recorded.response();
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let State::Recording(ref mut interactions) = *state {
+            interactions.push(recorded);
+            let cassette = json!({
+                "interactions": interactions.iter().map(Interaction::to_json).collect::<Vec<_>>(),
+            });
+            let cassette = serde_json::to_string_pretty(&cassette).expect("cassette is valid JSON");
+            if let Err(err) = fs::write(&self.path, cassette) {
+                return Err(crate::error::request(format!(
+                    "writing cassette {}: {err}",
+                    self.path.display()
+                ))
+                .with_url(url));
+            }
+        }
+        res
+    }
+}
+
+impl Middleware for Cassette {
+    fn handle<'a>(&'a self, req: Request, next: Next<'a>) -> Handling<'a> {
+        let recording = matches!(
+            *self.state.lock().unwrap_or_else(|e| e.into_inner()),
+            State::Recording(_)
+        );
+        if recording {
+            Box::pin(self.record(req, next))
+        } else {
+            let res = self.replay(&req);
+            Box::pin(async move { res })
+        }
+    }
+}
+
+impl fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("matching", &self.matching)
+            .finish()
+    }
+}
+
+/// The headers of a request as recorded, with credentials redacted.
+fn redacted(headers: &HeaderMap) -> Vec<(String, String)> {
+    let mut pairs = pairs(headers);
+    for (name, value) in &mut pairs {
+        if [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE]
+            .iter()
+            .any(|redact| redact.as_str() == name)
+        {
+            *value = REDACTED.to_owned();
+        }
+    }
+    pairs
+}
+
+fn pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.as_str().to_owned(), value)
+        })
+        .collect()
+}
+
+impl Interaction {
+    fn response(&self) -> crate::Result<Response> {
+        let invalid = |what: &str| {
+            crate::error::decode(format!("invalid response {what} recorded for {}", self.url))
+        };
+        let status = StatusCode::from_u16(self.status).map_err(|_| invalid("status"))?;
+        let url = Url::parse(&self.response_url).map_err(|_| invalid("url"))?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.response_headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid("header"))?;
+            let value = HeaderValue::from_str(value).map_err(|_| invalid("header"))?;
+            headers.append(name, value);
+        }
+        let mut res = http::Response::builder()
+            .status(status)
+            .url(url)
+            .body(self.response_body.clone())
+            .expect("valid response parts");
+        *res.headers_mut() = headers;
+        Ok(Response::from(res))
+    }
+
+    fn to_json(&self) -> Value {
+        let mut request = Map::new();
+        request.insert("method".into(), json!(self.method));
+        request.insert("url".into(), json!(self.url));
+        request.insert("headers".into(), headers_json(&self.headers));
+        body_json(&mut request, self.body.as_deref());
+
+        let mut response = Map::new();
+        response.insert("status".into(), json!(self.status));
+        response.insert("url".into(), json!(self.response_url));
+        response.insert("headers".into(), headers_json(&self.response_headers));
+        body_json(&mut response, Some(&self.response_body));
+
+        json!({ "request": request, "response": response })
+    }
+
+    fn from_json(value: &Value) -> Result<Interaction, String> {
+        let request = &value["request"];
+        let response = &value["response"];
+        Ok(Interaction {
+            method: string(&request["method"], "request method")?,
+            url: string(&request["url"], "request url")?,
+            headers: headers_from_json(&request["headers"])?,
+            body: body_from_json(request)?,
+            status: response["status"]
+                .as_u64()
+                .and_then(|status| u16::try_from(status).ok())
+                .ok_or("missing response status")?,
+            response_url: string(&response["url"], "response url")?,
+            response_headers: headers_from_json(&response["headers"])?,
+            response_body: body_from_json(response)?.unwrap_or_default(),
+        })
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<Interaction>, String> {
+    let cassette = fs::read(path).map_err(|err| err.to_string())?;
+    let cassette: Value = serde_json::from_slice(&cassette).map_err(|err| err.to_string())?;
+    cassette["interactions"]
+        .as_array()
+        .ok_or("missing interactions")?
+        .iter()
+        .map(Interaction::from_json)
+        .collect()
+}
+
+fn string(value: &Value, what: &str) -> Result<String, String> {
+    value
+        .as_str()
+        .map(str::to_owned)
+        .ok_or_else(|| format!("missing {what}"))
+}
+
+fn headers_json(headers: &[(String, String)]) -> Value {
+    headers
+        .iter()
+        .map(|(name, value)| json!([name, value]))
+        .collect()
+}
+
+fn headers_from_json(value: &Value) -> Result<Vec<(String, String)>, String> {
+    let headers = match value.as_array() {
+        Some(headers) => headers,
+        None => return Ok(Vec::new()),
+    };
+    headers
+        .iter()
+        .map(|header| match header.as_array().map(Vec::as_slice) {
+            Some([Value::String(name), Value::String(value)]) => Ok((name.clone(), value.clone())),
+            _ => Err(format!("invalid header {header}")),
+        })
+        .collect()
+}
+
+fn body_json(object: &mut Map<String, Value>, body: Option<&[u8]>) {
+    match body.map(std::str::from_utf8) {
+        Some(Ok(text)) => object.insert("body".into(), json!(text)),
+        Some(Err(_)) => {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(body.unwrap());
+            object.insert("body_base64".into(), json!(encoded))
+        }
+        None => object.insert("body".into(), Value::Null),
+    };
+}
+
+fn body_from_json(object: &Value) -> Result<Option<Bytes>, String> {
+    if let Some(text) = object["body"].as_str() {
+        return Ok(Some(Bytes::copy_from_slice(text.as_bytes())));
+    }
+    match object["body_base64"].as_str() {
+        Some(encoded) => base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map(|body| Some(body.into()))
+            .map_err(|err| format!("invalid body: {err}")),
+        None => Ok(None),
+    }
+}
+
+/// The error of a request replayed from a cassette that has no response
+/// for it.
+#[derive(Debug)]
+struct NotRecorded {
+    method: Method,
+    path: PathBuf,
+}
+
+impl fmt::Display for NotRecorded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "no {} request like this one in cassette {}",
+            self.method,
+            self.path.display()
+        )
+    }
+}
+
+impl std::error::Error for NotRecorded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interactions_round_trip() {
+        let recorded = Interaction {
+            method: "POST".into(),
+            url: "http://example.com/login".into(),
+            headers: vec![("authorization".into(), REDACTED.into())],
+            body: Some(Bytes::from_static(b"user=a")),
+            status: 201,
+            response_url: "http://example.com/welcome".into(),
+            response_headers: vec![("content-type".into(), "image/png".into())],
+            response_body: Bytes::from_static(&[0x89, b'P', b'N', b'G', 0xff]),
+        };
+        let value = recorded.to_json();
+        assert_eq!(value["request"]["body"], "user=a");
+        assert_eq!(value["response"]["body_base64"], "iVBOR/8=");
+
+        let read = Interaction::from_json(&value).unwrap();
+        assert_eq!(read.headers, recorded.headers);
+        assert_eq!(read.body, recorded.body);
+        assert_eq!(read.response_body, recorded.response_body);
+
+        let res = read.response().unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.url().as_str(), "http://example.com/welcome");
+        assert_eq!(res.headers()["content-type"], "image/png");
+    }
+
+    #[test]
+    fn redacts_request_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        headers.insert("x-id", HeaderValue::from_static("7"));
+        let headers = redacted(&headers);
+        assert!(headers.contains(&("authorization".into(), REDACTED.into())));
+        assert!(headers.contains(&("x-id".into(), "7".into())));
+    }
+}
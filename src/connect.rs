@@ -1,31 +1,44 @@
 #[cfg(feature = "__tls")]
-use http::header::HeaderValue;
+use http::header::{HeaderMap, HeaderValue};
 use http::uri::{Authority, Scheme};
 use http::Uri;
 use hyper::rt::{Read, ReadBufCursor, Write};
 use hyper_util::client::legacy::connect::{Connected, Connection};
-#[cfg(any(feature = "socks", feature = "__tls"))]
+#[cfg(any(
+    feature = "socks",
+    feature = "__tls",
+    all(unix, feature = "unix-socket")
+))]
 use hyper_util::rt::TokioIo;
 #[cfg(feature = "default-tls")]
 use native_tls_crate::{TlsConnector, TlsConnectorBuilder};
 use tower_service::Service;
 
 use pin_project_lite::pin_project;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::future::Future;
 use std::io::{self, IoSlice};
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 #[cfg(feature = "default-tls")]
 use self::native_tls_conn::NativeTlsConn;
 #[cfg(feature = "__rustls")]
 use self::rustls_tls_conn::RustlsTlsConn;
-use crate::dns::DynResolver;
+use crate::dns::{DynResolver, Resolve};
 use crate::error::BoxError;
-use crate::proxy::{Proxy, ProxyScheme};
+use crate::pool::{self, PoolCounters};
+use crate::proxy::{Proxy, ProxyScheme, RequestProxies};
+use crate::timings::{ConnectTimer, ConnectTimings};
+use crate::trace;
+#[cfg(feature = "__tls")]
+use crate::timings::{self, MarkConnected};
 
 pub(crate) type HttpConnector = hyper_util::client::legacy::connect::HttpConnector<DynResolver>;
 
@@ -35,12 +48,33 @@ pub(crate) struct Connector {
     proxies: Arc<Vec<Proxy>>,
     verbose: verbose::Wrapper,
     timeout: Option<Duration>,
+    timings: bool,
+    on_new_connection: Option<ConnectionHook>,
+    connection_info: bool,
+    host_limit: Option<HostLimit>,
+    pool: PoolCounters,
+    #[cfg(all(unix, feature = "unix-socket"))]
+    unix_socket: Option<Arc<std::path::Path>>,
+    local_addr: Option<IpAddr>,
+    // resolves host names to match them against no-proxy IP ranges
+    no_proxy_resolver: Option<Arc<dyn Resolve>>,
+    // the proxies chosen by the request this connection is opened for
+    request_proxies: Option<RequestProxies>,
     #[cfg(feature = "__tls")]
     nodelay: bool,
     #[cfg(feature = "__tls")]
     tls_info: bool,
     #[cfg(feature = "__tls")]
     user_agent: Option<HeaderValue>,
+    // server names to use with TLS instead of the host, by lowercase host
+    #[cfg(feature = "__tls")]
+    sni_overrides: Arc<HashMap<String, String>>,
+    // hashes of the public keys to accept, by lowercase host
+    #[cfg(feature = "__tls")]
+    spki_pins: Arc<HashMap<String, crate::pin::Pins>>,
+    // host patterns, and the connector not validating certificates for them
+    #[cfg(feature = "default-tls")]
+    insecure_tls: Option<(Arc<Vec<String>>, TlsConnector)>,
 }
 
 #[derive(Clone)]
@@ -54,6 +88,8 @@ enum Inner {
         http: HttpConnector,
         tls: Arc<rustls::ClientConfig>,
         tls_proxy: Arc<rustls::ClientConfig>,
+        // configs with another client certificate, by lowercase host
+        tls_per_host: Arc<HashMap<String, Arc<rustls::ClientConfig>>>,
     },
 }
 
@@ -70,7 +106,8 @@ impl Connector {
     where
         T: Into<Option<IpAddr>>,
     {
-        http.set_local_address(local_addr.into());
+        let local_addr = local_addr.into();
+        http.set_local_address(local_addr);
         #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
         if let Some(interface) = interface {
             http.set_interface(interface.to_owned());
@@ -82,6 +119,16 @@ impl Connector {
             verbose: verbose::OFF,
             proxies,
             timeout: None,
+            timings: false,
+            on_new_connection: None,
+            connection_info: false,
+            host_limit: None,
+            pool: PoolCounters::default(),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            unix_socket: None,
+            local_addr,
+            no_proxy_resolver: None,
+            request_proxies: None,
         }
     }
 
@@ -129,7 +176,8 @@ impl Connector {
     where
         T: Into<Option<IpAddr>>,
     {
-        http.set_local_address(local_addr.into());
+        let local_addr = local_addr.into();
+        http.set_local_address(local_addr);
         #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
         if let Some(interface) = interface {
             http.set_interface(interface);
@@ -142,9 +190,23 @@ impl Connector {
             proxies,
             verbose: verbose::OFF,
             timeout: None,
+            timings: false,
+            on_new_connection: None,
+            connection_info: false,
+            host_limit: None,
+            pool: PoolCounters::default(),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            unix_socket: None,
+            local_addr,
+            no_proxy_resolver: None,
+            request_proxies: None,
             nodelay,
             tls_info,
             user_agent,
+            sni_overrides: Arc::new(HashMap::new()),
+            spki_pins: Arc::new(HashMap::new()),
+            #[cfg(feature = "default-tls")]
+            insecure_tls: None,
         }
     }
 
@@ -163,7 +225,8 @@ impl Connector {
     where
         T: Into<Option<IpAddr>>,
     {
-        http.set_local_address(local_addr.into());
+        let local_addr = local_addr.into();
+        http.set_local_address(local_addr);
         #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
         if let Some(interface) = interface {
             http.set_interface(interface.to_owned());
@@ -185,16 +248,120 @@ impl Connector {
                 http,
                 tls,
                 tls_proxy,
+                tls_per_host: Arc::new(HashMap::new()),
             },
             proxies,
             verbose: verbose::OFF,
             timeout: None,
+            timings: false,
+            on_new_connection: None,
+            connection_info: false,
+            host_limit: None,
+            pool: PoolCounters::default(),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            unix_socket: None,
+            local_addr,
+            no_proxy_resolver: None,
+            request_proxies: None,
             nodelay,
             tls_info,
             user_agent,
+            sni_overrides: Arc::new(HashMap::new()),
+            spki_pins: Arc::new(HashMap::new()),
+            #[cfg(feature = "default-tls")]
+            insecure_tls: None,
+        }
+    }
+
+    /// Sets the TLS configs to use instead of the default one for some
+    /// hosts, keyed by lowercase host.
+    #[cfg(feature = "__rustls")]
+    pub(crate) fn set_rustls_per_host(&mut self, configs: HashMap<String, rustls::ClientConfig>) {
+        if let Inner::RustlsTls { tls_per_host, .. } = &mut self.inner {
+            *tls_per_host = Arc::new(
+                configs
+                    .into_iter()
+                    .map(|(host, config)| (host, Arc::new(config)))
+                    .collect(),
+            );
         }
     }
 
+    /// Sets the resolver used to match host names against the IP ranges
+    /// of no-proxy lists.
+    pub(crate) fn set_no_proxy_resolver(&mut self, resolver: Arc<dyn Resolve>) {
+        self.no_proxy_resolver = Some(resolver);
+    }
+
+    /// Binds to `ipv4` or `ipv6` depending on the family of the remote
+    /// address, or to the unspecified address of a family without one.
+    pub(crate) fn set_local_addresses(&mut self, ipv4: Option<Ipv4Addr>, ipv6: Option<Ipv6Addr>) {
+        let http = match &mut self.inner {
+            #[cfg(not(feature = "__tls"))]
+            Inner::Http(http) => http,
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(http, _) => http,
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls { http, .. } => http,
+        };
+        http.set_local_addresses(
+            ipv4.unwrap_or(Ipv4Addr::UNSPECIFIED),
+            ipv6.unwrap_or(Ipv6Addr::UNSPECIFIED),
+        );
+        self.local_addr = None;
+    }
+
+    /// Sets the server names to use with TLS instead of the host, keyed by
+    /// lowercase host.
+    #[cfg(feature = "__tls")]
+    pub(crate) fn set_sni_overrides(&mut self, overrides: HashMap<String, String>) {
+        self.sni_overrides = Arc::new(overrides);
+    }
+
+    /// The server name to use with TLS when connecting to `dst`, if it
+    /// isn't the host of `dst`.
+    #[cfg(feature = "__tls")]
+    fn sni_override(&self, dst: &Uri) -> Option<&str> {
+        if self.sni_overrides.is_empty() || dst.scheme() != Some(&Scheme::HTTPS) {
+            return None;
+        }
+        let host = dst
+            .host()?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+        self.sni_overrides.get(&host).map(String::as_str)
+    }
+
+    /// Sets the public keys to accept, keyed by lowercase host.
+    #[cfg(feature = "__tls")]
+    pub(crate) fn set_spki_pins(&mut self, pins: HashMap<String, crate::pin::Pins>) {
+        self.spki_pins = Arc::new(pins);
+    }
+
+    /// The public keys to accept when connecting to `dst`, and its host, if
+    /// it is pinned.
+    #[cfg(feature = "__tls")]
+    fn spki_pins(&self, dst: &Uri) -> Option<(String, crate::pin::Pins)> {
+        if self.spki_pins.is_empty() || dst.scheme() != Some(&Scheme::HTTPS) {
+            return None;
+        }
+        let host = dst
+            .host()?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+        let pins = self.spki_pins.get(&host)?.clone();
+        Some((host, pins))
+    }
+
+    /// Sets a connector not validating certificates, to use for the hosts
+    /// matching one of `patterns`.
+    #[cfg(feature = "default-tls")]
+    pub(crate) fn set_native_tls_insecure_hosts(&mut self, patterns: Vec<String>, tls: TlsConnector) {
+        self.insecure_tls = Some((Arc::new(patterns), tls));
+    }
+
     pub(crate) fn set_timeout(&mut self, timeout: Option<Duration>) {
         self.timeout = timeout;
     }
@@ -203,14 +370,65 @@ impl Connector {
         self.verbose.0 = enabled;
     }
 
+    pub(crate) fn set_timings(&mut self, enabled: bool) {
+        self.timings = enabled;
+    }
+
+    /// Sets the hook called for every new connection, and whether to
+    /// attach a `ConnectionInfo` to connections at all.
+    pub(crate) fn set_connection_hooks(&mut self, on_new: Option<ConnectionHook>, info: bool) {
+        self.on_new_connection = on_new;
+        self.connection_info = info || self.on_new_connection.is_some();
+    }
+
+    pub(crate) fn set_max_connections_per_host(&mut self, max: Option<usize>) {
+        self.host_limit = max.map(HostLimit::new);
+    }
+
+    /// The counters of the connections opened by this connector and its
+    /// clones.
+    pub(crate) fn pool_counters(&self) -> PoolCounters {
+        self.pool.clone()
+    }
+
+    #[cfg(all(unix, feature = "unix-socket"))]
+    pub(crate) fn set_unix_socket(&mut self, path: Option<Arc<std::path::Path>>) {
+        self.unix_socket = path;
+    }
+
+    /// Connects to the Unix socket at `path`, whatever the host of `dst`.
+    #[cfg(all(unix, feature = "unix-socket"))]
+    async fn connect_unix(self, dst: Uri, path: Arc<std::path::Path>) -> Result<Conn, BoxError> {
+        if dst.scheme() == Some(&Scheme::HTTPS) {
+            return Err("https is not supported over a Unix socket".into());
+        }
+        let stream = tokio::net::UnixStream::connect(&*path).await?;
+        Ok(Conn {
+            inner: self.verbose.wrap(TokioIo::new(stream)),
+            is_proxy: false,
+            tls_info: false,
+            timings: None,
+            permit: None,
+            pool: None,
+            trace: None,
+            info: None,
+        })
+    }
+
     #[cfg(feature = "socks")]
     async fn connect_socks(&self, dst: Uri, proxy: ProxyScheme) -> Result<Conn, BoxError> {
         let dns = match proxy {
             ProxyScheme::Socks5 {
                 remote_dns: false, ..
+            }
+            | ProxyScheme::Socks4 {
+                remote_dns: false, ..
             } => socks::DnsResolve::Local,
             ProxyScheme::Socks5 {
                 remote_dns: true, ..
+            }
+            | ProxyScheme::Socks4 {
+                remote_dns: true, ..
             } => socks::DnsResolve::Proxy,
             ProxyScheme::Http { .. } | ProxyScheme::Https { .. } => {
                 unreachable!("connect_socks is only called for socks proxies");
@@ -221,29 +439,46 @@ impl Connector {
             #[cfg(feature = "default-tls")]
             Inner::DefaultTls(_http, tls) => {
                 if dst.scheme() == Some(&Scheme::HTTPS) {
-                    let host = dst.host().ok_or("no host in url")?.to_string();
+                    let host = self
+                        .sni_override(&dst)
+                        .or_else(|| dst.host())
+                        .ok_or("no host in url")?
+                        .to_string();
                     let conn = socks::connect(proxy, dst, dns).await?;
+                    timings::mark_connected();
                     let conn = TokioIo::new(conn);
                     let conn = TokioIo::new(conn);
-                    let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
+                    let tls_connector = native_tls_for(tls, &self.insecure_tls, &host);
                     let io = tls_connector.connect(&host, conn).await?;
                     let io = TokioIo::new(io);
                     return Ok(Conn {
                         inner: self.verbose.wrap(NativeTlsConn { inner: io }),
                         is_proxy: false,
                         tls_info: self.tls_info,
+                        timings: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                        info: None,
                     });
                 }
             }
             #[cfg(feature = "__rustls")]
-            Inner::RustlsTls { tls, .. } => {
+            Inner::RustlsTls {
+                tls, tls_per_host, ..
+            } => {
                 if dst.scheme() == Some(&Scheme::HTTPS) {
                     use std::convert::TryFrom;
                     use tokio_rustls::TlsConnector as RustlsConnector;
 
-                    let tls = tls.clone();
-                    let host = dst.host().ok_or("no host in url")?.to_string();
+                    let tls = rustls_config_for(tls, tls_per_host, &dst);
+                    let host = self
+                        .sni_override(&dst)
+                        .or_else(|| dst.host())
+                        .ok_or("no host in url")?
+                        .to_string();
                     let conn = socks::connect(proxy, dst, dns).await?;
+                    timings::mark_connected();
                     let conn = TokioIo::new(conn);
                     let conn = TokioIo::new(conn);
                     let server_name =
@@ -257,6 +492,11 @@ impl Connector {
                         inner: self.verbose.wrap(RustlsTlsConn { inner: io }),
                         is_proxy: false,
                         tls_info: false,
+                        timings: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                        info: None,
                     });
                 }
             }
@@ -268,10 +508,202 @@ impl Connector {
             inner: self.verbose.wrap(TokioIo::new(tcp)),
             is_proxy: false,
             tls_info: false,
+            timings: None,
+            permit: None,
+            pool: None,
+            trace: None,
+            info: None,
         })
     }
 
+    /// Opens a connection to `dst`, within `timeout`.
+    fn connecting(self, dst: Uri, timeout: Option<Duration>) -> Connecting {
+        if self.timings || self.connection_info || self.verbose.0 {
+            let tls = dst.scheme() == Some(&Scheme::HTTPS);
+            let timer = if self.verbose.0 {
+                ConnectTimer::new().traced(&dst)
+            } else {
+                ConnectTimer::new()
+            };
+            let (timings, connection_info) = (self.timings, self.connection_info);
+            let on_new_connection = self.on_new_connection.clone();
+            let connecting = timer.time(self.connect(dst));
+            return Box::pin(with_timeout(
+                async move {
+                    let mut conn = connecting.await?;
+                    let finished = timer.finish(tls);
+                    if let Some(host) = timer.traced_host() {
+                        let info = ConnectionInfo::new(&conn, finished.total());
+                        let h2 = info.alpn_protocol() == Some(b"h2");
+                        conn.trace = Some(trace::Connection::opened(
+                            host,
+                            info.remote_addr(),
+                            h2,
+                            finished.total(),
+                        ));
+                    }
+                    if connection_info {
+                        let info = ConnectionInfo::new(&conn, finished.total());
+                        if let Some(hook) = on_new_connection {
+                            hook(info.clone());
+                        }
+                        conn.info = Some(info);
+                    }
+                    if timings {
+                        conn.timings = Some(finished);
+                    }
+                    Ok(conn)
+                },
+                timeout,
+            ));
+        }
+
+        Box::pin(with_timeout(self.connect(dst), timeout))
+    }
+
+    async fn connect(self, dst: Uri) -> Result<Conn, BoxError> {
+        #[cfg(all(unix, feature = "unix-socket"))]
+        if let Some(path) = self.unix_socket.clone() {
+            return self.connect_unix(dst, path).await;
+        }
+
+        #[cfg(feature = "__tls")]
+        let pins = self.spki_pins(&dst);
+
+        let conn = match self.proxy_for(&dst).await? {
+            Some(proxy_scheme) => self.connect_via_proxy(dst, proxy_scheme).await?,
+            None => {
+                self.check_local_addr(&dst)?;
+                self.connect_with_maybe_proxy(dst, false).await?
+            }
+        };
+
+        // checked once the certificate was validated, whichever way the
+        // TLS connection was made
+        #[cfg(feature = "__tls")]
+        if let Some((host, pins)) = pins {
+            let info = conn.inner.tls_info();
+            let cert = info.as_ref().and_then(|info| info.peer_certificate());
+            crate::pin::verify(&host, cert, &pins)?;
+        }
+        Ok(conn)
+    }
+
+    /// Finds the proxy intercepting `dst`, if any.
+    async fn proxy_for(&self, dst: &Uri) -> Result<Option<ProxyScheme>, BoxError> {
+        let mut proxy_scheme = None;
+        for (index, proxy) in self.proxies.iter().enumerate() {
+            if proxy.is_direct(dst) {
+                break;
+            }
+            proxy_scheme = match self.request_proxies {
+                Some(ref chosen) if proxy.is_by_request() => chosen.get(index).cloned(),
+                _ => proxy.intercept_async(dst).await?,
+            };
+            if proxy_scheme.is_some() {
+                if let Some(ref resolver) = self.no_proxy_resolver {
+                    if proxy.resolves_into_no_proxy(dst, resolver).await {
+                        proxy_scheme = None;
+                        continue;
+                    }
+                }
+                break;
+            }
+        }
+        Ok(proxy_scheme)
+    }
+
+    /// Opens a tunnel to the authority of `dst`, with a CONNECT request
+    /// to the HTTP proxy intercepting it. See `Client::connect_tunnel`.
+    pub(crate) async fn connect_tunnel(self, dst: Uri) -> Result<hyper::upgrade::Upgraded, BoxError> {
+        let timeout = CONNECT_TIMEOUT.with(Cell::get).or(self.timeout);
+        with_timeout(self.tunnel_through_proxy(dst), timeout).await
+    }
+
+    async fn tunnel_through_proxy(self, dst: Uri) -> Result<hyper::upgrade::Upgraded, BoxError> {
+        use futures_util::future::{self, Either};
+        use http::header::{HOST, PROXY_AUTHORIZATION};
+        use http::{Method, Request, StatusCode};
+
+        let (proxy_dst, auth, misc) = match self.proxy_for(&dst).await? {
+            Some(ProxyScheme::Http { host, auth, misc }) => {
+                (into_uri(Scheme::HTTP, host), auth, misc)
+            }
+            Some(ProxyScheme::Https { host, auth, misc }) => {
+                (into_uri(Scheme::HTTPS, host), auth, misc)
+            }
+            #[cfg(feature = "socks")]
+            Some(_) => return Err("CONNECT tunnels need an HTTP proxy, not a SOCKS one".into()),
+            None => return Err("no proxy intercepts the tunnel".into()),
+        };
+        let authority = dst.authority().ok_or("no host in url")?.as_str().to_owned();
+        log::debug!("tunneling to {authority} over proxy {proxy_dst:?}");
+
+        let mut req = Request::builder()
+            .method(Method::CONNECT)
+            .uri(&authority)
+            .header(HOST, &authority)
+            .body(http_body_util::Empty::<bytes::Bytes>::new())?;
+        let headers = req.headers_mut();
+        #[cfg(feature = "__tls")]
+        if let Some(ref user_agent) = self.user_agent {
+            headers.insert(http::header::USER_AGENT, user_agent.clone());
+        }
+        if let Some(misc) = misc {
+            headers.extend(misc);
+        }
+        if let Some(auth) = auth {
+            headers.insert(PROXY_AUTHORIZATION, auth);
+        }
+
+        self.check_local_addr(&proxy_dst)?;
+        let conn = self.connect_with_maybe_proxy(proxy_dst, true).await?;
+
+        // hyper hands over the connection on a 2xx (or 101) response to
+        // CONNECT, along with any tunneled bytes it already read
+        let (mut sender, conn) = hyper::client::conn::http1::handshake(conn).await?;
+        let upgrade = async move {
+            let res = sender.send_request(req).await?;
+            match res.status() {
+                status if status.is_success() || status == StatusCode::SWITCHING_PROTOCOLS => {
+                    Ok(hyper::upgrade::on(res).await?)
+                }
+                StatusCode::PROXY_AUTHENTICATION_REQUIRED => {
+                    Err("proxy authentication required".into())
+                }
+                status => Err(format!("unsuccessful tunnel: {status}").into()),
+            }
+        };
+        futures_util::pin_mut!(upgrade);
+        match future::select(upgrade, conn.with_upgrades()).await {
+            Either::Left((upgraded, _)) => upgraded,
+            Either::Right((Ok(()), upgrade)) => upgrade.await,
+            Either::Right((Err(err), _)) => Err(err.into()),
+        }
+    }
+
+    /// Fails if `dst` is an IP address of another family than the local
+    /// address, since a socket bound to it could never connect there.
+    ///
+    /// Host names are checked once resolved, by `DnsResolverWithLocalAddress`.
+    fn check_local_addr(&self, dst: &Uri) -> Result<(), BoxError> {
+        let local = match self.local_addr {
+            Some(local) => local,
+            None => return Ok(()),
+        };
+        let host = dst.host().unwrap_or("");
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        match host.parse::<IpAddr>() {
+            Ok(remote) if remote.is_ipv4() != local.is_ipv4() => {
+                Err(crate::dns::local_address_mismatch(local, host))
+            }
+            _ => Ok(()),
+        }
+    }
+
     async fn connect_with_maybe_proxy(self, dst: Uri, is_proxy: bool) -> Result<Conn, BoxError> {
+        #[cfg(feature = "__tls")]
+        let sni_override = self.sni_override(&dst).map(str::to_owned);
         match self.inner {
             #[cfg(not(feature = "__tls"))]
             Inner::Http(mut http) => {
@@ -280,6 +712,11 @@ impl Connector {
                     inner: self.verbose.wrap(io),
                     is_proxy,
                     tls_info: false,
+                    timings: None,
+                    permit: None,
+                    pool: None,
+                    trace: None,
+                    info: None,
                 })
             }
             #[cfg(feature = "default-tls")]
@@ -293,8 +730,41 @@ impl Connector {
                     http.set_nodelay(true);
                 }
 
-                let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
-                let mut http = hyper_tls::HttpsConnector::from((http, tls_connector));
+                let tls_connector = match &sni_override {
+                    Some(server_name) => native_tls_for(&tls, &self.insecure_tls, server_name),
+                    None => native_tls_for(&tls, &self.insecure_tls, dst.host().unwrap_or("")),
+                };
+
+                // hyper-tls only uses the host as server name
+                if let Some(server_name) = sni_override {
+                    let tcp = http.call(dst).await?;
+                    timings::mark_connected();
+                    let io = tls_connector
+                        .connect(&server_name, TokioIo::new(tcp))
+                        .await?;
+                    if !self.nodelay {
+                        io.get_ref()
+                            .get_ref()
+                            .get_ref()
+                            .inner()
+                            .inner()
+                            .set_nodelay(false)?;
+                    }
+                    return Ok(Conn {
+                        inner: self.verbose.wrap(NativeTlsConn {
+                            inner: TokioIo::new(io),
+                        }),
+                        is_proxy,
+                        tls_info: self.tls_info,
+                        timings: None,
+                        info: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                    });
+                }
+
+                let mut http = hyper_tls::HttpsConnector::from((MarkConnected(http), tls_connector));
                 let io = http.call(dst).await?;
 
                 if let hyper_tls::MaybeHttpsStream::Https(stream) = io {
@@ -312,17 +782,32 @@ impl Connector {
                         inner: self.verbose.wrap(NativeTlsConn { inner: stream }),
                         is_proxy,
                         tls_info: self.tls_info,
+                        timings: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                        info: None,
                     })
                 } else {
                     Ok(Conn {
                         inner: self.verbose.wrap(io),
                         is_proxy,
                         tls_info: false,
+                        timings: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                        info: None,
                     })
                 }
             }
             #[cfg(feature = "__rustls")]
-            Inner::RustlsTls { http, tls, .. } => {
+            Inner::RustlsTls {
+                http,
+                tls,
+                tls_per_host,
+                ..
+            } => {
                 let mut http = http.clone();
 
                 // Disable Nagle's algorithm for TLS handshake
@@ -332,7 +817,38 @@ impl Connector {
                     http.set_nodelay(true);
                 }
 
-                let mut http = hyper_rustls::HttpsConnector::from((http, tls.clone()));
+                let tls = rustls_config_for(&tls, &tls_per_host, &dst);
+
+                // hyper-rustls only uses the host as server name
+                if let Some(server_name) = sni_override {
+                    use std::convert::TryFrom;
+
+                    let server_name = rustls_pki_types::ServerName::try_from(server_name)
+                        .map_err(|_| "Invalid Server Name")?;
+                    let tcp = http.call(dst).await?;
+                    timings::mark_connected();
+                    let io = tokio_rustls::TlsConnector::from(tls)
+                        .connect(server_name, TokioIo::new(tcp))
+                        .await?;
+                    if !self.nodelay {
+                        let (io, _) = io.get_ref();
+                        io.inner().inner().set_nodelay(false)?;
+                    }
+                    return Ok(Conn {
+                        inner: self.verbose.wrap(RustlsTlsConn {
+                            inner: TokioIo::new(io),
+                        }),
+                        is_proxy,
+                        tls_info: self.tls_info,
+                        timings: None,
+                        info: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                    });
+                }
+
+                let mut http = hyper_rustls::HttpsConnector::from((MarkConnected(http), tls));
                 let io = http.call(dst).await?;
 
                 if let hyper_rustls::MaybeHttpsStream::Https(stream) = io {
@@ -344,12 +860,22 @@ impl Connector {
                         inner: self.verbose.wrap(RustlsTlsConn { inner: stream }),
                         is_proxy,
                         tls_info: self.tls_info,
+                        timings: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                        info: None,
                     })
                 } else {
                     Ok(Conn {
                         inner: self.verbose.wrap(io),
                         is_proxy,
                         tls_info: false,
+                        timings: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                        info: None,
                     })
                 }
             }
@@ -363,15 +889,21 @@ impl Connector {
     ) -> Result<Conn, BoxError> {
         log::debug!("proxy({proxy_scheme:?}) intercepts '{dst:?}'");
 
-        let (proxy_dst, _auth) = match proxy_scheme {
-            ProxyScheme::Http { host, auth } => (into_uri(Scheme::HTTP, host), auth),
-            ProxyScheme::Https { host, auth } => (into_uri(Scheme::HTTPS, host), auth),
+        let (proxy_dst, _auth, _misc) = match proxy_scheme {
+            ProxyScheme::Http { host, auth, misc } => (into_uri(Scheme::HTTP, host), auth, misc),
+            ProxyScheme::Https { host, auth, misc } => (into_uri(Scheme::HTTPS, host), auth, misc),
             #[cfg(feature = "socks")]
-            ProxyScheme::Socks5 { .. } => return self.connect_socks(dst, proxy_scheme).await,
+            ProxyScheme::Socks5 { .. } | ProxyScheme::Socks4 { .. } => {
+                return self.connect_socks(dst, proxy_scheme).await
+            }
         };
 
+        self.check_local_addr(&proxy_dst)?;
+
         #[cfg(feature = "__tls")]
         let auth = _auth;
+        #[cfg(feature = "__tls")]
+        let misc = _misc;
 
         match &self.inner {
             #[cfg(feature = "default-tls")]
@@ -380,8 +912,9 @@ impl Connector {
                     let host = dst.host().to_owned();
                     let port = dst.port().map(|p| p.as_u16()).unwrap_or(443);
                     let http = http.clone();
-                    let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
-                    let mut http = hyper_tls::HttpsConnector::from((http, tls_connector));
+                    let proxy_host = proxy_dst.host().unwrap_or("");
+                    let tls_connector = native_tls_for(tls, &self.insecure_tls, proxy_host);
+                    let mut http = hyper_tls::HttpsConnector::from((MarkConnected(http), tls_connector));
                     let conn = http.call(proxy_dst).await?;
                     log::trace!("tunneling HTTPS over proxy");
                     let tunneled = tunnel(
@@ -390,11 +923,14 @@ impl Connector {
                         port,
                         self.user_agent.clone(),
                         auth,
+                        misc,
                     )
                     .await?;
-                    let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
+                    timings::mark_connected();
+                    let server_name = self.sni_override(&dst).or(host).ok_or("no host in url")?;
+                    let tls_connector = native_tls_for(tls, &self.insecure_tls, server_name);
                     let io = tls_connector
-                        .connect(host.ok_or("no host in url")?, TokioIo::new(tunneled))
+                        .connect(server_name, TokioIo::new(tunneled))
                         .await?;
                     return Ok(Conn {
                         inner: self.verbose.wrap(NativeTlsConn {
@@ -402,6 +938,11 @@ impl Connector {
                         }),
                         is_proxy: false,
                         tls_info: false,
+                        timings: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                        info: None,
                     });
                 }
             }
@@ -410,6 +951,7 @@ impl Connector {
                 http,
                 tls,
                 tls_proxy,
+                tls_per_host,
             } => {
                 if dst.scheme() == Some(&Scheme::HTTPS) {
                     use rustls_pki_types::ServerName;
@@ -419,13 +961,16 @@ impl Connector {
                     let host = dst.host().ok_or("no host in url")?.to_string();
                     let port = dst.port().map(|r| r.as_u16()).unwrap_or(443);
                     let http = http.clone();
-                    let mut http = hyper_rustls::HttpsConnector::from((http, tls_proxy.clone()));
-                    let tls = tls.clone();
+                    let mut http =
+                        hyper_rustls::HttpsConnector::from((MarkConnected(http), tls_proxy.clone()));
+                    let tls = rustls_config_for(tls, tls_per_host, &dst);
                     let conn = http.call(proxy_dst).await?;
                     log::trace!("tunneling HTTPS over proxy");
-                    let maybe_server_name = ServerName::try_from(host.as_str().to_owned())
+                    let server_name = self.sni_override(&dst).unwrap_or(&host);
+                    let maybe_server_name = ServerName::try_from(server_name.to_owned())
                         .map_err(|_| "Invalid Server Name");
-                    let tunneled = tunnel(conn, host, port, self.user_agent.clone(), auth).await?;
+                    let tunneled = tunnel(conn, host, port, self.user_agent.clone(), auth, misc).await?;
+                    timings::mark_connected();
                     let server_name = maybe_server_name?;
                     let io = RustlsConnector::from(tls)
                         .connect(server_name, TokioIo::new(tunneled))
@@ -437,6 +982,11 @@ impl Connector {
                         }),
                         is_proxy: false,
                         tls_info: false,
+                        timings: None,
+                        permit: None,
+                        pool: None,
+                        trace: None,
+                        info: None,
                     });
                 }
             }
@@ -457,6 +1007,61 @@ impl Connector {
             Inner::Http(http) => http.set_keepalive(dur),
         }
     }
+
+    pub fn set_keepalive_interval(&mut self, interval: Option<Duration>) {
+        #[cfg(not(any(
+            target_os = "android",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "fuchsia",
+            target_os = "illumos",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "windows",
+        )))]
+        if interval.is_some() {
+            log::warn!("tcp_keepalive_interval is not supported on this platform, ignoring it");
+        }
+        match &mut self.inner {
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(http, _tls) => http.set_keepalive_interval(interval),
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls { http, .. } => http.set_keepalive_interval(interval),
+            #[cfg(not(feature = "__tls"))]
+            Inner::Http(http) => http.set_keepalive_interval(interval),
+        }
+    }
+
+    pub fn set_keepalive_retries(&mut self, retries: Option<u32>) {
+        #[cfg(not(any(
+            target_os = "android",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "fuchsia",
+            target_os = "illumos",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "tvos",
+            target_os = "watchos",
+        )))]
+        if retries.is_some() {
+            log::warn!("tcp_keepalive_retries is not supported on this platform, ignoring it");
+        }
+        match &mut self.inner {
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(http, _tls) => http.set_keepalive_retries(retries),
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls { http, .. } => http.set_keepalive_retries(retries),
+            #[cfg(not(feature = "__tls"))]
+            Inner::Http(http) => http.set_keepalive_retries(retries),
+        }
+    }
 }
 
 fn into_uri(scheme: Scheme, host: Authority) -> Uri {
@@ -469,6 +1074,63 @@ fn into_uri(scheme: Scheme, host: Authority) -> Uri {
         .expect("scheme and authority is valid Uri")
 }
 
+thread_local! {
+    static CONNECT_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+    static REQUEST_PROXIES: RefCell<Option<RequestProxies>> = const { RefCell::new(None) };
+}
+
+/// Overrides the connect timeout of any connection started on this thread
+/// while the guard is alive.
+///
+/// hyper only hands the destination `Uri` to the `Connector`, so a
+/// per-request connect timeout is passed along this way instead. The
+/// connector is called synchronously from the first poll of the request
+/// future, which is where the guard should be held.
+pub(crate) struct ConnectTimeoutGuard {
+    prev: Option<Duration>,
+}
+
+impl ConnectTimeoutGuard {
+    pub(crate) fn enter(timeout: Option<Duration>) -> ConnectTimeoutGuard {
+        let prev = CONNECT_TIMEOUT.with(|cell| match timeout {
+            Some(timeout) => cell.replace(Some(timeout)),
+            None => cell.get(),
+        });
+        ConnectTimeoutGuard { prev }
+    }
+}
+
+impl Drop for ConnectTimeoutGuard {
+    fn drop(&mut self) {
+        CONNECT_TIMEOUT.with(|cell| cell.set(self.prev));
+    }
+}
+
+/// Sets the proxies chosen by a request for any connection started on this
+/// thread while the guard is alive, the way `ConnectTimeoutGuard` does.
+pub(crate) struct RequestProxiesGuard {
+    prev: Option<RequestProxies>,
+}
+
+impl RequestProxiesGuard {
+    pub(crate) fn enter(chosen: Option<RequestProxies>) -> RequestProxiesGuard {
+        let prev = REQUEST_PROXIES.with(|cell| cell.replace(chosen));
+        RequestProxiesGuard { prev }
+    }
+
+    /// Replaces the chosen proxies, as when following a redirect.
+    pub(crate) fn set(&self, chosen: Option<RequestProxies>) {
+        REQUEST_PROXIES.with(|cell| *cell.borrow_mut() = chosen);
+    }
+}
+
+impl Drop for RequestProxiesGuard {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        REQUEST_PROXIES.with(|cell| *cell.borrow_mut() = prev);
+    }
+}
+
 async fn with_timeout<T, F>(f: F, timeout: Option<Duration>) -> Result<T, BoxError>
 where
     F: Future<Output = Result<T, BoxError>>,
@@ -495,21 +1157,113 @@ impl Service<Uri> for Connector {
 
     fn call(&mut self, dst: Uri) -> Self::Future {
         log::debug!("starting new connection: {dst:?}");
-        let timeout = self.timeout;
-        for prox in self.proxies.iter() {
-            if let Some(proxy_scheme) = prox.intercept(&dst) {
-                return Box::pin(with_timeout(
-                    self.clone().connect_via_proxy(dst, proxy_scheme),
-                    timeout,
-                ));
+        let timeout = CONNECT_TIMEOUT.with(Cell::get).or(self.timeout);
+        let mut connector = self.clone();
+        connector.request_proxies = REQUEST_PROXIES.with(|cell| cell.borrow().clone());
+        let pending = self.pool.connecting(&dst);
+        match self.host_limit {
+            // The wait for a free slot doesn't count towards the connect
+            // timeout, nor the connect timings.
+            Some(ref limit) => {
+                let acquire = limit.acquire(&dst);
+                Box::pin(async move {
+                    let permit = acquire.await;
+                    let mut conn = connector.connecting(dst, timeout).await?;
+                    conn.permit = Some(permit);
+                    conn.pool = Some(pending.opened());
+                    Ok(conn)
+                })
+            }
+            None => {
+                let connecting = connector.connecting(dst, timeout);
+                Box::pin(async move {
+                    let mut conn = connecting.await?;
+                    conn.pool = Some(pending.opened());
+                    Ok(conn)
+                })
             }
         }
+    }
+}
 
-        Box::pin(with_timeout(
-            self.clone().connect_with_maybe_proxy(dst, false),
-            timeout,
-        ))
+/// Caps how many connections can be open to each host at once.
+///
+/// Every connection holds a permit from its host's semaphore until it is
+/// closed, so a new one waits for an existing one to close first. HTTP/2
+/// streams share their connection's permit.
+#[derive(Clone)]
+struct HostLimit {
+    max: usize,
+    hosts: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl HostLimit {
+    fn new(max: usize) -> HostLimit {
+        HostLimit {
+            max,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn acquire(&self, dst: &Uri) -> impl Future<Output = OwnedSemaphorePermit> {
+        let key = format!(
+            "{}://{}",
+            dst.scheme_str().unwrap_or(""),
+            dst.authority().map_or("", Authority::as_str)
+        );
+        let semaphore = {
+            let mut hosts = self.hosts.lock().unwrap();
+            // drop hosts that have no connections, or any waiting for one
+            hosts.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+            hosts
+                .entry(key)
+                .or_insert_with(|| Arc::new(Semaphore::new(self.max)))
+                .clone()
+        };
+        async move {
+            semaphore
+                .acquire_owned()
+                .await
+                .expect("host semaphore is never closed")
+        }
+    }
+}
+
+/// The native-tls connector to use when the certificate is checked for
+/// `host`, which doesn't validate it if `host` is one of the insecure ones.
+#[cfg(feature = "default-tls")]
+fn native_tls_for(
+    tls: &TlsConnector,
+    insecure_tls: &Option<(Arc<Vec<String>>, TlsConnector)>,
+    host: &str,
+) -> tokio_native_tls::TlsConnector {
+    let tls = match insecure_tls {
+        Some((patterns, insecure)) if crate::tls::matches_any_host_pattern(patterns, host) => {
+            insecure
+        }
+        _ => tls,
+    };
+    tokio_native_tls::TlsConnector::from(tls.clone())
+}
+
+/// The TLS config for connecting to `dst`, which has its own if a client
+/// certificate was set for its host.
+#[cfg(feature = "__rustls")]
+fn rustls_config_for(
+    tls: &Arc<rustls::ClientConfig>,
+    tls_per_host: &HashMap<String, Arc<rustls::ClientConfig>>,
+    dst: &Uri,
+) -> Arc<rustls::ClientConfig> {
+    if tls_per_host.is_empty() {
+        return tls.clone();
     }
+    let host = dst
+        .host()
+        .unwrap_or("")
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase();
+    tls_per_host.get(&host).unwrap_or(tls).clone()
 }
 
 #[cfg(feature = "__tls")]
@@ -524,6 +1278,13 @@ impl TlsInfoFactory for tokio::net::TcpStream {
     }
 }
 
+#[cfg(all(feature = "__tls", unix, feature = "unix-socket"))]
+impl TlsInfoFactory for tokio::net::UnixStream {
+    fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+        None
+    }
+}
+
 #[cfg(feature = "__tls")]
 impl<T: TlsInfoFactory> TlsInfoFactory for TokioIo<T> {
     fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
@@ -531,16 +1292,32 @@ impl<T: TlsInfoFactory> TlsInfoFactory for TokioIo<T> {
     }
 }
 
+// native-tls can't report the negotiated protocol version or cipher suite
+#[cfg(feature = "default-tls")]
+fn native_tls_info<S: std::io::Read + std::io::Write>(
+    stream: &native_tls_crate::TlsStream<S>,
+) -> crate::tls::TlsInfo {
+    let peer_certificate = stream
+        .peer_certificate()
+        .ok()
+        .flatten()
+        .and_then(|c| c.to_der().ok());
+    #[cfg(feature = "native-tls-alpn")]
+    let alpn_protocol = stream.negotiated_alpn().ok().flatten();
+    #[cfg(not(feature = "native-tls-alpn"))]
+    let alpn_protocol = None;
+    crate::tls::TlsInfo {
+        peer_certificate,
+        protocol_version: None,
+        cipher_suite: None,
+        alpn_protocol,
+    }
+}
+
 #[cfg(feature = "default-tls")]
 impl TlsInfoFactory for tokio_native_tls::TlsStream<TokioIo<TokioIo<tokio::net::TcpStream>>> {
     fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
-        let peer_certificate = self
-            .get_ref()
-            .peer_certificate()
-            .ok()
-            .flatten()
-            .and_then(|c| c.to_der().ok());
-        Some(crate::tls::TlsInfo { peer_certificate })
+        Some(native_tls_info(self.get_ref()))
     }
 }
 
@@ -551,13 +1328,7 @@ impl TlsInfoFactory
     >
 {
     fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
-        let peer_certificate = self
-            .get_ref()
-            .peer_certificate()
-            .ok()
-            .flatten()
-            .and_then(|c| c.to_der().ok());
-        Some(crate::tls::TlsInfo { peer_certificate })
+        Some(native_tls_info(self.get_ref()))
     }
 }
 
@@ -571,16 +1342,28 @@ impl TlsInfoFactory for hyper_tls::MaybeHttpsStream<TokioIo<tokio::net::TcpStrea
     }
 }
 
+#[cfg(feature = "__rustls")]
+fn rustls_tls_info(conn: &rustls::ClientConnection) -> crate::tls::TlsInfo {
+    let peer_certificate = conn
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .map(|c| c.to_vec());
+    crate::tls::TlsInfo {
+        peer_certificate,
+        protocol_version: conn
+            .protocol_version()
+            .and_then(crate::tls::Version::from_rustls),
+        cipher_suite: conn
+            .negotiated_cipher_suite()
+            .and_then(|suite| suite.suite().as_str()),
+        alpn_protocol: conn.alpn_protocol().map(<[u8]>::to_vec),
+    }
+}
+
 #[cfg(feature = "__rustls")]
 impl TlsInfoFactory for tokio_rustls::client::TlsStream<TokioIo<TokioIo<tokio::net::TcpStream>>> {
     fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
-        let peer_certificate = self
-            .get_ref()
-            .1
-            .peer_certificates()
-            .and_then(|certs| certs.first())
-            .map(|c| c.to_vec());
-        Some(crate::tls::TlsInfo { peer_certificate })
+        Some(rustls_tls_info(self.get_ref().1))
     }
 }
 
@@ -591,13 +1374,7 @@ impl TlsInfoFactory
     >
 {
     fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
-        let peer_certificate = self
-            .get_ref()
-            .1
-            .peer_certificates()
-            .and_then(|certs| certs.first())
-            .map(|c| c.to_vec());
-        Some(crate::tls::TlsInfo { peer_certificate })
+        Some(rustls_tls_info(self.get_ref().1))
     }
 }
 
@@ -641,12 +1418,41 @@ pin_project! {
         is_proxy: bool,
         // Only needed for __tls, but #[cfg()] on fields breaks pin_project!
         tls_info: bool,
+        timings: Option<ConnectTimings>,
+        info: Option<ConnectionInfo>,
+        // Held for as long as the connection is open, if connections per
+        // host are limited.
+        permit: Option<OwnedSemaphorePermit>,
+        // Keeps the connection counted as open in the pool stats.
+        pool: Option<pool::Open>,
+        // Emits the `tracing` events of the connection, if verbose.
+        trace: Option<trace::Connection>,
     }
 }
 
 impl Connection for Conn {
     fn connected(&self) -> Connected {
-        let connected = self.inner.connected().proxy(self.is_proxy);
+        let connected = self
+            .inner
+            .connected()
+            .proxy(self.is_proxy)
+            .extra(ConnectionUse::default());
+        let connected = match self.pool {
+            Some(ref open) => connected.extra(open.activity()),
+            None => connected,
+        };
+        let connected = match self.timings {
+            Some(timings) => connected.extra(timings),
+            None => connected,
+        };
+        let connected = match self.trace {
+            Some(ref trace) => connected.extra(trace.responses()),
+            None => connected,
+        };
+        let connected = match self.info {
+            Some(ref info) => connected.extra(info.clone()),
+            None => connected,
+        };
         #[cfg(feature = "__tls")]
         if self.tls_info {
             if let Some(tls_info) = self.inner.tls_info() {
@@ -662,14 +1468,121 @@ impl Connection for Conn {
     }
 }
 
+/// Tracks whether a connection has already carried a response.
+///
+/// A new one is attached to every connection, and hyper gives a clone of it
+/// to each response received over that connection, so all of them share the
+/// same flag, whether they are HTTP/1 requests in turn or HTTP/2 streams.
+#[derive(Clone, Default)]
+pub(crate) struct ConnectionUse(Arc<AtomicBool>);
+
+impl ConnectionUse {
+    /// Marks the connection as used, returning whether it already was.
+    pub(crate) fn mark_used(&self) -> bool {
+        self.0.swap(true, Ordering::AcqRel)
+    }
+}
+
+/// Whether a response came over a connection that carried an earlier one.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ConnectionReused(pub(crate) bool);
+
+pub(crate) type ConnectionHook = Arc<dyn Fn(ConnectionInfo) + Send + Sync>;
+
+/// Details about a connection, passed to the hooks set with
+/// `ClientBuilder::on_new_connection` and
+/// `ClientBuilder::on_connection_reuse`.
+#[derive(Clone, Debug)]
+pub struct ConnectionInfo {
+    remote_addr: Option<SocketAddr>,
+    #[cfg(feature = "__tls")]
+    tls_version: Option<crate::tls::Version>,
+    alpn_protocol: Option<Vec<u8>>,
+    handshake_duration: Duration,
+}
+
+impl ConnectionInfo {
+    fn new(conn: &Conn, handshake_duration: Duration) -> ConnectionInfo {
+        let connected = conn.inner.connected();
+        let mut extras = http::Extensions::new();
+        connected.get_extras(&mut extras);
+        let remote_addr = extras
+            .get::<hyper_util::client::legacy::connect::HttpInfo>()
+            .map(|info| info.remote_addr());
+
+        #[cfg(feature = "__tls")]
+        let tls = conn.inner.tls_info();
+        #[cfg(feature = "__tls")]
+        let alpn_protocol = tls.as_ref().and_then(|tls| tls.alpn_protocol.clone());
+        #[cfg(not(feature = "__tls"))]
+        let alpn_protocol = None;
+
+        ConnectionInfo {
+            remote_addr,
+            #[cfg(feature = "__tls")]
+            tls_version: tls.and_then(|tls| tls.protocol_version),
+            alpn_protocol: alpn_protocol
+                .or_else(|| connected.is_negotiated_h2().then(|| b"h2".to_vec())),
+            handshake_duration,
+        }
+    }
+
+    /// The address of the peer, or of the proxy the connection goes
+    /// through.
+    ///
+    /// This is `None` if it isn't known, such as for a Unix socket.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    /// The negotiated TLS protocol version, if the connection uses TLS and
+    /// the TLS backend reports it.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_version(&self) -> Option<crate::tls::Version> {
+        self.tls_version
+    }
+
+    /// The protocol negotiated with ALPN, such as `b"h2"`.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn_protocol.as_deref()
+    }
+
+    /// How long it took to open the connection, from resolving the host to
+    /// the end of the TLS handshake, if any.
+    pub fn handshake_duration(&self) -> Duration {
+        self.handshake_duration
+    }
+}
+
 impl Read for Conn {
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context,
-        buf: ReadBufCursor<'_>,
+        mut buf: ReadBufCursor<'_>,
     ) -> Poll<io::Result<()>> {
         let this = self.project();
-        Read::poll_read(this.inner, cx, buf)
+        let trace = match this.trace {
+            Some(trace) if !trace.has_received() => trace,
+            _ => return Read::poll_read(this.inner, cx, buf),
+        };
+        // read through a buffer of our own to see how much was read
+        //
+        // Safety: only bytes the inner connection filled are marked as
+        // filled in `buf`.
+        let mut inner = hyper::rt::ReadBuf::uninit(unsafe { buf.as_mut() });
+        futures_core::ready!(Read::poll_read(this.inner, cx, inner.unfilled()))?;
+        let n = inner.filled().len();
+        unsafe { buf.advance(n) };
+        trace.read(n);
+        Poll::Ready(Ok(()))
     }
 }
 
@@ -680,7 +1593,11 @@ impl Write for Conn {
         buf: &[u8],
     ) -> Poll<Result<usize, io::Error>> {
         let this = self.project();
-        Write::poll_write(this.inner, cx, buf)
+        let n = futures_core::ready!(Write::poll_write(this.inner, cx, buf))?;
+        if let Some(trace) = this.trace {
+            trace.wrote(n);
+        }
+        Poll::Ready(Ok(n))
     }
 
     fn poll_write_vectored(
@@ -689,7 +1606,11 @@ impl Write for Conn {
         bufs: &[IoSlice<'_>],
     ) -> Poll<Result<usize, io::Error>> {
         let this = self.project();
-        Write::poll_write_vectored(this.inner, cx, bufs)
+        let n = futures_core::ready!(Write::poll_write_vectored(this.inner, cx, bufs))?;
+        if let Some(trace) = this.trace {
+            trace.wrote(n);
+        }
+        Poll::Ready(Ok(n))
     }
 
     fn is_write_vectored(&self) -> bool {
@@ -716,6 +1637,7 @@ async fn tunnel<T>(
     port: u16,
     user_agent: Option<HeaderValue>,
     auth: Option<HeaderValue>,
+    misc: Option<HeaderMap>,
 ) -> Result<T, BoxError>
 where
     T: Read + Write + Unpin,
@@ -739,6 +1661,7 @@ where
     }
 
     // proxy-authorization
+    let auth_set = auth.is_some();
     if let Some(value) = auth {
         log::debug!("tunnel to {host}:{port} using basic auth");
         buf.extend_from_slice(b"Proxy-Authorization: ");
@@ -746,6 +1669,19 @@ where
         buf.extend_from_slice(b"\r\n");
     }
 
+    // custom proxy headers
+    if let Some(misc) = misc {
+        for (name, value) in &misc {
+            if name == http::header::PROXY_AUTHORIZATION && auth_set {
+                continue;
+            }
+            buf.extend_from_slice(name.as_str().as_bytes());
+            buf.extend_from_slice(b": ");
+            buf.extend_from_slice(value.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+    }
+
     // headers end
     buf.extend_from_slice(b"\r\n");
 
@@ -1031,7 +1967,7 @@ mod socks {
 
     use http::Uri;
     use tokio::net::TcpStream;
-    use tokio_socks::tcp::Socks5Stream;
+    use tokio_socks::tcp::{Socks4Stream, Socks5Stream};
 
     use super::{BoxError, Scheme};
     use crate::proxy::ProxyScheme;
@@ -1057,6 +1993,22 @@ mod socks {
             _ => 80u16,
         };
 
+        if let ProxyScheme::Socks4 { addr, .. } = proxy {
+            if let DnsResolve::Local = dns {
+                // SOCKS4 only carries IPv4 addresses
+                let target = (host.as_str(), port)
+                    .to_socket_addrs()?
+                    .find(|addr| addr.is_ipv4())
+                    .ok_or("socks4 proxies can only connect to IPv4 addresses")?;
+                host = target.ip().to_string();
+            }
+            // a hostname makes tokio-socks use SOCKS4A
+            let stream = Socks4Stream::connect(addr, (host.as_str(), port))
+                .await
+                .map_err(|e| connect_error(e, false))?;
+            return Ok(stream.into_inner());
+        }
+
         if let DnsResolve::Local = dns {
             let maybe_new_target = (host.as_str(), port).to_socket_addrs()?.next();
             if let Some(new_target) = maybe_new_target {
@@ -1078,15 +2030,29 @@ mod socks {
                 &password,
             )
             .await
-            .map_err(|e| format!("socks connect error: {e}"))?
+            .map_err(|e| connect_error(e, true))?
         } else {
             Socks5Stream::connect(socket_addr, (host.as_str(), port))
                 .await
-                .map_err(|e| format!("socks connect error: {e}"))?
+                .map_err(|e| connect_error(e, false))?
         };
 
         Ok(stream.into_inner())
     }
+
+    fn connect_error(err: tokio_socks::Error, has_auth: bool) -> BoxError {
+        use tokio_socks::Error;
+
+        match err {
+            // the proxy picked a method we didn't offer, or none of ours
+            Error::NoAcceptableAuthMethods | Error::UnknownAuthMethod if !has_auth => {
+                "socks proxy requires authentication, but no username and password were given"
+                    .into()
+            }
+            Error::PasswordAuthFailure(_) => "socks proxy rejected the username and password".into(),
+            err => format!("socks connect error: {err}").into(),
+        }
+    }
 }
 
 mod verbose {
@@ -1271,6 +2237,66 @@ mod tests {
     use tokio::net::TcpStream;
     use tokio::runtime;
 
+    #[cfg(feature = "__rustls")]
+    #[test]
+    fn rustls_config_for_host() {
+        use super::rustls_config_for;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let config = || {
+            Arc::new(
+                rustls::ClientConfig::builder()
+                    .with_root_certificates(rustls::RootCertStore::empty())
+                    .with_no_client_auth(),
+            )
+        };
+        let default = config();
+        let mut per_host = HashMap::new();
+        per_host.insert("mtls.example".to_owned(), config());
+        per_host.insert("::1".to_owned(), config());
+
+        let pick = |uri: &str| rustls_config_for(&default, &per_host, &uri.parse().unwrap());
+        assert!(Arc::ptr_eq(&pick("https://MTLS.example:8443/"), &per_host["mtls.example"]));
+        assert!(Arc::ptr_eq(&pick("https://[::1]/"), &per_host["::1"]));
+        assert!(Arc::ptr_eq(&pick("https://other.example/"), &default));
+    }
+
+    #[cfg(feature = "__rustls")]
+    #[test]
+    fn sni_override_for_host() {
+        use super::{Connector, HttpConnector};
+        use crate::dns::{gai::GaiResolver, DynResolver};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let tls = rustls::ClientConfig::builder()
+            .with_root_certificates(rustls::RootCertStore::empty())
+            .with_no_client_auth();
+        let http = HttpConnector::new_with_resolver(DynResolver::new(Arc::new(GaiResolver::new())));
+        let mut connector = Connector::new_rustls_tls(
+            http,
+            tls,
+            Arc::new(Vec::new()),
+            None,
+            None,
+            #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+            None,
+            true,
+            false,
+        );
+        let mut overrides = HashMap::new();
+        overrides.insert("10.0.0.1".to_owned(), "api.example".to_owned());
+        overrides.insert("::1".to_owned(), "local.example".to_owned());
+        connector.set_sni_overrides(overrides);
+
+        let name = |uri: &str| connector.sni_override(&uri.parse().unwrap());
+        assert_eq!(name("https://10.0.0.1:8443/"), Some("api.example"));
+        assert_eq!(name("https://[::1]/"), Some("local.example"));
+        assert_eq!(name("http://10.0.0.1/"), None);
+        assert_eq!(name("https://10.0.0.2/"), None);
+    }
+
     static TUNNEL_UA: &str = "tunnel-test/x.y";
     static TUNNEL_OK: &[u8] = b"\
         HTTP/1.1 200 OK\r\n\
@@ -1330,7 +2356,7 @@ mod tests {
             let tcp = TokioIo::new(TcpStream::connect(&addr).await?);
             let host = addr.ip().to_string();
             let port = addr.port();
-            tunnel(tcp, host, port, ua(), None).await
+            tunnel(tcp, host, port, ua(), None, None).await
         };
 
         rt.block_on(f).unwrap();
@@ -1348,7 +2374,7 @@ mod tests {
             let tcp = TokioIo::new(TcpStream::connect(&addr).await?);
             let host = addr.ip().to_string();
             let port = addr.port();
-            tunnel(tcp, host, port, ua(), None).await
+            tunnel(tcp, host, port, ua(), None, None).await
         };
 
         rt.block_on(f).unwrap_err();
@@ -1366,7 +2392,7 @@ mod tests {
             let tcp = TokioIo::new(TcpStream::connect(&addr).await?);
             let host = addr.ip().to_string();
             let port = addr.port();
-            tunnel(tcp, host, port, ua(), None).await
+            tunnel(tcp, host, port, ua(), None, None).await
         };
 
         rt.block_on(f).unwrap_err();
@@ -1390,7 +2416,7 @@ mod tests {
             let tcp = TokioIo::new(TcpStream::connect(&addr).await?);
             let host = addr.ip().to_string();
             let port = addr.port();
-            tunnel(tcp, host, port, ua(), None).await
+            tunnel(tcp, host, port, ua(), None, None).await
         };
 
         let error = rt.block_on(f).unwrap_err();
@@ -1418,10 +2444,34 @@ mod tests {
                 port,
                 ua(),
                 Some(proxy::encode_basic_auth("Aladdin", "open sesame")),
+                None,
             )
             .await
         };
 
         rt.block_on(f).unwrap();
     }
+
+    #[test]
+    fn test_tunnel_custom_headers() {
+        let addr = mock_tunnel!(TUNNEL_OK, "x-proxy-token: letmein\r\n");
+
+        let rt = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("new rt");
+        let f = async move {
+            let tcp = TokioIo::new(TcpStream::connect(&addr).await?);
+            let host = addr.ip().to_string();
+            let port = addr.port();
+            let mut misc = http::HeaderMap::new();
+            misc.insert(
+                "x-proxy-token",
+                http::header::HeaderValue::from_static("letmein"),
+            );
+            tunnel(tcp, host, port, ua(), None, Some(misc)).await
+        };
+
+        rt.block_on(f).unwrap();
+    }
 }
@@ -9,10 +9,38 @@ use crate::header::{HeaderValue, SET_COOKIE};
 use bytes::Bytes;
 
 /// Actions for a persistent cookie store providing session support.
+///
+/// A `Client` uses a store set with `ClientBuilder::cookie_provider`, or the
+/// default `Jar` with `ClientBuilder::cookie_store(true)`. Implementing it
+/// allows keeping cookies somewhere else, such as a file or a database, so
+/// they survive a restart. Since it is shared through an `Arc`, one store can
+/// also serve several clients.
+///
+/// # Responsibilities
+///
+/// The `Client` only hands raw headers to the store, and asks it for the
+/// `Cookie` header of each request:
+///
+/// - `set_cookies` is called with every `Set-Cookie` header of a response,
+///   including those of redirects, and the URL of the request that got it.
+///   The headers are not parsed or filtered.
+/// - `cookies` is called with the URL of each request, and of each redirect
+///   followed, unless the request already has a `Cookie` header. Whatever it
+///   returns is sent as is.
+///
+/// Everything else is up to the store: parsing the cookies, matching their
+/// domain and path against the URL, honoring `Secure`, dropping them once
+/// they expire by `Max-Age` or `Expires`, and telling persistent cookies
+/// from session ones. `Jar` does all of this, with the rules of
+/// [RFC 6265](https://datatracker.ietf.org/doc/html/rfc6265), so a store
+/// that only needs to be persisted can wrap the same logic, from the
+/// `cookie_store` crate, and save it. See the `cookie_persist` example.
 pub trait CookieStore: Send + Sync {
     /// Store a set of Set-Cookie header values received from `url`
     fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &url::Url);
     /// Get any Cookie values in the store for `url`
+    ///
+    /// Returns `None` if no cookies should be sent.
     fn cookies(&self, url: &url::Url) -> Option<HeaderValue>;
 }
 
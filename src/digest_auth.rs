@@ -0,0 +1,404 @@
+//! Digest authentication (RFC 7616), see `RequestBuilder::digest_auth`.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Mutex;
+
+use http::header::{HeaderMap, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use http::Method;
+use url::{Origin, Url};
+
+use crate::hash::{md5, sha256};
+
+/// The username and password to answer digest challenges with.
+#[derive(Clone)]
+pub(crate) struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    pub(crate) fn new(username: String, password: String) -> Credentials {
+        Credentials { username, password }
+    }
+
+    /// The `Authorization` header answering `challenge` for a request.
+    fn answer(
+        &self,
+        challenge: &Challenge,
+        method: &Method,
+        url: &Url,
+        count: u32,
+    ) -> Option<HeaderValue> {
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_owned(),
+        };
+        let nc = format!("{count:08x}");
+        let cnonce = format!("{:016x}", crate::util::fast_random());
+        let response = self.response(challenge, method, &uri, &nc, &cnonce);
+
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+            quote(&self.username),
+            quote(&challenge.realm),
+            quote(&challenge.nonce),
+            quote(&uri),
+            challenge.algorithm.name(),
+            response,
+        );
+        if challenge.qop {
+            header.push_str(&format!(", qop=auth, nc={nc}, cnonce=\"{cnonce}\""));
+        }
+        if let Some(ref opaque) = challenge.opaque {
+            header.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+        }
+        let mut value = HeaderValue::try_from(header).ok()?;
+        value.set_sensitive(true);
+        Some(value)
+    }
+
+    fn response(
+        &self,
+        challenge: &Challenge,
+        method: &Method,
+        uri: &str,
+        nc: &str,
+        cnonce: &str,
+    ) -> String {
+        let algorithm = challenge.algorithm;
+        let nonce = &challenge.nonce;
+        let mut ha1 = algorithm.hash(&format!(
+            "{}:{}:{}",
+            self.username, challenge.realm, self.password
+        ));
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(&format!("{ha1}:{nonce}:{cnonce}"));
+        }
+        let ha2 = algorithm.hash(&format!("{method}:{uri}"));
+        if challenge.qop {
+            algorithm.hash(&format!("{ha1}:{nonce}:{nc}:{cnonce}:auth:{ha2}"))
+        } else {
+            algorithm.hash(&format!("{ha1}:{nonce}:{ha2}"))
+        }
+    }
+}
+
+/// Escapes a value for a quoted-string.
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Algorithm {
+    Md5,
+    Md5Session,
+    Sha256,
+    Sha256Session,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Algorithm> {
+        [
+            Algorithm::Md5,
+            Algorithm::Md5Session,
+            Algorithm::Sha256,
+            Algorithm::Sha256Session,
+        ]
+        .into_iter()
+        .find(|algorithm| algorithm.name().eq_ignore_ascii_case(name))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Md5Session => "MD5-sess",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha256Session => "SHA-256-sess",
+        }
+    }
+
+    fn is_session(self) -> bool {
+        matches!(self, Algorithm::Md5Session | Algorithm::Sha256Session)
+    }
+
+    fn is_md5(self) -> bool {
+        matches!(self, Algorithm::Md5 | Algorithm::Md5Session)
+    }
+
+    fn hash(self, data: &str) -> String {
+        let hash: &[u8] = if self.is_md5() {
+            &md5(data.as_bytes())
+        } else {
+            &sha256(data.as_bytes())
+        };
+        hash.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+/// A `WWW-Authenticate: Digest` challenge.
+#[derive(Clone, Debug)]
+struct Challenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    // `qop=auth` was offered; without a `qop`, the answer is as in RFC 2069
+    qop: bool,
+    // the nonce of the answer was only outdated, not the credentials wrong
+    stale: bool,
+}
+
+impl Challenge {
+    /// The digest challenge of a response, preferring SHA-256 if several
+    /// are offered.
+    fn from_headers(headers: &HeaderMap) -> Option<Challenge> {
+        headers
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(challenges)
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("digest"))
+            .filter_map(|(_, params)| Challenge::from_params(&params))
+            .min_by_key(|challenge| challenge.algorithm.is_md5())
+    }
+
+    fn from_params(params: &[(String, String)]) -> Option<Challenge> {
+        let get = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let algorithm = match get("algorithm") {
+            Some(name) => Algorithm::parse(name)?,
+            None => Algorithm::Md5,
+        };
+        // only `auth` is supported, not `auth-int`
+        let qop = match get("qop") {
+            Some(qop) if qop.split(',').any(|q| q.trim().eq_ignore_ascii_case("auth")) => true,
+            Some(_) => return None,
+            None => false,
+        };
+        Some(Challenge {
+            realm: get("realm")?.to_owned(),
+            nonce: get("nonce")?.to_owned(),
+            opaque: get("opaque").map(str::to_owned),
+            algorithm,
+            qop,
+            stale: get("stale").map_or(false, |stale| stale.eq_ignore_ascii_case("true")),
+        })
+    }
+}
+
+/// Splits a `WWW-Authenticate` value into its challenges, each a scheme and
+/// its parameters.
+fn challenges(value: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut challenges: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+        if rest.is_empty() {
+            return challenges;
+        }
+        let end = rest
+            .find(|c: char| c == ',' || c == '=' || c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let token = &rest[..end];
+        rest = rest[end..].trim_start();
+        match rest.strip_prefix('=') {
+            Some(value) => {
+                let (value, after) = param_value(value.trim_start());
+                if let Some((_, params)) = challenges.last_mut() {
+                    params.push((token.to_owned(), value));
+                }
+                rest = after;
+            }
+            None => challenges.push((token.to_owned(), Vec::new())),
+        }
+    }
+}
+
+/// Reads a token or quoted-string, returning it and what follows it.
+fn param_value(input: &str) -> (String, &str) {
+    let quoted = match input.strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => {
+            let end = input
+                .find(|c: char| c == ',' || c.is_ascii_whitespace())
+                .unwrap_or(input.len());
+            return (input[..end].to_owned(), &input[end..]);
+        }
+    };
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next().map(|(_, c)| c)),
+            '"' => return (value, &quoted[i + 1..]),
+            c => value.push(c),
+        }
+    }
+    (value, "")
+}
+
+/// The last challenge of each origin, shared by the requests of a `Client`,
+/// so later requests answer it without getting a `401` first.
+#[derive(Default)]
+pub(crate) struct Nonces(Mutex<HashMap<String, Session>>);
+
+struct Session {
+    challenge: Challenge,
+    // the requests that answered the nonce so far
+    count: u32,
+}
+
+impl Nonces {
+    fn challenged(&self, url: &Url, challenge: Challenge) {
+        let session = Session {
+            challenge,
+            count: 0,
+        };
+        self.0
+            .lock()
+            .unwrap()
+            .insert(url.origin().ascii_serialization(), session);
+    }
+
+    fn authorization(
+        &self,
+        url: &Url,
+        method: &Method,
+        credentials: &Credentials,
+    ) -> Option<HeaderValue> {
+        let mut sessions = self.0.lock().unwrap();
+        let session = sessions.get_mut(&url.origin().ascii_serialization())?;
+        session.count += 1;
+        credentials.answer(&session.challenge, method, url, session.count)
+    }
+}
+
+/// The digest authentication of a request and its retries.
+pub(crate) struct Auth {
+    credentials: Credentials,
+    // credentials are only sent to the origin of the request
+    origin: Origin,
+    // an answer was sent with the current attempt
+    sent: bool,
+    retries: usize,
+}
+
+impl Auth {
+    pub(crate) fn new(credentials: Credentials, url: &Url) -> Auth {
+        Auth {
+            credentials,
+            origin: url.origin(),
+            sent: false,
+            retries: 0,
+        }
+    }
+
+    /// Sets the `Authorization` header of an attempt to `url`, if its
+    /// origin sent a challenge before.
+    pub(crate) fn authorize(
+        &mut self,
+        nonces: &Nonces,
+        method: &Method,
+        url: &Url,
+        headers: &mut HeaderMap,
+    ) {
+        // an earlier answer is for another nonce count or uri
+        if self.sent {
+            headers.remove(AUTHORIZATION);
+            self.sent = false;
+        }
+        if url.origin() != self.origin {
+            return;
+        }
+        if let Some(value) = nonces.authorization(url, method, &self.credentials) {
+            headers.insert(AUTHORIZATION, value);
+            self.sent = true;
+        }
+    }
+
+    /// Keeps the challenge of a `401` response from `url`, returning
+    /// whether the request should be sent again to answer it.
+    pub(crate) fn challenged(&mut self, nonces: &Nonces, url: &Url, headers: &HeaderMap) -> bool {
+        if url.origin() != self.origin || self.retries >= 2 {
+            return false;
+        }
+        let challenge = match Challenge::from_headers(headers) {
+            Some(challenge) => challenge,
+            None => return false,
+        };
+        // a fresh nonce rejecting an answer means wrong credentials
+        if self.sent && !challenge.stale {
+            return false;
+        }
+        self.retries += 1;
+        nonces.challenged(url, challenge);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(value: &'static str) -> Option<Challenge> {
+        let mut headers = HeaderMap::new();
+        headers.append(WWW_AUTHENTICATE, HeaderValue::from_static(value));
+        Challenge::from_headers(&headers)
+    }
+
+    #[test]
+    fn rfc_7616_example() {
+        let credentials = Credentials::new("Mufasa".into(), "Circle of Life".into());
+        let header = "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", \
+                      algorithm=MD5, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+                      opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"";
+        let md5 = challenge(header).unwrap();
+        assert_eq!(md5.algorithm, Algorithm::Md5);
+        assert!(md5.qop && !md5.stale);
+
+        let response = |challenge: &Challenge| {
+            credentials.response(
+                challenge,
+                &Method::GET,
+                "/dir/index.html",
+                "00000001",
+                "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+            )
+        };
+        assert_eq!(response(&md5), "8ca523f5e9506fed4657c9700eebdbec");
+
+        let sha256 = Challenge {
+            algorithm: Algorithm::Sha256,
+            ..md5
+        };
+        assert_eq!(
+            response(&sha256),
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+        );
+    }
+
+    #[test]
+    fn parse_challenges() {
+        let both = challenge(
+            "Basic realm=\"x\", Digest realm=\"a \\\"b\\\"\", nonce=n1, algorithm=MD5, \
+             Digest realm=r, nonce=\"n2\", algorithm=SHA-256, stale=TRUE",
+        )
+        .unwrap();
+        assert_eq!(both.algorithm, Algorithm::Sha256);
+        assert_eq!(both.nonce, "n2");
+        assert!(both.stale && !both.qop);
+
+        let quoted = challenge("Digest realm=\"a \\\"b\\\", c\", nonce=n1").unwrap();
+        assert_eq!(quoted.realm, "a \"b\", c");
+
+        assert!(challenge("Digest realm=r, nonce=n, qop=auth-int").is_none());
+        assert!(challenge("Digest realm=r, nonce=n, algorithm=SHA-512-256").is_none());
+        assert!(challenge("Digest realm=r").is_none());
+        assert!(challenge("Basic realm=r").is_none());
+    }
+}
@@ -1,7 +1,9 @@
 //! DNS resolution
 
 pub use resolve::{Addrs, Name, Resolve, Resolving};
-pub(crate) use resolve::{DnsResolverWithOverrides, DynResolver};
+pub(crate) use resolve::{
+    local_address_mismatch, DnsResolverWithLocalAddress, DnsResolverWithOverrides, DynResolver,
+};
 
 pub(crate) mod gai;
 #[cfg(feature = "hickory-dns")]
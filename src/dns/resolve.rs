@@ -3,13 +3,15 @@ use tower_service::Service;
 
 use std::collections::HashMap;
 use std::future::Future;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
 use crate::error::BoxError;
+use crate::timings::ConnectTimer;
 
 /// Alias for an `Iterator` trait object over `SocketAddr`.
 pub type Addrs = Box<dyn Iterator<Item = SocketAddr> + Send>;
@@ -72,7 +74,20 @@ impl Service<HyperName> for DynResolver {
     }
 
     fn call(&mut self, name: HyperName) -> Self::Future {
-        self.resolver.resolve(Name(name))
+        let timer = ConnectTimer::current();
+        if let Some(ref timer) = timer {
+            timer.dns_started();
+        }
+        let start = Instant::now();
+        let resolving = self.resolver.resolve(Name(name));
+        match timer {
+            Some(timer) => Box::pin(async move {
+                let addrs = resolving.await;
+                timer.dns_done(start.elapsed());
+                addrs
+            }),
+            None => resolving,
+        }
     }
 }
 
@@ -105,6 +120,45 @@ impl Resolve for DnsResolverWithOverrides {
     }
 }
 
+/// Only keeps the resolved addresses that a socket bound to `local_address`
+/// can connect to, failing if there are none.
+pub(crate) struct DnsResolverWithLocalAddress {
+    dns_resolver: Arc<dyn Resolve>,
+    local_address: IpAddr,
+}
+
+impl DnsResolverWithLocalAddress {
+    pub(crate) fn new(dns_resolver: Arc<dyn Resolve>, local_address: IpAddr) -> Self {
+        DnsResolverWithLocalAddress {
+            dns_resolver,
+            local_address,
+        }
+    }
+}
+
+impl Resolve for DnsResolverWithLocalAddress {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_owned();
+        let local = self.local_address;
+        let resolving = self.dns_resolver.resolve(name);
+        Box::pin(async move {
+            let addrs = resolving
+                .await?
+                .filter(|addr| addr.is_ipv4() == local.is_ipv4())
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                return Err(local_address_mismatch(local, &host));
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+pub(crate) fn local_address_mismatch(local: IpAddr, host: &str) -> BoxError {
+    let family = if local.is_ipv4() { "IPv4" } else { "IPv6" };
+    format!("{host} has no {family} address to connect to from local address {local}").into()
+}
+
 mod sealed {
     use std::fmt;
 
@@ -116,13 +116,122 @@ impl Error {
         false
     }
 
-    /// Returns true if the error is related to the request
+    /// Returns true if the error is from a response body larger than
+    /// allowed by `Response::bytes_limited` or
+    /// `ClientBuilder::max_response_size`.
+    pub fn is_body_too_large(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<BodyTooLarge>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns true if the error is from a request aborted with
+    /// `CancelHandle::abort`.
+    pub fn is_aborted(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<Aborted>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns true if the error came from using the blocking client on the
+    /// thread of a `current_thread` Tokio runtime.
+    ///
+    /// Blocking that thread would stall every task on the runtime, so the
+    /// blocking client refuses to, instead of deadlocking.
+    pub fn is_blocking_on_current_thread(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<BlockingOnCurrentThread>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns true if the error came from a request, or a redirect, to a
+    /// URL that isn't `https` while `ClientBuilder::https_only` is enabled.
+    ///
+    /// Nothing has been sent to that URL when this happens.
+    pub fn is_not_https(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<NotHttps>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Returns true if the error came from a resolver set with
+    /// `Proxy::custom_async`, which failed or returned an invalid proxy URL.
+    ///
+    /// No connection was attempted. These errors are `is_connect` too.
+    pub fn is_proxy_resolve(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<ProxyResolve>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns true if the error happened while sending the request, before
+    /// its response arrived.
+    ///
+    /// This includes:
+    ///
+    /// - failing to connect, see `is_connect`,
+    /// - the connection closing, or the server breaking the protocol,
+    ///   before the response head arrived,
+    /// - the request body failing while it was sent,
+    /// - the total timeout passing before the response arrived, which is
+    ///   also `is_timeout`.
+    ///
+    /// A request that couldn't be built, such as one with an invalid header
+    /// or a body that couldn't be serialized, is an `is_builder` error
+    /// instead, and was never sent.
     pub fn is_request(&self) -> bool {
         matches!(self.inner.kind, Kind::Request)
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    /// Returns true if the error is related to connect
+    /// Returns true if no connection could be made for the request.
+    ///
+    /// This includes failing to:
+    ///
+    /// - resolve the host with DNS,
+    /// - open the TCP or Unix socket connection, including when the connect
+    ///   timeout passes, which is also `is_timeout`,
+    /// - set up the tunnel through an HTTP or SOCKS proxy,
+    /// - complete the TLS handshake.
+    ///
+    /// Nothing reached the server, so such requests are safe to retry.
+    /// These errors are `is_request` too.
     pub fn is_connect(&self) -> bool {
         let mut source = self.source();
 
@@ -139,7 +248,40 @@ impl Error {
         false
     }
 
-    /// Returns true if the error is related to the request or response body
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Returns true if the error is from an HTTP/2 connection that was closed
+    /// because the server didn't answer a keep-alive ping in time.
+    ///
+    /// See `ClientBuilder::http2_keep_alive_timeout`. A connection closed by
+    /// the server, such as with a `GOAWAY`, doesn't count.
+    pub fn is_connection_closed_by_keepalive(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if let Some(hyper_err) = err.downcast_ref::<hyper::Error>() {
+                // the keep-alive ping is the only timeout of hyper's client
+                if hyper_err.is_timeout() {
+                    return true;
+                }
+            }
+
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns true if the error happened while streaming a body.
+    ///
+    /// This includes:
+    ///
+    /// - the connection closing or failing while the response body is read,
+    /// - the total timeout passing while the response body is read, which is
+    ///   also `is_timeout`,
+    /// - a blocking request body failing to be read.
+    ///
+    /// A response body that can't be decompressed, or parsed as JSON or
+    /// text, is an `is_decode` error instead.
     pub fn is_body(&self) -> bool {
         matches!(self.inner.kind, Kind::Body)
     }
@@ -149,6 +291,11 @@ impl Error {
         matches!(self.inner.kind, Kind::Decode)
     }
 
+    /// Returns true if the error is from upgrading the connection
+    pub fn is_upgrade(&self) -> bool {
+        matches!(self.inner.kind, Kind::Upgrade)
+    }
+
     /// Returns the status code, if the error was generated from a response.
     pub fn status(&self) -> Option<StatusCode> {
         match self.inner.kind {
@@ -157,6 +304,19 @@ impl Error {
         }
     }
 
+    /// Returns the start of the response body, if the error came from
+    /// `RequestBuilder::send_success`.
+    ///
+    /// At most `ClientBuilder::error_body_limit` bytes are kept, after
+    /// decompression.
+    pub fn response_body_snippet(&self) -> Option<&[u8]> {
+        self.inner
+            .source
+            .as_ref()?
+            .downcast_ref::<BodySnippet>()
+            .map(|snippet| &snippet.0[..])
+    }
+
     // private
 
     #[allow(unused)]
@@ -267,10 +427,18 @@ pub(crate) fn status_code(url: Url, status: StatusCode) -> Error {
     Error::new(Kind::Status(status), None::<Error>).with_url(url)
 }
 
+pub(crate) fn status_code_with_body(url: Url, status: StatusCode, body: bytes::Bytes) -> Error {
+    Error::new(Kind::Status(status), Some(BodySnippet(body))).with_url(url)
+}
+
 pub(crate) fn url_bad_scheme(url: Url) -> Error {
     Error::new(Kind::Builder, Some(BadScheme)).with_url(url)
 }
 
+pub(crate) fn url_not_https(url: Url) -> Error {
+    Error::new(Kind::Builder, Some(NotHttps)).with_url(url)
+}
+
 pub(crate) fn url_invalid_uri(url: Url) -> Error {
     Error::new(Kind::Builder, Some("Parsed Url is not a valid Uri")).with_url(url)
 }
@@ -291,6 +459,14 @@ pub(crate) fn into_io(e: BoxError) -> io::Error {
     io::Error::new(io::ErrorKind::Other, e)
 }
 
+/// Wraps an error from reading a body, unless it already is an `Error`.
+pub(crate) fn body_read(e: BoxError) -> Error {
+    match e.downcast::<Error>() {
+        Ok(err) => *err,
+        Err(e) => body(e),
+    }
+}
+
 #[allow(unused)]
 pub(crate) fn decode_io(e: io::Error) -> Error {
     if e.get_ref().map(|r| r.is::<Error>()).unwrap_or(false) {
@@ -316,6 +492,41 @@ impl fmt::Display for TimedOut {
 
 impl StdError for TimedOut {}
 
+#[derive(Debug)]
+pub(crate) struct Aborted;
+
+impl fmt::Display for Aborted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("request aborted")
+    }
+}
+
+impl StdError for Aborted {}
+
+#[derive(Debug)]
+pub(crate) struct BodyTooLarge {
+    pub(crate) limit: u64,
+}
+
+impl fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "response body is larger than {} bytes", self.limit)
+    }
+}
+
+impl StdError for BodyTooLarge {}
+
+#[derive(Debug)]
+pub(crate) struct BlockingOnCurrentThread;
+
+impl fmt::Display for BlockingOnCurrentThread {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("blocking client used inside a current_thread runtime")
+    }
+}
+
+impl StdError for BlockingOnCurrentThread {}
+
 #[derive(Debug)]
 pub(crate) struct BadScheme;
 
@@ -327,6 +538,43 @@ impl fmt::Display for BadScheme {
 
 impl StdError for BadScheme {}
 
+#[derive(Debug)]
+pub(crate) struct NotHttps;
+
+impl fmt::Display for NotHttps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("URL scheme is not https, and the client is https only")
+    }
+}
+
+impl StdError for NotHttps {}
+
+#[derive(Debug)]
+pub(crate) struct ProxyResolve(pub(crate) BoxError);
+
+impl fmt::Display for ProxyResolve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("failed to resolve a proxy")
+    }
+}
+
+impl StdError for ProxyResolve {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct BodySnippet(bytes::Bytes);
+
+impl fmt::Display for BodySnippet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "response body: {}", String::from_utf8_lossy(&self.0))
+    }
+}
+
+impl StdError for BodySnippet {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -384,4 +632,43 @@ mod tests {
         let nested = super::request(io);
         assert!(nested.is_timeout());
     }
+
+    #[test]
+    fn is_not_https() {
+        let url = Url::parse("http://example.com").unwrap();
+        let err = super::url_not_https(url.clone());
+        assert!(err.is_not_https());
+        assert!(err.is_builder());
+
+        let redirect = super::redirect(err, url.clone());
+        assert!(redirect.is_not_https());
+        assert!(redirect.is_redirect());
+
+        assert!(!super::url_bad_scheme(url).is_not_https());
+    }
+
+    #[test]
+    fn response_body_snippet() {
+        let url = Url::parse("http://example.com").unwrap();
+        let status = StatusCode::BAD_REQUEST;
+        let err = super::status_code_with_body(url.clone(), status, "oops".into());
+        assert_eq!(err.status(), Some(status));
+        assert_eq!(err.response_body_snippet(), Some(&b"oops"[..]));
+        assert_eq!(err.source().unwrap().to_string(), "response body: oops");
+
+        assert_eq!(
+            super::status_code(url, status).response_body_snippet(),
+            None
+        );
+    }
+
+    #[test]
+    fn is_blocking_on_current_thread() {
+        let err = super::builder(super::BlockingOnCurrentThread);
+        assert!(err.is_blocking_on_current_thread());
+        assert!(!err.is_timeout());
+
+        let io = super::decode(super::BlockingOnCurrentThread).into_io();
+        assert!(super::decode_io(io).is_blocking_on_current_thread());
+    }
 }
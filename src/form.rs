@@ -0,0 +1,564 @@
+//! Form encoding
+//!
+//! `RequestBuilder::form` encodes with `serde_urlencoded`, which only
+//! accepts flat values. `RequestBuilder::form_with` also encodes sequences
+//! and nested structs or maps, the way the server expects them according
+//! to `FormOptions`:
+//!
+//! ```rust
+//! use reqwest::form::{ArrayFormat, FormOptions};
+//!
+//! #[derive(serde::Serialize)]
+//! struct Search {
+//!     tags: Vec<&'static str>,
+//! }
+//!
+//! # async fn run() -> Result<(), reqwest::Error> {
+//! let search = Search { tags: vec!["rust", "http"] };
+//!
+//! // sends `tags[]=rust&tags[]=http`
+//! let res = reqwest::Client::new()
+//!     .post("http://httpbin.org/post")
+//!     .form_with(&search, FormOptions::new().arrays(ArrayFormat::Brackets))
+//!     .send()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Nested structs and maps are always encoded with brackets, so
+//! `{"user": {"name": "a"}}` becomes `user[name]=a`.
+
+use std::fmt;
+
+use serde::ser::{self, Serialize};
+
+/// How sequences are encoded by `RequestBuilder::form_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArrayFormat {
+    /// The key is repeated for each value: `k=1&k=2`.
+    #[default]
+    Repeat,
+    /// Empty brackets follow the key, as PHP and Rails expect:
+    /// `k[]=1&k[]=2`.
+    Brackets,
+    /// The index of each value is put in brackets: `k[0]=1&k[1]=2`.
+    Indices,
+}
+
+/// Options for `RequestBuilder::form_with`.
+#[derive(Clone, Debug, Default)]
+pub struct FormOptions {
+    arrays: ArrayFormat,
+}
+
+impl FormOptions {
+    /// Creates options encoding sequences with `ArrayFormat::Repeat`.
+    pub fn new() -> FormOptions {
+        FormOptions::default()
+    }
+
+    /// Sets how sequences are encoded.
+    pub fn arrays(mut self, format: ArrayFormat) -> FormOptions {
+        self.arrays = format;
+        self
+    }
+}
+
+/// Encodes `value` as `application/x-www-form-urlencoded`.
+pub(crate) fn to_string<T: Serialize + ?Sized>(
+    value: &T,
+    options: &FormOptions,
+) -> Result<String, Error> {
+    let mut pairs = Vec::new();
+    match value.serialize(NodeSerializer)? {
+        Node::Map(entries) => {
+            for (key, node) in entries {
+                flatten(key, node, options, &mut pairs);
+            }
+        }
+        // a sequence of `(key, value)` pairs, as `form` accepts
+        Node::Seq(items) => {
+            for item in items {
+                match item {
+                    Node::Seq(pair) if pair.len() == 2 => {
+                        let mut pair = pair.into_iter();
+                        let key = match pair.next() {
+                            Some(Node::Scalar(key)) => key,
+                            _ => return Err(Error::new("form keys must be strings or numbers")),
+                        };
+                        flatten(key, pair.next().unwrap(), options, &mut pairs);
+                    }
+                    _ => return Err(Error::new("form sequences must contain pairs")),
+                }
+            }
+        }
+        Node::Null => {}
+        Node::Scalar(_) => return Err(Error::new("top-level form value must be a map")),
+    }
+
+    let mut encoder = url::form_urlencoded::Serializer::new(String::new());
+    encoder.extend_pairs(pairs);
+    Ok(encoder.finish())
+}
+
+fn flatten(key: String, node: Node, options: &FormOptions, pairs: &mut Vec<(String, String)>) {
+    match node {
+        Node::Null => {}
+        Node::Scalar(value) => pairs.push((key, value)),
+        Node::Seq(items) => {
+            for (i, item) in items.into_iter().enumerate() {
+                let key = match options.arrays {
+                    ArrayFormat::Repeat => key.clone(),
+                    ArrayFormat::Brackets => format!("{key}[]"),
+                    ArrayFormat::Indices => format!("{key}[{i}]"),
+                };
+                flatten(key, item, options, pairs);
+            }
+        }
+        Node::Map(entries) => {
+            for (name, item) in entries {
+                flatten(format!("{key}[{name}]"), item, options, pairs);
+            }
+        }
+    }
+}
+
+/// An error encoding a form.
+#[derive(Debug)]
+pub(crate) struct Error(String);
+
+impl Error {
+    fn new(msg: &str) -> Error {
+        Error(msg.to_owned())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(msg.to_string())
+    }
+}
+
+/// A serialized value, before it is flattened into pairs.
+enum Node {
+    Null,
+    Scalar(String),
+    Seq(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+struct NodeSerializer;
+
+impl NodeSerializer {
+    fn key<T: Serialize + ?Sized>(key: &T) -> Result<String, Error> {
+        match key.serialize(NodeSerializer)? {
+            Node::Scalar(key) => Ok(key),
+            _ => Err(Error::new("form keys must be strings or numbers")),
+        }
+    }
+}
+
+fn scalar<T: ToString>(value: T) -> Result<Node, Error> {
+    Ok(Node::Scalar(value.to_string()))
+}
+
+fn variant(name: &str, node: Node) -> Node {
+    Node::Map(vec![(name.to_owned(), node)])
+}
+
+impl ser::Serializer for NodeSerializer {
+    type Ok = Node;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Node, Error> {
+        scalar(v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Node, Error> {
+        Err(Error::new("bytes can't be encoded in a form"))
+    }
+
+    fn serialize_none(self) -> Result<Node, Error> {
+        Ok(Node::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Node, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Node, Error> {
+        Ok(Node::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Node, Error> {
+        Ok(Node::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Node, Error> {
+        scalar(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Node, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Node, Error> {
+        Ok(variant(name, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len),
+            variant: Some(name),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer {
+            entries: Vec::with_capacity(len),
+            key: None,
+            variant: Some(name),
+        })
+    }
+}
+
+struct SeqSerializer {
+    items: Vec<Node>,
+    variant: Option<&'static str>,
+}
+
+impl SeqSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(value.serialize(NodeSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Node, Error> {
+        let node = Node::Seq(self.items);
+        Ok(match self.variant {
+            Some(name) => variant(name, node),
+            None => node,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        self.finish()
+    }
+}
+
+struct MapSerializer {
+    entries: Vec<(String, Node)>,
+    key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl MapSerializer {
+    fn finish(self) -> Result<Node, Error> {
+        let node = Node::Map(self.entries);
+        Ok(match self.variant {
+            Some(name) => variant(name, node),
+            None => node,
+        })
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(NodeSerializer::key(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::new("form map value without a key"))?;
+        self.entries.push((key, value.serialize(NodeSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entries
+            .push((key.to_owned(), value.serialize(NodeSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entries
+            .push((key.to_owned(), value.serialize(NodeSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(serde::Serialize)]
+    struct User {
+        name: &'static str,
+        tags: Vec<u32>,
+        address: Address,
+        nickname: Option<&'static str>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Address {
+        city: &'static str,
+    }
+
+    fn user() -> User {
+        User {
+            name: "a b",
+            tags: vec![1, 2],
+            address: Address { city: "Oslo" },
+            nickname: None,
+        }
+    }
+
+    fn encode<T: Serialize>(value: &T, arrays: ArrayFormat) -> String {
+        to_string(value, &FormOptions::new().arrays(arrays)).unwrap()
+    }
+
+    #[test]
+    fn array_formats() {
+        assert_eq!(
+            encode(&user(), ArrayFormat::Repeat),
+            "name=a+b&tags=1&tags=2&address%5Bcity%5D=Oslo"
+        );
+        assert_eq!(
+            encode(&user(), ArrayFormat::Brackets),
+            "name=a+b&tags%5B%5D=1&tags%5B%5D=2&address%5Bcity%5D=Oslo"
+        );
+        assert_eq!(
+            encode(&user(), ArrayFormat::Indices),
+            "name=a+b&tags%5B0%5D=1&tags%5B1%5D=2&address%5Bcity%5D=Oslo"
+        );
+    }
+
+    #[test]
+    fn pairs_and_maps() {
+        let pairs = [("k", vec!["x", "y"])];
+        assert_eq!(encode(&pairs, ArrayFormat::Brackets), "k%5B%5D=x&k%5B%5D=y");
+
+        let mut map = BTreeMap::new();
+        map.insert(1, "one");
+        assert_eq!(encode(&map, ArrayFormat::Repeat), "1=one");
+    }
+
+    #[test]
+    fn rejects_non_maps() {
+        let options = FormOptions::new();
+        assert!(to_string(&"scalar", &options).is_err());
+        assert!(to_string(&[1, 2], &options).is_err());
+
+        let mut map = BTreeMap::new();
+        map.insert(vec![1], "list key");
+        assert!(to_string(&map, &options).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+
+use http::header::HeaderValue;
+
+/// A language for `RequestBuilder::accept_language`, with an optional
+/// quality.
+///
+/// # Example
+///
+/// ```
+/// use reqwest::LanguageTag;
+///
+/// # async fn run() -> Result<(), reqwest::Error> {
+/// // sends `Accept-Language: fr-CH,fr;q=0.9,en;q=0.5`
+/// let res = reqwest::Client::new()
+///     .get("https://hyper.rs")
+///     .accept_language(&[
+///         LanguageTag::new("fr-CH"),
+///         LanguageTag::new("fr"),
+///         LanguageTag::new("en").with_quality(0.5),
+///     ])
+///     .send()
+///     .await?;
+///
+/// println!("content in {:?}", res.content_language());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct LanguageTag {
+    tag: String,
+    quality: Option<f32>,
+}
+
+impl LanguageTag {
+    /// A language tag such as `en`, `en-US` or `*`, for any language.
+    ///
+    /// The tag is checked when the request is sent.
+    pub fn new(tag: impl Into<String>) -> LanguageTag {
+        LanguageTag {
+            tag: tag.into(),
+            quality: None,
+        }
+    }
+
+    /// Sets the quality, from `0.0` to `1.0`, instead of the one given by
+    /// its position in the list. A quality of `0.0` marks the language as
+    /// not acceptable.
+    pub fn with_quality(mut self, quality: f32) -> LanguageTag {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// The tag, as given to `new`.
+    pub fn as_str(&self) -> &str {
+        &self.tag
+    }
+
+    /// The quality set with `with_quality`, if any.
+    pub fn quality(&self) -> Option<f32> {
+        self.quality
+    }
+}
+
+impl From<&str> for LanguageTag {
+    fn from(tag: &str) -> LanguageTag {
+        LanguageTag::new(tag)
+    }
+}
+
+impl From<String> for LanguageTag {
+    fn from(tag: String) -> LanguageTag {
+        LanguageTag::new(tag)
+    }
+}
+
+impl fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.tag)
+    }
+}
+
+/// Builds an `Accept-Language` of `languages`, most preferred first.
+///
+/// Languages without a quality get one lower than the one before by
+/// position: `1`, then from `0.9` down by `0.1`, down by `0.01` from `0.1`,
+/// and by `0.001` from `0.01`, ending at `0.001`.
+pub(crate) fn accept_language(languages: &[LanguageTag]) -> crate::Result<HeaderValue> {
+    if languages.is_empty() {
+        return Err(crate::error::builder("no language to accept"));
+    }
+    let mut value = String::new();
+    // in thousandths, as sent
+    let mut position = 1000;
+    for language in languages {
+        if !is_valid_tag(&language.tag) {
+            return Err(crate::error::builder(format!(
+                "invalid language tag: {:?}",
+                language.tag
+            )));
+        }
+        let quality = match language.quality {
+            Some(quality) if (0.0..=1.0).contains(&quality) => (quality * 1000.0).round() as u16,
+            Some(quality) => {
+                return Err(crate::error::builder(format!(
+                    "invalid quality {quality} of language {}",
+                    language.tag
+                )));
+            }
+            None => position,
+        };
+        position = lower(position);
+
+        if !value.is_empty() {
+            value.push(',');
+        }
+        value.push_str(&language.tag);
+        if quality == 0 {
+            value.push_str(";q=0");
+        } else if quality < 1000 {
+            let digits = format!("{quality:03}");
+            value.push_str(";q=0.");
+            value.push_str(digits.trim_end_matches('0'));
+        }
+    }
+    Ok(HeaderValue::from_str(&value).expect("language tags are valid header values"))
+}
+
+fn lower(quality: u16) -> u16 {
+    let step = match quality {
+        101.. => 100,
+        11..=100 => 10,
+        _ => 1,
+    };
+    quality.saturating_sub(step).max(1)
+}
+
+/// Checks the form of a tag, as in RFC 4647: `*`, or a primary tag of one to
+/// eight letters, with subtags of one to eight letters or digits.
+fn is_valid_tag(tag: &str) -> bool {
+    if tag == "*" {
+        return true;
+    }
+    let mut subtags = tag.split('-');
+    let primary = subtags.next().unwrap_or("");
+    let valid = |subtag: &str, digits: bool| {
+        (1..=8).contains(&subtag.len())
+            && subtag
+                .bytes()
+                .all(|b| b.is_ascii_alphabetic() || digits && b.is_ascii_digit())
+    };
+    valid(primary, false) && subtags.all(|subtag| valid(subtag, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(languages: &[LanguageTag]) -> String {
+        accept_language(languages)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[test]
+    fn qualities_by_position() {
+        let languages = ["en-US", "en", "fr"].map(LanguageTag::new);
+        assert_eq!(header(&languages), "en-US,en;q=0.9,fr;q=0.8");
+
+        let many: Vec<LanguageTag> = (0..12).map(|_| LanguageTag::new("x")).collect();
+        let value = header(&many);
+        assert!(value.ends_with("x;q=0.2,x;q=0.1,x;q=0.09,x;q=0.08"), "{value}");
+
+        let mut qualities = 1000;
+        for _ in 0..30 {
+            qualities = lower(qualities);
+        }
+        assert_eq!(qualities, 1);
+    }
+
+    #[test]
+    fn explicit_qualities() {
+        let languages = [
+            LanguageTag::new("de").with_quality(0.75),
+            LanguageTag::new("en"),
+            LanguageTag::new("*").with_quality(0.0),
+        ];
+        assert_eq!(header(&languages), "de;q=0.75,en;q=0.9,*;q=0");
+        assert_eq!(header(&[LanguageTag::new("de").with_quality(1.0)]), "de");
+    }
+
+    #[test]
+    fn invalid_languages() {
+        for invalid in [
+            vec![],
+            vec![LanguageTag::new("")],
+            vec![LanguageTag::new("en_US")],
+            vec![LanguageTag::new("en-")],
+            vec![LanguageTag::new("1en")],
+            vec![LanguageTag::new("toolonglanguage")],
+            vec![LanguageTag::new("en").with_quality(1.5)],
+            vec![LanguageTag::new("en").with_quality(f32::NAN)],
+        ] {
+            assert!(accept_language(&invalid).unwrap_err().is_builder());
+        }
+        assert!(is_valid_tag("zh-Hant-TW"));
+        assert!(is_valid_tag("es-419"));
+    }
+}
@@ -195,13 +195,20 @@
 //! - **blocking**: Provides the [blocking][] client API.
 //! - **charset** *(enabled by default)*: Improved support for decoding text.
 //! - **cookies**: Provides cookie session support.
-//! - **gzip**: Provides response body gzip decompression.
-//! - **brotli**: Provides response body brotli decompression.
+//! - **gzip**: Provides response body gzip decompression, and request body
+//!   gzip compression.
+//! - **brotli**: Provides response body brotli decompression, and request body
+//!   brotli compression.
 //! - **deflate**: Provides response body deflate decompression.
+//! - **zstd**: Provides response body zstd decompression, and request body
+//!   zstd compression.
 //! - **json**: Provides serialization and deserialization for JSON bodies.
+//! - **cassette**: Allows recording HTTP interactions to a file, and replaying them.
 //! - **multipart**: Provides functionality for multipart forms.
 //! - **stream**: Adds support for `futures::Stream`.
+//! - **sse**: Provides parsing of server-sent event streams.
 //! - **socks**: Provides SOCKS5 proxy support.
+//! - **unix-socket**: Allows sending requests over a Unix domain socket, on Unix.
 //! - **hickory-dns**: Enables a hickory-dns async resolver instead of default
 //!   threadpool using `getaddrinfo`.
 //!
@@ -339,9 +346,18 @@ if_hyper! {
     doctest!("../README.md");
 
     pub use self::async_impl::{
-        Body, Client, ClientBuilder, Request, RequestBuilder, Response, Upgraded,
+        Body, CancelHandle, Client, ClientBuilder, Request, RequestBuilder, Response, Upgraded,
     };
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    pub use self::async_impl::ContentEncoding;
+    pub use self::language::LanguageTag;
     pub use self::proxy::{Proxy,NoProxy};
+    pub use self::range::ContentRange;
+    #[cfg(feature = "sse")]
+    pub use self::async_impl::SseEvent;
+    pub use self::connect::ConnectionInfo;
+    pub use self::pool::{HostPoolStats, PoolStats};
+    pub use self::timings::Timings;
     #[cfg(feature = "__tls")]
     // Re-exports, to be removed in a future release
     pub use tls::{Certificate, Identity};
@@ -352,15 +368,31 @@ if_hyper! {
     mod async_impl;
     #[cfg(feature = "blocking")]
     pub mod blocking;
+    #[cfg(feature = "cassette")]
+    pub mod cassette;
     mod connect;
     #[cfg(feature = "cookies")]
     pub mod cookie;
+    mod digest_auth;
     pub mod dns;
+    pub mod form;
+    mod hash;
+    mod language;
+    pub mod middleware;
+    pub mod sign;
     mod proxy;
+    mod range;
     pub mod redirect;
+    pub mod retry;
+    #[cfg(feature = "__tls")]
+    mod pin;
+    mod pool;
+    mod timings;
+    mod trace;
     #[cfg(feature = "__tls")]
     pub mod tls;
     mod util;
+    pub mod wire;
 }
 
 if_wasm! {
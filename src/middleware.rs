@@ -0,0 +1,192 @@
+//! Request middleware
+//!
+//! Middleware wrap every request a `Client` executes. Each one is handed the
+//! `Request` and a `Next` to pass it on with, so it can change the request,
+//! look at or replace the response, or answer without sending anything.
+//! They run in the order they were added, the first one being the outermost.
+//!
+//! There are two places to add them:
+//!
+//! - `ClientBuilder::with_middleware` runs them once per call, around the
+//!   whole exchange. They see the request as it was built, and the final
+//!   response, after redirects and retries.
+//! - `ClientBuilder::with_hop_middleware` runs them inside the redirect
+//!   loop, once for every request actually sent: the first one, each
+//!   redirect followed and each retry. They see the exact request sent,
+//!   with default headers, cookies and signature added, and the response
+//!   to it, before it is decompressed or a redirect in it is followed.
+//!
+//! A `Client` is also a `tower_service::Service<Request>`, so it can itself
+//! be wrapped by tower layers from the outside.
+//!
+//! # Extensions
+//!
+//! Values added with `RequestBuilder::extension` travel with the `Request`
+//! through the middleware added with `with_middleware`, but are never sent.
+//! reqwest doesn't copy them to the `Response` either: a middleware that
+//! wants them there inserts them itself.
+//!
+//! ```rust
+//! use reqwest::middleware::{Handling, Middleware, Next};
+//!
+//! #[derive(Clone)]
+//! struct RequestId(u64);
+//!
+//! struct PropagateId;
+//!
+//! impl Middleware for PropagateId {
+//!     fn handle<'a>(&'a self, req: reqwest::Request, next: Next<'a>) -> Handling<'a> {
+//!         let id = req.extensions().get::<RequestId>().cloned();
+//!         Box::pin(async move {
+//!             let mut res = next.run(req).await?;
+//!             if let Some(id) = id {
+//!                 res.extensions_mut().insert(id);
+//!             }
+//!             Ok(res)
+//!         })
+//!     }
+//! }
+//!
+//! # async fn run() -> Result<(), reqwest::Error> {
+//! let client = reqwest::Client::builder()
+//!     .with_middleware(PropagateId)
+//!     .build()?;
+//! let res = client
+//!     .get("http://httpbin.org/get")
+//!     .extension(RequestId(7))
+//!     .send()
+//!     .await?;
+//! assert_eq!(res.extension::<RequestId>().map(|id| id.0), Some(7));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Hop middleware are given a new `Request` for every hop, without them.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use crate::{Client, Request, Response};
+
+/// Alias for the `Future` returned by a `Middleware` and by `Next::run`.
+pub type Handling<'a> = Pin<Box<dyn Future<Output = crate::Result<Response>> + Send + 'a>>;
+
+/// Trait for code that runs around each request sent by a `Client`.
+///
+/// # Example
+///
+/// ```rust
+/// use reqwest::header::HeaderValue;
+/// use reqwest::middleware::{Handling, Middleware, Next};
+///
+/// struct Token(HeaderValue);
+///
+/// impl Middleware for Token {
+///     fn handle<'a>(&'a self, mut req: reqwest::Request, next: Next<'a>) -> Handling<'a> {
+///         req.headers_mut().insert("x-token", self.0.clone());
+///         next.run(req)
+///     }
+/// }
+///
+/// # fn run() -> Result<(), reqwest::Error> {
+/// let client = reqwest::Client::builder()
+///     .with_middleware(Token(HeaderValue::from_static("secret")))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Handles a `Request`, usually by passing it on with `next.run(req)`.
+    ///
+    /// Since trait objects cannot use `async fn`, the returned `Future` must
+    /// be boxed.
+    fn handle<'a>(&'a self, req: Request, next: Next<'a>) -> Handling<'a>;
+}
+
+/// The rest of the middleware chain, ending with the `Client` itself.
+///
+/// `Next` is `Copy`, so a middleware can run the rest of the chain more than
+/// once, for instance to retry a request.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    client: &'a Client,
+    middleware: &'a [Arc<dyn Middleware>],
+    hop: Option<&'a Hop>,
+}
+
+/// What a chain of hop middleware ends with.
+pub(crate) struct Hop {
+    // extensions of the request first given to the chain, like the hook
+    // waiting for `100 Continue`
+    extensions: Mutex<Option<http::Extensions>>,
+}
+
+impl Hop {
+    pub(crate) fn new(extensions: http::Extensions) -> Hop {
+        Hop {
+            extensions: Mutex::new(Some(extensions)),
+        }
+    }
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(client: &'a Client, middleware: &'a [Arc<dyn Middleware>]) -> Next<'a> {
+        Next {
+            client,
+            middleware,
+            hop: None,
+        }
+    }
+
+    /// The chain of hop middleware, ending with sending a single request.
+    pub(crate) fn hop(
+        client: &'a Client,
+        middleware: &'a [Arc<dyn Middleware>],
+        hop: &'a Hop,
+    ) -> Next<'a> {
+        Next {
+            client,
+            middleware,
+            hop: Some(hop),
+        }
+    }
+
+    /// Passes the request to the next middleware, or sends it if there are
+    /// none left.
+    ///
+    /// At the end of hop middleware, the request is sent as is, without
+    /// following redirects or retrying it.
+    pub fn run(self, req: Request) -> Handling<'a> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.handle(
+                req,
+                Next {
+                    middleware: rest,
+                    ..self
+                },
+            ),
+            None => match self.hop {
+                Some(hop) => {
+                    let extensions = hop
+                        .extensions
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .take();
+                    Box::pin(self.client.send_hop(req, extensions))
+                }
+                None => Box::pin(self.client.dispatch_request(req)),
+            },
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("remaining", &self.middleware.len())
+            .field("hop", &self.hop.is_some())
+            .finish()
+    }
+}
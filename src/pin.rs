@@ -0,0 +1,114 @@
+//! Public key pinning, see `ClientBuilder::add_pinned_spki_sha256`.
+
+use crate::error::BoxError;
+use crate::hash::sha256;
+
+/// The pins of a host, each the SHA-256 of a `SubjectPublicKeyInfo`.
+pub(crate) type Pins = Vec<[u8; 32]>;
+
+/// Checks that the DER encoded certificate `cert` of `host` has the public
+/// key of one of `pins`.
+///
+/// A certificate that is missing or can't be parsed doesn't match.
+pub(crate) fn verify(host: &str, cert: Option<&[u8]>, pins: &Pins) -> Result<(), BoxError> {
+    let matches = cert
+        .and_then(spki)
+        .map(|spki| pins.contains(&sha256(spki)))
+        .unwrap_or(false);
+    if matches {
+        Ok(())
+    } else {
+        Err(format!("certificate of {host} doesn't match any pinned public key").into())
+    }
+}
+
+/// A DER element, and what follows it.
+struct Element<'a> {
+    tag: u8,
+    whole: &'a [u8],
+    contents: &'a [u8],
+    rest: &'a [u8],
+}
+
+/// Splits the DER element at the start of `input`.
+fn element(input: &[u8]) -> Option<Element<'_>> {
+    let tag = *input.first()?;
+    let first = *input.get(1)?;
+    let (len, header) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 {
+            return None;
+        }
+        let bytes = input.get(2..2 + n)?;
+        let len = bytes.iter().fold(0usize, |len, &b| len << 8 | b as usize);
+        (len, 2 + n)
+    };
+    let end = header.checked_add(len)?;
+    let whole = input.get(..end)?;
+    Some(Element {
+        tag,
+        whole,
+        contents: &whole[header..],
+        rest: &input[end..],
+    })
+}
+
+/// Returns the whole `SubjectPublicKeyInfo` element of an X.509
+/// certificate.
+fn spki(cert: &[u8]) -> Option<&[u8]> {
+    const SEQUENCE: u8 = 0x30;
+    const VERSION: u8 = 0xa0;
+    const INTEGER: u8 = 0x02;
+
+    let expect = |input, tag| element(input).filter(|element| element.tag == tag);
+
+    let cert = expect(cert, SEQUENCE)?.contents;
+    let mut tbs = expect(cert, SEQUENCE)?.contents;
+    if let Some(version) = expect(tbs, VERSION) {
+        tbs = version.rest;
+    }
+    // serialNumber, signature, issuer, validity, subject
+    for tag in [INTEGER, SEQUENCE, SEQUENCE, SEQUENCE, SEQUENCE] {
+        tbs = expect(tbs, tag)?.rest;
+    }
+    Some(expect(tbs, SEQUENCE)?.whole)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn spki_of_certificate() {
+        use base64::Engine;
+
+        let pem = include_str!("../tests/support/client_identity.pem");
+        let base64: String = pem[..pem.find("-----END CERTIFICATE-----").unwrap()]
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(base64)
+            .unwrap();
+
+        // openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256
+        assert_eq!(
+            hex(&sha256(spki(&der).unwrap())),
+            "d98ba063f8349d568668dc23ecb93cf95ac29b822e3543db525348f73eb3376e"
+        );
+    }
+
+    #[test]
+    fn spki_of_garbage() {
+        assert_eq!(spki(b""), None);
+        assert_eq!(spki(&[0x30, 0x03, 0x02, 0x01, 0x00]), None);
+        assert_eq!(spki(&[0x30, 0x84, 0xff, 0xff, 0xff, 0xff]), None);
+        assert!(verify("example.com", None, &vec![[0; 32]]).is_err());
+    }
+}
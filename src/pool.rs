@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http::uri::{Authority, Uri};
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use http_body_util::BodyExt;
+
+use crate::async_impl::body::ResponseBody;
+use crate::error::BoxError;
+
+/// The connections of a `Client`, as returned by `Client::pool_stats`.
+///
+/// This is a snapshot taken without stopping the `Client`, so the counts
+/// of different hosts, or of the same host, may be from slightly different
+/// moments.
+#[derive(Clone, Debug, Default)]
+pub struct PoolStats {
+    hosts: Vec<HostPoolStats>,
+}
+
+/// The connections of a `Client` to one host.
+#[derive(Clone, Debug)]
+pub struct HostPoolStats {
+    host: String,
+    idle: usize,
+    in_use: usize,
+    pending: usize,
+}
+
+impl PoolStats {
+    /// The hosts with connections open or being opened, sorted by host.
+    pub fn hosts(&self) -> &[HostPoolStats] {
+        &self.hosts
+    }
+
+    /// The connections to `host`, as given by `HostPoolStats::host`.
+    pub fn host(&self, host: &str) -> Option<&HostPoolStats> {
+        self.hosts.iter().find(|stats| stats.host == host)
+    }
+
+    /// The idle connections to all hosts.
+    pub fn idle(&self) -> usize {
+        self.hosts.iter().map(|stats| stats.idle).sum()
+    }
+
+    /// The connections in use to all hosts.
+    pub fn in_use(&self) -> usize {
+        self.hosts.iter().map(|stats| stats.in_use).sum()
+    }
+
+    /// The connections being opened to all hosts.
+    pub fn pending(&self) -> usize {
+        self.hosts.iter().map(|stats| stats.pending).sum()
+    }
+}
+
+impl HostPoolStats {
+    /// The scheme and authority connections are opened to, such as
+    /// `https://example.com` or `http://127.0.0.1:8080`.
+    ///
+    /// The port is only there if the URLs of the requests had one other
+    /// than the default of their scheme.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Open connections waiting in the pool for a request.
+    pub fn idle(&self) -> usize {
+        self.idle
+    }
+
+    /// Open connections receiving a response.
+    ///
+    /// A connection is in use from the moment the headers of a response
+    /// arrive over it until its body was read or dropped, so this is
+    /// approximate: the pool can give an HTTP/1 connection to the next
+    /// request as soon as a short body was received. An HTTP/2 connection
+    /// counts once, however many streams it carries.
+    pub fn in_use(&self) -> usize {
+        self.in_use
+    }
+
+    /// Connections being opened, including the time spent waiting for the
+    /// limit of `ClientBuilder::pool_max_connections_per_host`.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+}
+
+/// Counts the connections of a `Client` and its clones, by host.
+#[derive(Clone, Default)]
+pub(crate) struct PoolCounters {
+    hosts: Arc<Mutex<HashMap<String, Arc<HostCounters>>>>,
+}
+
+#[derive(Default)]
+struct HostCounters {
+    open: AtomicUsize,
+    in_use: AtomicUsize,
+    pending: AtomicUsize,
+}
+
+impl PoolCounters {
+    /// Counts a connection being opened to `dst`, until the returned guard
+    /// is dropped or opened.
+    pub(crate) fn connecting(&self, dst: &Uri) -> Pending {
+        let key = format!(
+            "{}://{}",
+            dst.scheme_str().unwrap_or(""),
+            dst.authority().map_or("", Authority::as_str)
+        );
+        let host = {
+            let mut hosts = self.hosts.lock().unwrap();
+            // drop hosts that have no connections, or any being opened
+            hosts.retain(|_, host| Arc::strong_count(host) > 1);
+            hosts.entry(key).or_default().clone()
+        };
+        host.pending.fetch_add(1, Ordering::Relaxed);
+        Pending(Some(host))
+    }
+
+    pub(crate) fn snapshot(&self) -> PoolStats {
+        let hosts = self.hosts.lock().unwrap();
+        let mut hosts: Vec<HostPoolStats> = hosts
+            .iter()
+            .filter(|(_, host)| Arc::strong_count(host) > 1)
+            .map(|(key, host)| {
+                let open = host.open.load(Ordering::Relaxed);
+                let in_use = host.in_use.load(Ordering::Relaxed).min(open);
+                HostPoolStats {
+                    host: key.clone(),
+                    idle: open - in_use,
+                    in_use,
+                    pending: host.pending.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        hosts.sort_by(|a, b| a.host.cmp(&b.host));
+        PoolStats { hosts }
+    }
+}
+
+impl fmt::Debug for PoolCounters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.snapshot().fmt(f)
+    }
+}
+
+/// A connection being opened.
+pub(crate) struct Pending(Option<Arc<HostCounters>>);
+
+impl Pending {
+    /// Counts the connection as open, until the returned guard is dropped
+    /// with it.
+    pub(crate) fn opened(mut self) -> Open {
+        let host = self.0.take().expect("pending connection opened once");
+        host.pending.fetch_sub(1, Ordering::Relaxed);
+        host.open.fetch_add(1, Ordering::Relaxed);
+        Open(Arc::new(Activity {
+            host,
+            responses: AtomicUsize::new(0),
+        }))
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if let Some(ref host) = self.0 {
+            host.pending.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// An open connection, held by it.
+pub(crate) struct Open(Arc<Activity>);
+
+struct Activity {
+    host: Arc<HostCounters>,
+    responses: AtomicUsize,
+}
+
+impl Open {
+    /// Tracks the responses received over this connection.
+    pub(crate) fn activity(&self) -> ConnectionActivity {
+        ConnectionActivity(self.0.clone())
+    }
+}
+
+impl Drop for Open {
+    fn drop(&mut self) {
+        self.0.host.open.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Given by hyper to every response received over a connection.
+#[derive(Clone)]
+pub(crate) struct ConnectionActivity(Arc<Activity>);
+
+impl ConnectionActivity {
+    /// Counts the connection as in use until `body` was read or dropped.
+    pub(crate) fn track(self, body: ResponseBody) -> ResponseBody {
+        if body.is_end_stream() {
+            return body;
+        }
+        if self.0.responses.fetch_add(1, Ordering::Relaxed) == 0 {
+            self.0.host.in_use.fetch_add(1, Ordering::Relaxed);
+        }
+        InUseBody {
+            inner: body,
+            activity: Some(self),
+        }
+        .boxed()
+    }
+
+    fn done(&self) {
+        if self.0.responses.fetch_sub(1, Ordering::Relaxed) == 1 {
+            self.0.host.in_use.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A response body keeping its connection in use.
+struct InUseBody {
+    inner: ResponseBody,
+    activity: Option<ConnectionActivity>,
+}
+
+impl InUseBody {
+    fn done(&mut self) {
+        if let Some(activity) = self.activity.take() {
+            activity.done();
+        }
+    }
+}
+
+impl HttpBody for InUseBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = futures_core::ready!(Pin::new(&mut self.inner).poll_frame(cx));
+        if !matches!(frame, Some(Ok(_))) || self.inner.is_end_stream() {
+            self.done();
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for InUseBody {
+    fn drop(&mut self) {
+        self.done();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_connections_by_host() {
+        let pool = PoolCounters::default();
+        let a: Uri = "http://a.example/".parse().unwrap();
+        let b: Uri = "https://b.example:8443/".parse().unwrap();
+
+        let pending = pool.connecting(&a);
+        let failed = pool.connecting(&b);
+        let stats = pool.snapshot();
+        assert_eq!(stats.pending(), 2);
+        assert_eq!(stats.host("http://a.example").unwrap().pending(), 1);
+        assert_eq!(stats.host("https://b.example:8443").unwrap().pending(), 1);
+
+        drop(failed);
+        let open = pending.opened();
+        let stats = pool.snapshot();
+        assert_eq!(stats.hosts().len(), 1);
+        assert_eq!(stats.hosts()[0].host(), "http://a.example");
+        assert_eq!((stats.idle(), stats.in_use(), stats.pending()), (1, 0, 0));
+
+        let body = || {
+            http_body_util::StreamBody::new(futures_util::stream::pending::<
+                Result<Frame<Bytes>, BoxError>,
+            >())
+            .boxed()
+        };
+        let first = open.activity().track(body());
+        let second = open.activity().track(body());
+        assert_eq!((pool.snapshot().idle(), pool.snapshot().in_use()), (0, 1));
+        drop(first);
+        assert_eq!(pool.snapshot().in_use(), 1);
+        drop(second);
+        assert_eq!((pool.snapshot().idle(), pool.snapshot().in_use()), (1, 0));
+
+        drop(open);
+        assert!(pool.snapshot().hosts().is_empty());
+    }
+}
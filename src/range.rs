@@ -0,0 +1,139 @@
+use http::header::{HeaderMap, HeaderValue, CONTENT_RANGE};
+use http::StatusCode;
+
+/// The byte range a `206 Partial Content` response holds, from its
+/// `Content-Range` header.
+///
+/// # Example
+///
+/// ```
+/// # async fn run() -> Result<(), reqwest::Error> {
+/// let res = reqwest::Client::new()
+///     .get("https://hyper.rs")
+///     .range(500, None)
+///     .send()
+///     .await?;
+///
+/// match res.content_range() {
+///     Some(range) => println!("got bytes {}-{}", range.start(), range.end()),
+///     None => println!("server sent the whole body"),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContentRange {
+    start: u64,
+    end: u64,
+    total: Option<u64>,
+}
+
+impl ContentRange {
+    /// The position of the first byte in the range.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// The position of the last byte in the range, inclusive.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// The length of the complete resource, if the server knows it.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Reads the range of a response, if it is a partial one.
+    ///
+    /// A server that doesn't support ranges ignores them and answers
+    /// `200 OK` with the whole resource, so only `206 Partial Content`
+    /// responses have a range.
+    pub(crate) fn from_response(status: StatusCode, headers: &HeaderMap) -> Option<ContentRange> {
+        if status != StatusCode::PARTIAL_CONTENT {
+            return None;
+        }
+        parse(headers.get(CONTENT_RANGE)?.to_str().ok()?)
+    }
+}
+
+/// Parses a `Content-Range` of the form `bytes <start>-<end>/<total or *>`.
+fn parse(value: &str) -> Option<ContentRange> {
+    let (unit, rest) = value.trim().split_once(' ')?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+    let (range, total) = rest.trim_start().split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start = start.parse().ok()?;
+    let end = end.parse().ok()?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    if end < start || total.map_or(false, |total| end >= total) {
+        return None;
+    }
+    Some(ContentRange { start, end, total })
+}
+
+/// Builds a `Range` header asking for bytes `start` to `end`, inclusive, or
+/// to the end of the resource.
+pub(crate) fn range_header(start: u64, end: Option<u64>) -> crate::Result<HeaderValue> {
+    let value = match end {
+        Some(end) if end < start => {
+            return Err(crate::error::builder(format!(
+                "invalid range: end {end} is before start {start}"
+            )));
+        }
+        Some(end) => format!("bytes={start}-{end}"),
+        None => format!("bytes={start}-"),
+    };
+    Ok(HeaderValue::from_str(&value).expect("range is a valid header value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_header_values() {
+        assert_eq!(range_header(0, Some(499)).unwrap(), "bytes=0-499");
+        assert_eq!(range_header(500, None).unwrap(), "bytes=500-");
+        assert_eq!(range_header(7, Some(7)).unwrap(), "bytes=7-7");
+        assert!(range_header(10, Some(9)).unwrap_err().is_builder());
+    }
+
+    #[test]
+    fn parse_content_range() {
+        assert_eq!(
+            parse("bytes 0-499/1234"),
+            Some(ContentRange {
+                start: 0,
+                end: 499,
+                total: Some(1234),
+            })
+        );
+        assert_eq!(parse("bytes 500-999/*").unwrap().total(), None);
+        assert_eq!(parse("Bytes  5-5/6").unwrap().start(), 5);
+
+        for invalid in [
+            "bytes */1234",
+            "bytes 500-/1234",
+            "bytes 9-3/10",
+            "bytes 0-10/10",
+            "items 0-1/2",
+            "bytes 0-1",
+        ] {
+            assert_eq!(parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn only_partial_content_has_a_range() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_RANGE, HeaderValue::from_static("bytes 0-9/100"));
+        assert!(ContentRange::from_response(StatusCode::PARTIAL_CONTENT, &headers).is_some());
+        assert!(ContentRange::from_response(StatusCode::OK, &headers).is_none());
+    }
+}
@@ -7,10 +7,12 @@
 use std::error::Error as StdError;
 use std::fmt;
 
-use crate::header::{HeaderMap, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, WWW_AUTHENTICATE};
+use crate::header::{
+    HeaderMap, AUTHORIZATION, COOKIE, HOST, PROXY_AUTHORIZATION, WWW_AUTHENTICATE,
+};
 use hyper::StatusCode;
 
-use crate::Url;
+use crate::{Method, Url};
 
 /// A type that controls the policy on how to handle the following of redirects.
 ///
@@ -21,6 +23,7 @@ use crate::Url;
 ///   the allowed maximum redirect hops in a chain.
 /// - `none` can be used to disable all redirect behavior.
 /// - `custom` can be used to create a customized policy.
+/// - `for_method` can be used to give some methods a different policy.
 pub struct Policy {
     inner: PolicyKind,
 }
@@ -30,10 +33,18 @@ pub struct Policy {
 #[derive(Debug)]
 pub struct Attempt<'a> {
     status: StatusCode,
+    method: &'a Method,
     next: &'a Url,
     previous: &'a [Url],
 }
 
+/// A redirect that was followed, as listed by `Response::redirect_history`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hop {
+    url: Url,
+    status: StatusCode,
+}
+
 /// An action to perform when a redirect status code is found.
 #[derive(Debug)]
 pub struct Action {
@@ -104,6 +115,35 @@ impl Policy {
         }
     }
 
+    /// Use `policy` instead of this one for redirects of `method` requests.
+    ///
+    /// The method checked is the one the redirected request would be sent
+    /// with, see [`Attempt::method`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use reqwest::{Error, Method, redirect};
+    /// #
+    /// # fn run() -> Result<(), Error> {
+    /// // follow redirects, except for HEAD requests
+    /// let policy = redirect::Policy::default().for_method(Method::HEAD, redirect::Policy::none());
+    /// let client = reqwest::Client::builder()
+    ///     .redirect(policy)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_method(self, method: Method, policy: Policy) -> Self {
+        Policy::custom(move |attempt| {
+            if *attempt.method() == method {
+                policy.redirect(attempt)
+            } else {
+                self.redirect(attempt)
+            }
+        })
+    }
+
     /// Apply this policy to a given [`Attempt`] to produce a [`Action`].
     ///
     /// # Note
@@ -138,9 +178,16 @@ impl Policy {
         }
     }
 
-    pub(crate) fn check(&self, status: StatusCode, next: &Url, previous: &[Url]) -> ActionKind {
+    pub(crate) fn check(
+        &self,
+        status: StatusCode,
+        method: &Method,
+        next: &Url,
+        previous: &[Url],
+    ) -> ActionKind {
         self.redirect(Attempt {
             status,
+            method,
             next,
             previous,
         })
@@ -170,6 +217,15 @@ impl<'a> Attempt<'a> {
         self.next
     }
 
+    /// Get the method the next request would be sent with.
+    ///
+    /// This is the method of the redirected request, except after a `301`,
+    /// `302` or `303` to a request other than `GET` or `HEAD`, which is
+    /// followed with `GET`.
+    pub fn method(&self) -> &Method {
+        self.method
+    }
+
     /// Get the list of previous URLs that have already been requested in this chain.
     pub fn previous(&self) -> &[Url] {
         self.previous
@@ -181,6 +237,42 @@ impl<'a> Attempt<'a> {
         }
     }
 
+    /// Returns an action meaning reqwest should follow a different URL
+    /// instead of the next one.
+    ///
+    /// This can be used to rewrite redirects, for instance to upgrade them
+    /// to `https` or to strip query parameters. The new URL is handled like
+    /// the one it replaces: if it is on another host, sensitive headers such
+    /// as `Authorization` and `Cookie` are removed, and the `https_only` and
+    /// scheme checks apply to it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use reqwest::{Error, redirect};
+    /// #
+    /// # fn run() -> Result<(), Error> {
+    /// let upgrade = redirect::Policy::custom(|attempt| {
+    ///     if attempt.url().scheme() == "http" {
+    ///         let mut url = attempt.url().clone();
+    ///         url.set_scheme("https").expect("http can become https");
+    ///         attempt.follow_to(url)
+    ///     } else {
+    ///         redirect::Policy::default().redirect(attempt)
+    ///     }
+    /// });
+    /// let client = reqwest::Client::builder()
+    ///     .redirect(upgrade)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn follow_to(self, url: Url) -> Action {
+        Action {
+            inner: ActionKind::FollowTo(url),
+        }
+    }
+
     /// Returns an action meaning reqwest should not follow the next URL.
     ///
     /// The 30x response will be returned as the `Ok` result.
@@ -200,6 +292,18 @@ impl<'a> Attempt<'a> {
     }
 }
 
+impl Hop {
+    /// Get the URL that answered with a redirect.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Get the redirect status it answered with.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
 enum PolicyKind {
     Custom(Box<dyn Fn(Attempt) -> Action + Send + Sync + 'static>),
     Limit(usize),
@@ -227,10 +331,35 @@ impl fmt::Debug for PolicyKind {
 #[derive(Debug)]
 pub(crate) enum ActionKind {
     Follow,
+    FollowTo(Url),
     Stop,
     Error(Box<dyn StdError + Send + Sync>),
 }
 
+/// The redirects followed to get a response, kept in its extensions.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct History(pub(crate) Vec<Hop>);
+
+impl History {
+    pub(crate) fn push(&mut self, url: Url, status: StatusCode) {
+        self.0.push(Hop { url, status });
+    }
+}
+
+/// The URLs redirected to, in order, given the `history` of a response
+/// that ended up at `last`.
+pub(crate) fn redirect_targets(history: &[Hop], last: &Url) -> Vec<Url> {
+    if history.is_empty() {
+        return Vec::new();
+    }
+    history
+        .iter()
+        .skip(1)
+        .map(|hop| hop.url.clone())
+        .chain(std::iter::once(last.clone()))
+        .collect()
+}
+
 pub(crate) fn remove_sensitive_headers(headers: &mut HeaderMap, next: &Url, previous: &[Url]) {
     if let Some(previous) = previous.last() {
         let cross_host = next.host_str() != previous.host_str()
@@ -241,6 +370,8 @@ pub(crate) fn remove_sensitive_headers(headers: &mut HeaderMap, next: &Url, prev
             headers.remove("cookie2");
             headers.remove(PROXY_AUTHORIZATION);
             headers.remove(WWW_AUTHENTICATE);
+            // an overridden host is meant for the original server only
+            headers.remove(HOST);
         }
     }
 }
@@ -264,14 +395,14 @@ fn test_redirect_policy_limit() {
         .map(|i| Url::parse(&format!("http://a.b/c/{i}")).unwrap())
         .collect::<Vec<_>>();
 
-    match policy.check(StatusCode::FOUND, &next, &previous) {
+    match policy.check(StatusCode::FOUND, &Method::GET, &next, &previous) {
         ActionKind::Follow => (),
         other => panic!("unexpected {other:?}"),
     }
 
     previous.push(Url::parse("http://a.b.d/e/33").unwrap());
 
-    match policy.check(StatusCode::FOUND, &next, &previous) {
+    match policy.check(StatusCode::FOUND, &Method::GET, &next, &previous) {
         ActionKind::Error(err) if err.is::<TooManyRedirects>() => (),
         other => panic!("unexpected {other:?}"),
     }
@@ -283,7 +414,7 @@ fn test_redirect_policy_limit_to_0() {
     let next = Url::parse("http://x.y/z").unwrap();
     let previous = vec![Url::parse("http://a.b/c").unwrap()];
 
-    match policy.check(StatusCode::FOUND, &next, &previous) {
+    match policy.check(StatusCode::FOUND, &Method::GET, &next, &previous) {
         ActionKind::Error(err) if err.is::<TooManyRedirects>() => (),
         other => panic!("unexpected {other:?}"),
     }
@@ -300,13 +431,29 @@ fn test_redirect_policy_custom() {
     });
 
     let next = Url::parse("http://bar/baz").unwrap();
-    match policy.check(StatusCode::FOUND, &next, &[]) {
+    match policy.check(StatusCode::FOUND, &Method::GET, &next, &[]) {
         ActionKind::Follow => (),
         other => panic!("unexpected {other:?}"),
     }
 
     let next = Url::parse("http://foo/baz").unwrap();
-    match policy.check(StatusCode::FOUND, &next, &[]) {
+    match policy.check(StatusCode::FOUND, &Method::GET, &next, &[]) {
+        ActionKind::Stop => (),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn test_redirect_policy_for_method() {
+    let policy = Policy::default().for_method(Method::HEAD, Policy::none());
+    let next = Url::parse("http://x.y/z").unwrap();
+
+    match policy.check(StatusCode::FOUND, &Method::GET, &next, &[]) {
+        ActionKind::Follow => (),
+        other => panic!("unexpected {other:?}"),
+    }
+
+    match policy.check(StatusCode::FOUND, &Method::HEAD, &next, &[]) {
         ActionKind::Stop => (),
         other => panic!("unexpected {other:?}"),
     }
@@ -314,12 +461,13 @@ fn test_redirect_policy_custom() {
 
 #[test]
 fn test_remove_sensitive_headers() {
-    use hyper::header::{HeaderValue, ACCEPT, AUTHORIZATION, COOKIE};
+    use hyper::header::{HeaderValue, ACCEPT, AUTHORIZATION, COOKIE, HOST};
 
     let mut headers = HeaderMap::new();
     headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
     headers.insert(AUTHORIZATION, HeaderValue::from_static("let me in"));
     headers.insert(COOKIE, HeaderValue::from_static("foo=bar"));
+    headers.insert(HOST, HeaderValue::from_static("internal.example"));
 
     let next = Url::parse("http://initial-domain.com/path").unwrap();
     let mut prev = vec![Url::parse("http://initial-domain.com/new_path").unwrap()];
@@ -331,6 +479,7 @@ fn test_remove_sensitive_headers() {
     prev.push(Url::parse("http://new-domain.com/path").unwrap());
     filtered_headers.remove(AUTHORIZATION);
     filtered_headers.remove(COOKIE);
+    filtered_headers.remove(HOST);
 
     remove_sensitive_headers(&mut headers, &next, &prev);
     assert_eq!(headers, filtered_headers);
@@ -0,0 +1,348 @@
+//! Retry Handling
+//!
+//! By default, a `Request` is sent once, and any error or unsuccessful status
+//! is handed back to the caller. A `retry::Policy` can be set on a
+//! `RequestBuilder` to send the request again when it fails in a way that is
+//! likely to be temporary.
+//!
+//! Only requests whose body can be replayed are retried. A request with a
+//! streaming body is sent once, whatever its policy says.
+//!
+//! A `RetryPolicy` can instead be set on a whole client with
+//! `ClientBuilder::with_retry`, to decide from the error itself when a
+//! request is sent again.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::StatusCode;
+
+use crate::middleware::{Handling, Middleware, Next};
+use crate::Request;
+
+/// A type that controls if, when and how often a request is retried.
+///
+/// - `constant` waits the same delay between each attempt.
+/// - `exponential` doubles the delay after each attempt, with random jitter.
+/// - `none` never retries.
+///
+/// By default a policy retries on `429 Too Many Requests`,
+/// `502 Bad Gateway`, `503 Service Unavailable`, `504 Gateway Timeout`, and
+/// on errors while connecting.
+///
+/// # Example
+///
+/// ```rust
+/// # use reqwest::{Error, retry};
+/// #
+/// # async fn run() -> Result<(), Error> {
+/// let client = reqwest::Client::new();
+/// let res = client
+///     .get("http://httpbin.org/status/503")
+///     .retry(retry::Policy::exponential(3))
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Policy {
+    max_retries: usize,
+    backoff: Backoff,
+    statuses: Vec<StatusCode>,
+    connect_errors: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Backoff {
+    Constant(Duration),
+    Exponential { base: Duration, max: Duration },
+}
+
+impl Policy {
+    /// Create a `Policy` that retries up to `max_retries` times, waiting
+    /// `delay` before each retry.
+    pub fn constant(max_retries: usize, delay: Duration) -> Self {
+        Self::with_backoff(max_retries, Backoff::Constant(delay))
+    }
+
+    /// Create a `Policy` that retries up to `max_retries` times with
+    /// exponential backoff.
+    ///
+    /// The first retry waits around 100 milliseconds, and each following one
+    /// waits about twice as long as the last, up to 10 seconds. Every delay
+    /// is randomized so that many clients failing at once don't retry in
+    /// lockstep.
+    pub fn exponential(max_retries: usize) -> Self {
+        Self::with_backoff(
+            max_retries,
+            Backoff::Exponential {
+                base: Duration::from_millis(100),
+                max: Duration::from_secs(10),
+            },
+        )
+    }
+
+    /// Create a `Policy` that never retries.
+    pub fn none() -> Self {
+        Self::constant(0, Duration::ZERO)
+    }
+
+    /// Set the response statuses that trigger a retry.
+    ///
+    /// This replaces the default list.
+    pub fn statuses<I>(mut self, statuses: I) -> Self
+    where
+        I: IntoIterator<Item = StatusCode>,
+    {
+        self.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Set whether errors while connecting trigger a retry.
+    ///
+    /// Default is `true`.
+    pub fn connect_errors(mut self, enabled: bool) -> Self {
+        self.connect_errors = enabled;
+        self
+    }
+
+    fn with_backoff(max_retries: usize, backoff: Backoff) -> Self {
+        Self {
+            max_retries,
+            backoff,
+            statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            connect_errors: true,
+        }
+    }
+
+    pub(crate) fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    pub(crate) fn retries_status(&self, status: StatusCode) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    pub(crate) fn retries_connect_errors(&self) -> bool {
+        self.connect_errors
+    }
+
+    /// The delay before the retry following `retries` previous ones.
+    pub(crate) fn backoff(&self, retries: usize) -> Duration {
+        match self.backoff {
+            Backoff::Constant(delay) => delay,
+            Backoff::Exponential { base, max } => {
+                let exp = u32::try_from(retries).unwrap_or(u32::MAX).min(16);
+                let delay = base.saturating_mul(1 << exp).min(max);
+                // "equal jitter": keep half the delay, randomize the rest
+                let half = delay / 2;
+                let jitter = (crate::util::fast_random() % 1_000) as u32;
+                half + (delay - half) * jitter / 1_000
+            }
+        }
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy::none()
+    }
+}
+
+/// A type that decides whether a request that failed is sent again.
+///
+/// It is set on a client with `ClientBuilder::with_retry`, and asked after
+/// every attempt that ends with an error.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use reqwest::retry::RetryPolicy;
+///
+/// struct OnConnect;
+///
+/// impl RetryPolicy for OnConnect {
+///     fn should_retry(&self, attempt: u32, error: &reqwest::Error) -> Option<Duration> {
+///         if attempt < 3 && error.is_connect() {
+///             Some(Duration::from_millis(50))
+///         } else {
+///             None
+///         }
+///     }
+/// }
+///
+/// # fn run() -> Result<(), reqwest::Error> {
+/// let client = reqwest::Client::builder().with_retry(OnConnect).build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait RetryPolicy: Send + Sync {
+    /// Returns how long to wait before sending the request again, after
+    /// `attempt` attempts of which the last failed with `error`, or `None`
+    /// to give up and return `error`.
+    fn should_retry(&self, attempt: u32, error: &crate::Error) -> Option<Duration>;
+}
+
+/// A `RetryPolicy` that retries failed connections and timeouts, waiting
+/// twice as long before each retry.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use reqwest::retry::ExponentialBackoff;
+///
+/// # fn run() -> Result<(), reqwest::Error> {
+/// let client = reqwest::Client::builder()
+///     .with_retry(ExponentialBackoff {
+///         max_attempts: 5,
+///         ..ExponentialBackoff::default()
+///     })
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ExponentialBackoff {
+    /// How many times a request is sent at most, counting the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry.
+    pub base_delay: Duration,
+    /// The longest delay between two attempts.
+    pub max_delay: Duration,
+    /// Whether each delay is randomized, so that many clients failing at
+    /// once don't retry in lockstep.
+    pub jitter: bool,
+}
+
+impl Default for ExponentialBackoff {
+    /// Three attempts, the first retry waiting 100 milliseconds and no delay
+    /// longer than 10 seconds, with jitter.
+    fn default() -> ExponentialBackoff {
+        ExponentialBackoff {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn should_retry(&self, attempt: u32, error: &crate::Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || !(error.is_connect() || error.is_timeout()) {
+            return None;
+        }
+        let exp = attempt.saturating_sub(1).min(16);
+        let delay = self.base_delay.saturating_mul(1 << exp).min(self.max_delay);
+        if !self.jitter {
+            return Some(delay);
+        }
+        let half = delay / 2;
+        let jitter = (crate::util::fast_random() % 1_000) as u32;
+        Some(half + (delay - half) * jitter / 1_000)
+    }
+}
+
+/// Runs a `RetryPolicy` as part of the middleware chain.
+pub(crate) struct Retry(pub(crate) Arc<dyn RetryPolicy>);
+
+impl Middleware for Retry {
+    fn handle<'a>(&'a self, mut req: Request, next: Next<'a>) -> Handling<'a> {
+        Box::pin(async move {
+            if let Some(limit) = req.retry_body_buffer_limit() {
+                if let Some(body) = req.body_mut().take() {
+                    *req.body_mut() = Some(body.buffer_up_to(limit).await?);
+                }
+            }
+
+            let mut attempt = 0;
+            loop {
+                let again = req.try_clone();
+                let err = match next.run(req).await {
+                    Ok(res) => return Ok(res),
+                    Err(err) => err,
+                };
+                attempt += 1;
+                // a streaming body can't be sent twice
+                let again = match again {
+                    Some(again) => again,
+                    None => return Err(err),
+                };
+                match self.0.should_retry(attempt, &err) {
+                    Some(delay) => {
+                        log::debug!("retrying {} after {:?}: {}", again.url(), delay, err);
+                        tokio::time::sleep(delay).await;
+                        req = again;
+                    }
+                    None => return Err(err),
+                }
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Policy")
+            .field("max_retries", &self.max_retries)
+            .field("backoff", &self.backoff)
+            .field("statuses", &self.statuses)
+            .field("connect_errors", &self.connect_errors)
+            .finish()
+    }
+}
+
+#[test]
+fn test_constant_backoff() {
+    let policy = Policy::constant(3, Duration::from_millis(50));
+    assert_eq!(policy.max_retries(), 3);
+    for retries in 0..3 {
+        assert_eq!(policy.backoff(retries), Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn test_exponential_backoff() {
+    let policy = Policy::exponential(3);
+    for (retries, full) in [(0, 100), (1, 200), (2, 400), (7, 10_000), (100, 10_000)] {
+        let full = Duration::from_millis(full);
+        let delay = policy.backoff(retries);
+        assert!(delay >= full / 2 && delay <= full, "{retries}: {delay:?}");
+    }
+}
+
+#[test]
+fn test_exponential_backoff_policy() {
+    let policy = ExponentialBackoff {
+        max_attempts: 4,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(300),
+        jitter: false,
+    };
+    let err = crate::error::request(crate::error::TimedOut);
+    assert_eq!(policy.should_retry(1, &err), Some(Duration::from_millis(100)));
+    assert_eq!(policy.should_retry(2, &err), Some(Duration::from_millis(200)));
+    assert_eq!(policy.should_retry(3, &err), Some(Duration::from_millis(300)));
+    assert_eq!(policy.should_retry(4, &err), None);
+
+    let err = crate::error::decode("not retried");
+    assert_eq!(policy.should_retry(1, &err), None);
+}
+
+#[test]
+fn test_statuses() {
+    let policy = Policy::exponential(1);
+    assert!(policy.retries_status(StatusCode::SERVICE_UNAVAILABLE));
+    assert!(!policy.retries_status(StatusCode::INTERNAL_SERVER_ERROR));
+
+    let policy = policy.statuses([StatusCode::INTERNAL_SERVER_ERROR]);
+    assert!(policy.retries_status(StatusCode::INTERNAL_SERVER_ERROR));
+    assert!(!policy.retries_status(StatusCode::SERVICE_UNAVAILABLE));
+}
@@ -0,0 +1,109 @@
+//! Request signing
+//!
+//! A `RequestSigner` is called with each request just before it is sent,
+//! once its headers and body are final, so it can add a signature computed
+//! over them, like AWS Signature Version 4 does. It is set with
+//! `ClientBuilder::sign_with`.
+//!
+//! Signing happens after every middleware has run, and again for each
+//! redirect followed and each retry, since those change what is sent.
+
+use std::fmt;
+
+use crate::header::HeaderMap;
+use crate::{Method, Url};
+
+/// Trait for signing requests just before they are sent.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use reqwest::header::HeaderValue;
+/// use reqwest::sign::{RequestParts, RequestSigner};
+///
+/// struct Checksum;
+///
+/// impl RequestSigner for Checksum {
+///     fn sign(&self, parts: &mut RequestParts<'_>) {
+///         // a real signer would use a keyed hash
+///         let sum = parts.body().iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+///         let value = format!("{} {} {}", parts.method(), parts.url().path(), sum);
+///         parts
+///             .headers_mut()
+///             .insert("x-signature", HeaderValue::from_str(&value).unwrap());
+///     }
+/// }
+///
+/// # fn run() -> Result<(), reqwest::Error> {
+/// let client = reqwest::Client::builder()
+///     .sign_with(Arc::new(Checksum))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait RequestSigner: Send + Sync {
+    /// Signs a request, usually by adding headers to it.
+    fn sign(&self, parts: &mut RequestParts<'_>);
+}
+
+/// The parts of a request that a `RequestSigner` can see.
+pub struct RequestParts<'a> {
+    method: &'a Method,
+    url: &'a Url,
+    headers: &'a mut HeaderMap,
+    body: &'a [u8],
+}
+
+impl<'a> RequestParts<'a> {
+    pub(crate) fn new(
+        method: &'a Method,
+        url: &'a Url,
+        headers: &'a mut HeaderMap,
+        body: &'a [u8],
+    ) -> RequestParts<'a> {
+        RequestParts {
+            method,
+            url,
+            headers,
+            body,
+        }
+    }
+
+    /// The method the request is sent with.
+    pub fn method(&self) -> &Method {
+        self.method
+    }
+
+    /// The URL the request is sent to.
+    pub fn url(&self) -> &Url {
+        self.url
+    }
+
+    /// The headers the request is sent with, including the client's
+    /// defaults.
+    pub fn headers(&self) -> &HeaderMap {
+        self.headers
+    }
+
+    /// A mutable reference to the headers the request is sent with.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        self.headers
+    }
+
+    /// The full body of the request, which is empty if there is none.
+    pub fn body(&self) -> &[u8] {
+        self.body
+    }
+}
+
+impl fmt::Debug for RequestParts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RequestParts")
+            .field("method", self.method)
+            .field("url", self.url)
+            .field("headers", self.headers)
+            .field("body_len", &self.body.len())
+            .finish()
+    }
+}
@@ -0,0 +1,359 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "__tls")]
+use http::Uri;
+use pin_project_lite::pin_project;
+#[cfg(feature = "__tls")]
+use tower_service::Service;
+
+/// How long each phase of a request took.
+///
+/// Timings are only collected when enabled with
+/// `ClientBuilder::collect_timings`, and are read with `Response::timings`.
+///
+/// The connection phases are `None` when the request reused a connection
+/// from the pool, since they didn't happen for this request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timings {
+    dns: Option<Duration>,
+    connect: Option<Duration>,
+    tls: Option<Duration>,
+    first_byte: Duration,
+    total: Duration,
+}
+
+impl Timings {
+    /// Time spent resolving the host name.
+    ///
+    /// Also `None` if the host was an IP address, so no lookup was needed.
+    pub fn dns_lookup(&self) -> Option<Duration> {
+        self.dns
+    }
+
+    /// Time spent opening the connection, after resolving the host name.
+    ///
+    /// This includes any proxy handshake, but not the TLS handshake.
+    pub fn connect(&self) -> Option<Duration> {
+        self.connect
+    }
+
+    /// Time spent on the TLS handshake with the server.
+    ///
+    /// Also `None` for plain HTTP requests.
+    pub fn tls_handshake(&self) -> Option<Duration> {
+        self.tls
+    }
+
+    /// Time from sending the request to receiving the response headers,
+    /// without the time spent opening a connection.
+    pub fn time_to_first_byte(&self) -> Duration {
+        self.first_byte
+    }
+
+    /// Time from sending the request to receiving the response headers,
+    /// including any new connection, redirects and retries.
+    ///
+    /// Reading the response body is not included.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+}
+
+/// Durations of the phases of opening one connection, attached to it as
+/// extra connection info.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ConnectTimings {
+    dns: Option<Duration>,
+    connect: Duration,
+    tls: Option<Duration>,
+}
+
+impl ConnectTimings {
+    /// The time taken to open the connection, over all phases.
+    pub(crate) fn total(&self) -> Duration {
+        self.dns.unwrap_or_default() + self.connect + self.tls.unwrap_or_default()
+    }
+}
+
+thread_local! {
+    static CONNECTING: RefCell<Option<ConnectTimer>> = const { RefCell::new(None) };
+}
+
+/// Collects the phases of a connection as it is opened.
+///
+/// hyper only hands the destination `Uri` to the resolver and connector, so
+/// the timer of the connection being opened is kept in a thread local, set
+/// around each poll of the connect future by `ConnectTimer::time`.
+#[derive(Clone)]
+pub(crate) struct ConnectTimer(Arc<Mutex<Phases>>);
+
+struct Phases {
+    start: Instant,
+    dns: Option<Duration>,
+    connected: Option<Instant>,
+    trace: Option<crate::trace::Connecting>,
+}
+
+impl ConnectTimer {
+    pub(crate) fn new() -> ConnectTimer {
+        ConnectTimer(Arc::new(Mutex::new(Phases {
+            start: Instant::now(),
+            dns: None,
+            connected: None,
+            trace: None,
+        })))
+    }
+
+    /// Also emits the `tracing` events of the phases of a connection to
+    /// `dst`.
+    pub(crate) fn traced(self, dst: &http::Uri) -> ConnectTimer {
+        self.0.lock().unwrap().trace = Some(crate::trace::Connecting::new(dst));
+        self
+    }
+
+    /// The host of the traced connection, if it is traced.
+    pub(crate) fn traced_host(&self) -> Option<std::sync::Arc<str>> {
+        let phases = self.0.lock().unwrap();
+        phases.trace.as_ref().map(crate::trace::Connecting::host)
+    }
+
+    /// The timer of the connection being opened on this thread, if any.
+    pub(crate) fn current() -> Option<ConnectTimer> {
+        CONNECTING.with(|cell| cell.borrow().clone())
+    }
+
+    /// Runs `fut` with this timer as the current one.
+    pub(crate) fn time<F: Future>(&self, fut: F) -> Timed<F> {
+        Timed {
+            inner: fut,
+            timer: self.clone(),
+        }
+    }
+
+    pub(crate) fn dns_started(&self) {
+        if let Some(ref trace) = self.0.lock().unwrap().trace {
+            trace.dns_started();
+        }
+    }
+
+    pub(crate) fn dns_done(&self, took: Duration) {
+        let mut phases = self.0.lock().unwrap();
+        phases.dns = Some(took);
+        if let Some(ref mut trace) = phases.trace {
+            trace.dns_completed(took);
+        }
+    }
+
+    /// Marks the connection as open, and ready for a TLS handshake.
+    ///
+    /// Marking it again, after a proxy tunnel for instance, moves the mark.
+    #[cfg(feature = "__tls")]
+    pub(crate) fn connected(&self) {
+        let mut phases = self.0.lock().unwrap();
+        phases.connected = Some(Instant::now());
+        let start = phases.start;
+        if let Some(ref mut trace) = phases.trace {
+            trace.tcp_completed(start);
+        }
+    }
+
+    /// Wraps up the timings, `tls` telling if there was a handshake.
+    pub(crate) fn finish(&self, tls: bool) -> ConnectTimings {
+        let end = Instant::now();
+        let mut phases = self.0.lock().unwrap();
+        let start = phases.start;
+        if let Some(ref mut trace) = phases.trace {
+            trace.finished(start);
+        }
+        let connected = phases.connected.unwrap_or(end);
+        let dns = phases.dns.unwrap_or_default();
+        ConnectTimings {
+            dns: phases.dns,
+            connect: connected
+                .saturating_duration_since(phases.start)
+                .saturating_sub(dns),
+            tls: if tls {
+                Some(end.saturating_duration_since(connected))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Marks the connection being opened on this thread as open, if timed.
+#[cfg(feature = "__tls")]
+pub(crate) fn mark_connected() {
+    if let Some(timer) = ConnectTimer::current() {
+        timer.connected();
+    }
+}
+
+pin_project! {
+    pub(crate) struct Timed<F> {
+        #[pin]
+        inner: F,
+        timer: ConnectTimer,
+    }
+}
+
+impl<F: Future> Future for Timed<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let prev = CONNECTING.with(|cell| cell.replace(Some(this.timer.clone())));
+        let res = this.inner.poll(cx);
+        CONNECTING.with(|cell| cell.replace(prev));
+        res
+    }
+}
+
+/// A connector that marks the timed connection as open once it is done.
+///
+/// This wraps the TCP connector under a TLS one, so the handshake can be
+/// told apart from the rest of the connect.
+#[cfg(feature = "__tls")]
+#[derive(Clone)]
+pub(crate) struct MarkConnected<S>(pub(crate) S);
+
+#[cfg(feature = "__tls")]
+impl<S> Service<Uri> for MarkConnected<S>
+where
+    S: Service<Uri>,
+    S::Future: Unpin,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = MarkingConnected<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        MarkingConnected {
+            inner: self.0.call(dst),
+            timer: ConnectTimer::current(),
+        }
+    }
+}
+
+#[cfg(feature = "__tls")]
+pub(crate) struct MarkingConnected<F> {
+    inner: F,
+    timer: Option<ConnectTimer>,
+}
+
+#[cfg(feature = "__tls")]
+impl<F, T, E> Future for MarkingConnected<F>
+where
+    F: Future<Output = Result<T, E>> + Unpin,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let res = futures_core::ready!(Pin::new(&mut self.inner).poll(cx));
+        if let (Ok(_), Some(timer)) = (&res, self.timer.take()) {
+            timer.connected();
+        }
+        Poll::Ready(res)
+    }
+}
+
+/// Tracks a request across its hops, to build its `Timings`.
+#[derive(Debug)]
+pub(crate) struct RequestTimer {
+    start: Instant,
+    hop: Option<Instant>,
+}
+
+impl RequestTimer {
+    pub(crate) fn new() -> RequestTimer {
+        RequestTimer {
+            start: Instant::now(),
+            hop: None,
+        }
+    }
+
+    /// Starts timing a hop, unless one is already being timed.
+    pub(crate) fn start_hop(&mut self) {
+        self.hop.get_or_insert_with(Instant::now);
+    }
+
+    /// Forgets the current hop, which is being sent again.
+    pub(crate) fn restart_hop(&mut self) {
+        self.hop = None;
+    }
+
+    /// Ends the current hop, whose response arrived with `extensions`, and
+    /// records its `Timings` there.
+    ///
+    /// `reused` tells if the hop went over a connection that was already
+    /// open, in which case its connect timings are left out.
+    pub(crate) fn finish_hop(&mut self, extensions: &mut http::Extensions, reused: bool) {
+        let end = Instant::now();
+        let hop = self.hop.take().unwrap_or(end);
+        let conn = extensions
+            .remove::<ConnectTimings>()
+            .filter(|_| !reused);
+        let connecting = conn.map_or(Duration::ZERO, |c| c.total());
+        extensions.insert(Timings {
+            dns: conn.and_then(|c| c.dns),
+            connect: conn.map(|c| c.connect),
+            tls: conn.and_then(|c| c.tls),
+            first_byte: end.saturating_duration_since(hop).saturating_sub(connecting),
+            total: end.saturating_duration_since(self.start),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_connection_has_no_connect_timings() {
+        let timer = ConnectTimer::new();
+        timer.dns_done(Duration::from_millis(1));
+        let conn = timer.finish(true);
+
+        let mut request = RequestTimer::new();
+        request.start_hop();
+        let mut extensions = http::Extensions::new();
+        extensions.insert(conn);
+        request.finish_hop(&mut extensions, false);
+        let timings = *extensions.get::<Timings>().unwrap();
+        assert_eq!(timings.dns_lookup(), Some(Duration::from_millis(1)));
+        assert!(timings.connect().is_some());
+        assert!(timings.tls_handshake().is_some());
+
+        request.start_hop();
+        let mut extensions = http::Extensions::new();
+        extensions.insert(conn);
+        request.finish_hop(&mut extensions, true);
+        let reused = *extensions.get::<Timings>().unwrap();
+        assert_eq!(reused.dns_lookup(), None);
+        assert_eq!(reused.connect(), None);
+        assert_eq!(reused.tls_handshake(), None);
+        assert!(reused.total() >= timings.total());
+    }
+
+    #[test]
+    fn timer_is_current_while_polled() {
+        let timer = ConnectTimer::new();
+        let fut = timer.time(futures_util::future::lazy(|_| {
+            ConnectTimer::current().unwrap().dns_done(Duration::from_millis(1));
+        }));
+        futures_util::FutureExt::now_or_never(fut).unwrap();
+        assert!(ConnectTimer::current().is_none());
+        let timings = timer.finish(false);
+        assert_eq!(timings.dns, Some(Duration::from_millis(1)));
+        assert_eq!(timings.tls, None);
+    }
+}
@@ -0,0 +1,192 @@
+//! Structured `tracing` events about connections, emitted when enabled
+//! with `ClientBuilder::connection_verbose`.
+//!
+//! All events have the target `reqwest::connection` and the `TRACE` level,
+//! with the fields `url.host`, `remote.addr`, `duration_ms` and
+//! `http.version`, the last three only once they are known.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use http::{Uri, Version};
+
+macro_rules! event {
+    ($message:literal, $host:expr, $remote:expr, $duration:expr, $version:expr) => {{
+        let host: &str = &$host;
+        let remote = $remote.map(tracing::field::display);
+        let duration = $duration.map(millis);
+        let version = $version.map(tracing::field::debug);
+        tracing::event!(
+            target: "reqwest::connection",
+            tracing::Level::TRACE,
+            url.host = host,
+            remote.addr = remote,
+            duration_ms = duration,
+            http.version = version,
+            $message
+        )
+    }};
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+const NONE: Option<SocketAddr> = None;
+const NO_VERSION: Option<Version> = None;
+
+/// The phases of a connection being opened, kept by its `ConnectTimer`.
+pub(crate) struct Connecting {
+    host: Arc<str>,
+    tls: bool,
+    tcp_start: Option<Instant>,
+    tcp_done: Option<Instant>,
+}
+
+impl Connecting {
+    pub(crate) fn new(dst: &Uri) -> Connecting {
+        let host = dst.host().unwrap_or("");
+        let mut connecting = Connecting {
+            host: host.into(),
+            tls: dst.scheme() == Some(&http::uri::Scheme::HTTPS),
+            tcp_start: None,
+            tcp_done: None,
+        };
+        // no lookup comes first
+        let ip = host.trim_start_matches('[').trim_end_matches(']');
+        if ip.parse::<IpAddr>().is_ok() {
+            connecting.tcp_started();
+        }
+        connecting
+    }
+
+    pub(crate) fn dns_started(&self) {
+        event!("dns resolution started", self.host, NONE, None, NO_VERSION);
+    }
+
+    pub(crate) fn dns_completed(&mut self, took: Duration) {
+        event!("dns resolution completed", self.host, NONE, Some(took), NO_VERSION);
+        self.tcp_started();
+    }
+
+    fn tcp_started(&mut self) {
+        event!("tcp connect started", self.host, NONE, None, NO_VERSION);
+        self.tcp_start = Some(Instant::now());
+    }
+
+    /// Marks the connection as open, and ready for a TLS handshake.
+    pub(crate) fn tcp_completed(&mut self, start: Instant) {
+        let now = Instant::now();
+        let took = now.saturating_duration_since(self.tcp_start.unwrap_or(start));
+        event!("tcp connect completed", self.host, NONE, Some(took), NO_VERSION);
+        self.tcp_done = Some(now);
+        if self.tls {
+            event!("tls handshake started", self.host, NONE, None, NO_VERSION);
+        }
+    }
+
+    /// Ends the phases of opening the connection, which started at `start`.
+    pub(crate) fn finished(&mut self, start: Instant) {
+        match self.tcp_done {
+            Some(tcp_done) if self.tls => {
+                let took = tcp_done.elapsed();
+                event!("tls handshake completed", self.host, NONE, Some(took), NO_VERSION);
+            }
+            Some(_) => (),
+            None => self.tcp_completed(start),
+        }
+    }
+
+    pub(crate) fn host(&self) -> Arc<str> {
+        self.host.clone()
+    }
+}
+
+/// An open connection, with the events about its use until it is closed.
+pub(crate) struct Connection {
+    peer: Arc<Peer>,
+    opened: Instant,
+    sent: bool,
+    received: bool,
+}
+
+struct Peer {
+    host: Arc<str>,
+    remote: Option<SocketAddr>,
+    version: Version,
+}
+
+impl Connection {
+    pub(crate) fn opened(
+        host: Arc<str>,
+        remote: Option<SocketAddr>,
+        h2: bool,
+        took: Duration,
+    ) -> Connection {
+        let version = if h2 {
+            Version::HTTP_2
+        } else {
+            Version::HTTP_11
+        };
+        event!("connection opened", host, remote, Some(took), Some(version));
+        Connection {
+            peer: Arc::new(Peer {
+                host,
+                remote,
+                version,
+            }),
+            opened: Instant::now(),
+            sent: false,
+            received: false,
+        }
+    }
+
+    pub(crate) fn has_received(&self) -> bool {
+        self.received
+    }
+
+    pub(crate) fn wrote(&mut self, n: usize) {
+        if n > 0 && !self.sent {
+            self.sent = true;
+            let (peer, took) = (&self.peer, Some(self.opened.elapsed()));
+            event!("first byte sent", peer.host, peer.remote, took, Some(peer.version));
+        }
+    }
+
+    pub(crate) fn read(&mut self, n: usize) {
+        if n > 0 && !self.received {
+            self.received = true;
+            let (peer, took) = (&self.peer, Some(self.opened.elapsed()));
+            event!("first byte received", peer.host, peer.remote, took, Some(peer.version));
+        }
+    }
+
+    /// Given to each response received over the connection.
+    pub(crate) fn responses(&self) -> PoolUse {
+        PoolUse(self.peer.clone())
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let peer = &self.peer;
+        let took = Some(self.opened.elapsed());
+        event!("connection closed", peer.host, peer.remote, took, Some(peer.version));
+    }
+}
+
+/// Records whether a response came from a connection of the pool.
+#[derive(Clone)]
+pub(crate) struct PoolUse(Arc<Peer>);
+
+impl PoolUse {
+    pub(crate) fn record(&self, reused: bool, version: Version) {
+        let peer = &self.0;
+        if reused {
+            event!("connection pool hit", peer.host, peer.remote, None, Some(version));
+        } else {
+            event!("connection pool miss", peer.host, peer.remote, None, Some(version));
+        }
+    }
+}
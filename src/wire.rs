@@ -0,0 +1,292 @@
+//! Wire debugging
+//!
+//! A `WireDebugConfig` set with `ClientBuilder::wire_debug` is called with a
+//! `WireEvent` for every request a `Client` actually sends, and for the
+//! response to it: the first request, each redirect followed and each
+//! retry. It sees them as they go over the wire, after default headers,
+//! cookies and the signature were added, and before the response is
+//! decompressed.
+//!
+//! Headers that carry credentials are redacted by default.
+//!
+//! # Example
+//!
+//! ```rust
+//! use reqwest::wire::{WireDebugConfig, WireEvent};
+//!
+//! # fn run() -> Result<(), reqwest::Error> {
+//! let client = reqwest::Client::builder()
+//!     .wire_debug(
+//!         WireDebugConfig::new(|event| match event {
+//!             WireEvent::Request { method, url, headers, .. } => {
+//!                 eprintln!("> {method} {url} {headers:?}");
+//!             }
+//!             WireEvent::Response { status, headers, body, .. } => {
+//!                 eprintln!("< {status} {headers:?} {body:?}");
+//!             }
+//!             _ => {}
+//!         })
+//!         .body_limit(1024),
+//!     )
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use http_body_util::BodyExt;
+
+use crate::async_impl::body::ResponseBody;
+use crate::error::BoxError;
+use crate::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE,
+};
+use crate::middleware::{Handling, Middleware, Next};
+use crate::{Method, Request, StatusCode, Url, Version};
+
+/// What went over the wire, as given to a `WireDebugConfig`'s callback.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WireEvent<'a> {
+    /// A request, just before it is sent.
+    #[non_exhaustive]
+    Request {
+        /// The method.
+        method: &'a Method,
+        /// The URL, after any redirect.
+        url: &'a Url,
+        /// The HTTP version asked for.
+        version: Version,
+        /// The headers, redacted.
+        headers: &'a HeaderMap,
+        /// The start of the body, if capturing it is enabled and the body
+        /// isn't a stream.
+        body: Option<&'a [u8]>,
+    },
+    /// The response to a request.
+    #[non_exhaustive]
+    Response {
+        /// The URL of the request.
+        url: &'a Url,
+        /// The status.
+        status: StatusCode,
+        /// The HTTP version.
+        version: Version,
+        /// The headers, redacted.
+        headers: &'a HeaderMap,
+        /// The start of the body, still compressed if it was sent so, if
+        /// capturing it is enabled.
+        body: Option<&'a [u8]>,
+    },
+}
+
+/// Configures wire debugging, see the [module docs](self).
+#[derive(Clone)]
+pub struct WireDebugConfig {
+    callback: Arc<dyn Fn(&WireEvent<'_>) + Send + Sync>,
+    redact: Vec<HeaderName>,
+    body_limit: usize,
+}
+
+impl WireDebugConfig {
+    /// Calls `callback` with each request sent and each response received.
+    ///
+    /// By default, `Authorization`, `Proxy-Authorization`, `Cookie` and
+    /// `Set-Cookie` are redacted, and no body is captured.
+    pub fn new<F>(callback: F) -> WireDebugConfig
+    where
+        F: Fn(&WireEvent<'_>) + Send + Sync + 'static,
+    {
+        WireDebugConfig {
+            callback: Arc::new(callback),
+            redact: vec![AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE, SET_COOKIE],
+            body_limit: 0,
+        }
+    }
+
+    /// Redacts the header `name` too.
+    pub fn redact_header(mut self, name: HeaderName) -> WireDebugConfig {
+        self.redact.push(name);
+        self
+    }
+
+    /// Redacts no header at all, not even the default ones.
+    pub fn no_redaction(mut self) -> WireDebugConfig {
+        self.redact.clear();
+        self
+    }
+
+    /// Captures up to `limit` bytes of each body.
+    ///
+    /// A response body isn't consumed to do so: it is copied as it is read,
+    /// so its response event only fires once `limit` bytes were read, the
+    /// body ended, or it was dropped. Request bodies that are streams
+    /// aren't captured.
+    ///
+    /// Default is 0, which captures nothing and fires response events as
+    /// soon as the headers arrive.
+    pub fn body_limit(mut self, limit: usize) -> WireDebugConfig {
+        self.body_limit = limit;
+        self
+    }
+
+    fn redacted<'h>(&self, headers: &'h HeaderMap) -> Cow<'h, HeaderMap> {
+        if !self.redact.iter().any(|name| headers.contains_key(name)) {
+            return Cow::Borrowed(headers);
+        }
+        let mut redacted = headers.clone();
+        for name in &self.redact {
+            if let http::header::Entry::Occupied(mut entry) = redacted.entry(name) {
+                let mut value = HeaderValue::from_static("[redacted]");
+                value.set_sensitive(true);
+                entry.insert(value);
+            }
+        }
+        Cow::Owned(redacted)
+    }
+}
+
+impl fmt::Debug for WireDebugConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WireDebugConfig")
+            .field("redact", &self.redact)
+            .field("body_limit", &self.body_limit)
+            .finish()
+    }
+}
+
+/// Runs a `WireDebugConfig` as the innermost hop middleware.
+pub(crate) struct WireDebug(pub(crate) Arc<WireDebugConfig>);
+
+impl Middleware for WireDebug {
+    fn handle<'a>(&'a self, req: Request, next: Next<'a>) -> Handling<'a> {
+        let config = &self.0;
+        {
+            let headers = config.redacted(req.headers());
+            let body = match req.body().and_then(|body| body.as_bytes()) {
+                Some(body) if config.body_limit > 0 => {
+                    Some(&body[..body.len().min(config.body_limit)])
+                }
+                _ => None,
+            };
+            (config.callback)(&WireEvent::Request {
+                method: req.method(),
+                url: req.url(),
+                version: req.version(),
+                headers: &headers,
+                body,
+            });
+        }
+
+        Box::pin(async move {
+            let res = next.run(req).await?;
+            let headers = config.redacted(res.headers()).into_owned();
+            let head = Head {
+                config: config.clone(),
+                url: res.url().clone(),
+                status: res.status(),
+                version: res.version(),
+                headers,
+            };
+            if config.body_limit == 0 {
+                head.emit(None);
+                return Ok(res);
+            }
+            Ok(res.map_hop_body(|body| {
+                Tee {
+                    inner: body,
+                    captured: Vec::new(),
+                    head: Some(head),
+                }
+                .boxed()
+            }))
+        })
+    }
+}
+
+/// A response whose event hasn't fired yet.
+struct Head {
+    config: Arc<WireDebugConfig>,
+    url: Url,
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+}
+
+impl Head {
+    fn emit(self, body: Option<&[u8]>) {
+        (self.config.callback)(&WireEvent::Response {
+            url: &self.url,
+            status: self.status,
+            version: self.version,
+            headers: &self.headers,
+            body,
+        });
+    }
+}
+
+/// Copies the start of a response body as it is read.
+struct Tee {
+    inner: ResponseBody,
+    captured: Vec<u8>,
+    head: Option<Head>,
+}
+
+impl Tee {
+    fn emit(&mut self) {
+        if let Some(head) = self.head.take() {
+            head.emit(Some(&self.captured));
+        }
+    }
+}
+
+impl HttpBody for Tee {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = futures_core::ready!(Pin::new(&mut self.inner).poll_frame(cx));
+        let limit = match self.head {
+            Some(ref head) => head.config.body_limit,
+            None => return Poll::Ready(frame),
+        };
+        match frame {
+            Some(Ok(ref f)) => {
+                if let Some(data) = f.data_ref() {
+                    let room = limit - self.captured.len();
+                    self.captured
+                        .extend_from_slice(&data[..data.len().min(room)]);
+                    if self.captured.len() == limit {
+                        self.emit();
+                    }
+                }
+            }
+            Some(Err(_)) | None => self.emit(),
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for Tee {
+    fn drop(&mut self) {
+        self.emit();
+    }
+}
@@ -145,3 +145,38 @@ async fn brotli_case(response_size: usize, chunk_size: usize) {
     let body = res.text().await.expect("text");
     assert_eq!(body, content);
 }
+
+#[tokio::test]
+async fn test_compress_body_brotli() {
+    use http_body_util::BodyExt;
+
+    let content: String = (0..10_000).map(|i| format!("test {i}")).collect();
+    let expected = content.clone();
+
+    let server = server::http(move |req| {
+        let expected = expected.clone();
+        async move {
+            assert_eq!(req.headers()["content-encoding"], "br");
+            let len = req.headers()["content-length"].clone();
+
+            let compressed = req.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(len, compressed.len().to_string());
+            let mut body = String::new();
+            brotli_crate::Decompressor::new(&compressed[..], 4096)
+                .read_to_string(&mut body)
+                .unwrap();
+            assert_eq!(body, expected);
+            http::Response::default()
+        }
+    });
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/compress", server.addr()))
+        .body(content)
+        .compress_body(reqwest::ContentEncoding::Brotli)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
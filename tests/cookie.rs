@@ -95,6 +95,36 @@ async fn cookie_store_simple() {
     client.get(&url).send().await.unwrap();
 }
 
+#[tokio::test]
+async fn cookie_provider_shared_between_clients() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/2" {
+            assert_eq!(req.headers()["cookie"], "key=val");
+        }
+        http::Response::builder()
+            .header("Set-Cookie", "key=val; HttpOnly")
+            .body(Default::default())
+            .unwrap()
+    });
+
+    let jar = std::sync::Arc::new(reqwest::cookie::Jar::default());
+    let first = reqwest::Client::builder()
+        .cookie_provider(jar.clone())
+        .build()
+        .unwrap();
+    let second = reqwest::Client::builder()
+        .cookie_provider(jar.clone())
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/", server.addr());
+    first.get(&url).send().await.unwrap();
+
+    // the cookie set through the first client is sent by the second
+    let url = format!("http://{}/2", server.addr());
+    second.get(&url).send().await.unwrap();
+}
+
 #[tokio::test]
 async fn cookie_store_overwrite_existing() {
     let server = server::http(move |req| async move {
@@ -201,3 +231,67 @@ async fn cookie_store_path() {
     let url = format!("http://{}/subpath", server.addr());
     client.get(&url).send().await.unwrap();
 }
+
+#[tokio::test]
+async fn cookie_provider_persists_across_clients() {
+    use reqwest::cookie::CookieStore;
+    use reqwest::header::HeaderValue;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    // a store keeping every cookie it is given in a file, with no matching
+    struct FileStore(PathBuf);
+
+    impl CookieStore for FileStore {
+        fn set_cookies(&self, headers: &mut dyn Iterator<Item = &HeaderValue>, _: &url::Url) {
+            let mut saved = std::fs::read_to_string(&self.0).unwrap_or_default();
+            for header in headers {
+                let pair = header.to_str().unwrap().split(';').next().unwrap();
+                saved.push_str(pair);
+                saved.push('\n');
+            }
+            std::fs::write(&self.0, saved).unwrap();
+        }
+
+        fn cookies(&self, _: &url::Url) -> Option<HeaderValue> {
+            let saved = std::fs::read_to_string(&self.0).ok()?;
+            let pairs = saved.lines().collect::<Vec<_>>().join("; ");
+            HeaderValue::from_str(&pairs).ok()
+        }
+    }
+
+    let server = server::http(move |req| async move {
+        if req.uri() == "/login" {
+            assert_eq!(req.headers().get("cookie"), None);
+            http::Response::builder()
+                .header("Set-Cookie", "session=abc; HttpOnly")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            assert_eq!(req.headers()["cookie"], "session=abc");
+            http::Response::default()
+        }
+    });
+
+    let path = std::env::temp_dir().join(format!("reqwest-cookies-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = reqwest::Client::builder()
+        .cookie_provider(Arc::new(FileStore(path.clone())))
+        .build()
+        .unwrap();
+    let url = format!("http://{}/login", server.addr());
+    client.get(&url).send().await.unwrap();
+    drop(client);
+
+    // a new client, and store, as after a restart
+    let client = reqwest::Client::builder()
+        .cookie_provider(Arc::new(FileStore(path.clone())))
+        .build()
+        .unwrap();
+    let url = format!("http://{}/account", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    std::fs::remove_file(&path).unwrap();
+}
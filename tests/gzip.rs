@@ -149,3 +149,176 @@ async fn gzip_case(response_size: usize, chunk_size: usize) {
     let body = res.text().await.expect("text");
     assert_eq!(body, content);
 }
+
+#[tokio::test]
+async fn test_compress_body_gzip() {
+    use http_body_util::BodyExt;
+    use std::io::Read;
+
+    let content: String = (0..10_000).map(|i| format!("test {i}")).collect();
+    let expected = content.clone();
+
+    let server = server::http(move |req| {
+        let expected = expected.clone();
+        async move {
+            assert_eq!(req.headers()["content-encoding"], "gzip");
+            let len = req.headers()["content-length"].clone();
+
+            let gzipped = req.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(len, gzipped.len().to_string());
+            let mut decoder = libflate::gzip::Decoder::new(&gzipped[..]).unwrap();
+            let mut body = String::new();
+            decoder.read_to_string(&mut body).unwrap();
+            assert_eq!(body, expected);
+            http::Response::default()
+        }
+    });
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/compress", server.addr()))
+        .header("content-length", content.len())
+        .body(content)
+        .compress_body(reqwest::ContentEncoding::Gzip)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_compress_body_gzip_stream() {
+    use http_body_util::BodyExt;
+    use std::io::Read;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["content-encoding"], "gzip");
+        assert_eq!(req.headers()["transfer-encoding"], "chunked");
+        assert!(req.headers().get("content-length").is_none());
+
+        let gzipped = req.into_body().collect().await.unwrap().to_bytes();
+        let mut decoder = libflate::gzip::Decoder::new(&gzipped[..]).unwrap();
+        let mut body = String::new();
+        decoder.read_to_string(&mut body).unwrap();
+        assert_eq!(body, "hello world");
+        http::Response::default()
+    });
+
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>("hello"), Ok(" world")]);
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/compress", server.addr()))
+        .header("content-length", 11)
+        .body(reqwest::Body::wrap_stream(stream))
+        .compress_body(reqwest::ContentEncoding::Gzip)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_compress_body_default_policy() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        let encoding = req.headers().get("content-encoding").cloned();
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        match encoding {
+            Some(encoding) => assert_eq!(encoding, "gzip"),
+            None => assert!(body.is_empty()),
+        }
+        if body.starts_with(b"already") {
+            assert_eq!(body, "already encoded");
+        }
+        http::Response::default()
+    });
+
+    let client = reqwest::Client::builder()
+        .compress_body(reqwest::ContentEncoding::Gzip)
+        .build()
+        .unwrap();
+    let url = format!("http://{}/compress", server.addr());
+
+    // compressed by default
+    let res = client.post(&url).body("hello").send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    // nothing to compress
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    // the body is already encoded, and sent as is
+    let res = client
+        .post(&url)
+        .header("content-encoding", "gzip")
+        .body("already encoded")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_no_decompress_passes_body_through() {
+    let mut encoder = libflate::gzip::Encoder::new(Vec::new()).unwrap();
+    encoder.write_all(b"test request").unwrap();
+    let gzipped = encoder.finish().into_result().unwrap();
+    let expected = gzipped.clone();
+
+    let server = server::http(move |req| {
+        // the server compresses whatever it was asked for
+        assert!(req.headers().get("accept-encoding").is_none());
+        let gzipped = gzipped.clone();
+        async move {
+            http::Response::builder()
+                .header("content-encoding", "gzip")
+                .header("content-length", gzipped.len())
+                .body(gzipped.into())
+                .unwrap()
+        }
+    });
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/raw", server.addr()))
+        .no_decompress()
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.headers()["content-encoding"], "gzip");
+    assert_eq!(res.content_length(), Some(expected.len() as u64));
+    assert_eq!(res.bytes().await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_max_response_size_counts_decompressed_bytes() {
+    let mut encoder = libflate::gzip::Encoder::new(Vec::new()).unwrap();
+    encoder.write_all(&[0; 100_000]).unwrap();
+    let gzipped = encoder.finish().into_result().unwrap();
+    assert!(gzipped.len() < 10_000);
+
+    let server = server::http(move |_req| {
+        let gzipped = gzipped.clone();
+        async move {
+            http::Response::builder()
+                .header("content-encoding", "gzip")
+                .header("content-length", gzipped.len())
+                .body(gzipped.into())
+                .unwrap()
+        }
+    });
+
+    let err = reqwest::Client::builder()
+        .max_response_size(10_000)
+        .build()
+        .unwrap()
+        .get(format!("http://{}/bomb", server.addr()))
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap_err();
+    assert!(err.is_body_too_large());
+}
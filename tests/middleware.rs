@@ -0,0 +1,408 @@
+#![cfg(not(target_arch = "wasm32"))]
+mod support;
+use support::server;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::middleware::{Handling, Middleware, Next};
+use reqwest::{Request, StatusCode};
+
+struct AddHeader(HeaderName, HeaderValue);
+
+impl Middleware for AddHeader {
+    fn handle<'a>(&'a self, mut req: Request, next: Next<'a>) -> Handling<'a> {
+        req.headers_mut().append(self.0.clone(), self.1.clone());
+        next.run(req)
+    }
+}
+
+struct RetryOn503 {
+    max_attempts: usize,
+}
+
+impl Middleware for RetryOn503 {
+    fn handle<'a>(&'a self, mut req: Request, next: Next<'a>) -> Handling<'a> {
+        Box::pin(async move {
+            let mut attempts = 1;
+            loop {
+                let retry = req.try_clone().filter(|_| attempts < self.max_attempts);
+                let res = next.run(req).await?;
+                match retry {
+                    Some(again) if res.status() == StatusCode::SERVICE_UNAVAILABLE => {
+                        req = again;
+                        attempts += 1;
+                    }
+                    _ => return Ok(res),
+                }
+            }
+        })
+    }
+}
+
+struct ShortCircuit;
+
+impl Middleware for ShortCircuit {
+    fn handle<'a>(&'a self, _req: Request, _next: Next<'a>) -> Handling<'a> {
+        Box::pin(async { Ok(http::Response::new("cached").into()) })
+    }
+}
+
+/// Records the path of each request and the status of its response.
+#[derive(Clone, Default)]
+struct Log(Arc<Mutex<Vec<(String, u16)>>>);
+
+impl Middleware for Log {
+    fn handle<'a>(&'a self, req: Request, next: Next<'a>) -> Handling<'a> {
+        Box::pin(async move {
+            let path = req.url().path().to_owned();
+            let res = next.run(req).await?;
+            self.0.lock().unwrap().push((path, res.status().as_u16()));
+            Ok(res)
+        })
+    }
+}
+
+impl Log {
+    fn entries(&self) -> Vec<(String, u16)> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+#[tokio::test]
+async fn middleware_adds_header() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        let values = req
+            .headers()
+            .get_all("x-layer")
+            .iter()
+            .map(|v| v.to_str().unwrap())
+            .collect::<Vec<_>>();
+        // middleware run in the order they were added
+        assert_eq!(values, ["outer", "inner"]);
+        http::Response::default()
+    });
+
+    let client = reqwest::Client::builder()
+        .with_middleware(AddHeader(
+            HeaderName::from_static("x-layer"),
+            HeaderValue::from_static("outer"),
+        ))
+        .with_middleware(AddHeader(
+            HeaderName::from_static("x-layer"),
+            HeaderValue::from_static("inner"),
+        ))
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{}/header", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn middleware_retries_503() {
+    let _ = env_logger::try_init();
+
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    let server = server::http(move |_req| {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            let status = if n < 2 {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::OK
+            };
+            http::Response::builder()
+                .status(status)
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .with_middleware(RetryOn503 { max_attempts: 3 })
+        .build()
+        .unwrap();
+
+    // a clone shares the middleware chain
+    let res = client
+        .clone()
+        .get(format!("http://{}/retry", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn middleware_short_circuits() {
+    let _ = env_logger::try_init();
+
+    let client = reqwest::Client::builder()
+        .with_middleware(ShortCircuit)
+        .build()
+        .unwrap();
+
+    // nothing listens here, the middleware answers first
+    let text = client
+        .get("http://127.0.0.1:9/never")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(text, "cached");
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_client_runs_middleware() {
+    let _ = env_logger::try_init();
+
+    let client = reqwest::blocking::Client::builder()
+        .with_middleware(ShortCircuit)
+        .build()
+        .unwrap();
+
+    let text = client
+        .get("http://127.0.0.1:9/never")
+        .send()
+        .unwrap()
+        .text();
+    assert_eq!(text.unwrap(), "cached");
+}
+
+#[tokio::test]
+async fn hop_middleware_runs_per_redirect() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        if req.uri() == "/start" {
+            assert!(!req.headers().contains_key("x-hop"));
+            http::Response::builder()
+                .status(302)
+                .header("location", "/dst")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            assert_eq!(req.uri(), "/dst");
+            assert_eq!(req.headers()["x-hop"], "yes");
+            http::Response::default()
+        }
+    });
+
+    let (outer, hops) = (Log::default(), Log::default());
+    let client = reqwest::Client::builder()
+        .with_middleware(outer.clone())
+        .with_hop_middleware(hops.clone())
+        .with_hop_middleware(AddHeaderAfterFirstHop)
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{}/start", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.url().path(), "/dst");
+
+    assert_eq!(outer.entries(), vec![("/start".to_owned(), 200)]);
+    assert_eq!(
+        hops.entries(),
+        vec![("/start".to_owned(), 302), ("/dst".to_owned(), 200)]
+    );
+}
+
+/// Adds `x-hop` to the requests following a redirect.
+struct AddHeaderAfterFirstHop;
+
+impl Middleware for AddHeaderAfterFirstHop {
+    fn handle<'a>(&'a self, mut req: Request, next: Next<'a>) -> Handling<'a> {
+        if req.url().path() != "/start" {
+            req.headers_mut()
+                .insert("x-hop", HeaderValue::from_static("yes"));
+        }
+        next.run(req)
+    }
+}
+
+#[tokio::test]
+async fn hop_middleware_answer_is_redirected() {
+    let _ = env_logger::try_init();
+
+    struct RedirectOld;
+
+    impl Middleware for RedirectOld {
+        fn handle<'a>(&'a self, req: Request, next: Next<'a>) -> Handling<'a> {
+            if req.url().path() == "/old" {
+                return Box::pin(async {
+                    let res = http::Response::builder()
+                        .status(301)
+                        .header("location", "/new")
+                        .body("")
+                        .unwrap();
+                    Ok(res.into())
+                });
+            }
+            next.run(req)
+        }
+    }
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.uri(), "/new");
+        http::Response::new("new".into())
+    });
+
+    let res = reqwest::Client::builder()
+        .with_hop_middleware(RedirectOld)
+        .build()
+        .unwrap()
+        .get(format!("http://{}/old", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.url().path(), "/new");
+    assert_eq!(res.text().await.unwrap(), "new");
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct RequestId(u64);
+
+/// Copies the `RequestId` extension of the request to its response.
+struct PropagateId;
+
+impl Middleware for PropagateId {
+    fn handle<'a>(&'a self, req: Request, next: Next<'a>) -> Handling<'a> {
+        let id = req.extensions().get::<RequestId>().cloned();
+        Box::pin(async move {
+            let mut res = next.run(req).await?;
+            if let Some(id) = id {
+                res.extensions_mut().insert(id);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[tokio::test]
+async fn extension_round_trip() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |_req| async { http::Response::default() });
+    let url = format!("http://{}/ext", server.addr());
+
+    let client = reqwest::Client::builder()
+        .with_middleware(PropagateId)
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(&url)
+        .extension(RequestId(7))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.extension::<RequestId>(), Some(&RequestId(7)));
+
+    // nothing is copied without a middleware doing it
+    let res = reqwest::Client::new()
+        .get(&url)
+        .extension(RequestId(7))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.extension::<RequestId>(), None);
+}
+
+#[tokio::test]
+async fn wire_debug_sees_each_hop() {
+    use reqwest::wire::{WireDebugConfig, WireEvent};
+
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        if req.uri() == "/start" {
+            http::Response::builder()
+                .status(307)
+                .header("location", "/dst")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            http::Response::builder()
+                .header("set-cookie", "key=val")
+                .body("hello world".into())
+                .unwrap()
+        }
+    });
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = events.clone();
+    let client = reqwest::Client::builder()
+        .wire_debug(
+            WireDebugConfig::new(move |event| {
+                let entry = match event {
+                    WireEvent::Request {
+                        method,
+                        url,
+                        headers,
+                        body,
+                        ..
+                    } => format!(
+                        "> {} {} {:?} {:?}",
+                        method,
+                        url.path(),
+                        headers.get("authorization").map(|v| v.to_str().unwrap()),
+                        body.map(String::from_utf8_lossy),
+                    ),
+                    WireEvent::Response {
+                        status,
+                        headers,
+                        body,
+                        ..
+                    } => format!(
+                        "< {} {:?} {:?}",
+                        status.as_u16(),
+                        headers.get("set-cookie").map(|v| v.to_str().unwrap()),
+                        body.map(String::from_utf8_lossy),
+                    ),
+                    _ => unreachable!(),
+                };
+                log.lock().unwrap().push(entry);
+            })
+            .body_limit(5),
+        )
+        .build()
+        .unwrap();
+
+    let res = client
+        .post(format!("http://{}/start", server.addr()))
+        .header("authorization", "secret")
+        .body("ping pong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.url().path(), "/dst");
+    // the captured bytes aren't taken from the body
+    assert_eq!(res.text().await.unwrap(), "hello world");
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            r#"> POST /start Some("[redacted]") Some("ping ")"#,
+            r#"< 307 None Some("")"#,
+            r#"> POST /dst Some("[redacted]") Some("ping ")"#,
+            r#"< 200 Some("[redacted]") Some("hello")"#,
+        ]
+    );
+}
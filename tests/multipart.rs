@@ -119,6 +119,112 @@ async fn stream_part() {
     assert_eq!(res.status(), reqwest::StatusCode::OK);
 }
 
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn async_read_part_chunked() {
+    let _ = env_logger::try_init();
+
+    let part = reqwest::multipart::Part::reader(&b"part1 part2"[..], None);
+    let form = reqwest::multipart::Form::new().part("reader", part);
+
+    let expected_body = format!(
+        "\
+         --{0}\r\n\
+         Content-Disposition: form-data; name=\"reader\"\r\n\
+         \r\n\
+         part1 part2\r\n\
+         --{0}--\r\n\
+         ",
+        form.boundary()
+    );
+
+    let server = server::http(move |req| {
+        let expected_body = expected_body.clone();
+        async move {
+            assert_eq!(req.headers()["transfer-encoding"], "chunked");
+            assert!(!req.headers().contains_key("content-length"));
+
+            let full = req.collect().await.unwrap().to_bytes();
+            assert_eq!(full, expected_body.as_bytes());
+
+            http::Response::default()
+        }
+    });
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/multipart/reader", server.addr()))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Failed to post multipart");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn async_read_part_multi_gigabyte() {
+    use tokio::io::AsyncReadExt;
+
+    let _ = env_logger::try_init();
+
+    const LEN: u64 = 2 * 1024 * 1024 * 1024;
+
+    let part = reqwest::multipart::Part::reader(tokio::io::repeat(b'x').take(LEN), Some(LEN))
+        .file_name("huge.bin");
+    let form = reqwest::multipart::Form::new()
+        .text("foo", "bar")
+        .part("huge", part);
+
+    let server = server::http(move |req| async move {
+        let expected: u64 = req.headers()["content-length"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(expected > LEN);
+
+        // count the bytes as they come, only keeping the start
+        let mut body = req.into_body();
+        let mut head = Vec::new();
+        let mut received = 0;
+        while let Some(frame) = body.frame().await {
+            if let Ok(data) = frame.unwrap().into_data() {
+                let keep = data.len().min(1024 - head.len());
+                head.extend_from_slice(&data[..keep]);
+                received += data.len() as u64;
+            }
+        }
+        assert_eq!(received, expected);
+        let head = String::from_utf8_lossy(&head);
+        assert!(
+            head.contains(&format!("content-length: {LEN}\r\n")),
+            "{head}"
+        );
+
+        http::Response::default()
+    });
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/multipart/huge", server.addr()))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Failed to post multipart");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    // the peak memory of the whole test process stays far below the body size
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        let peak_kb: u64 = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap();
+        assert!(peak_kb < 256 * 1024, "peak memory: {peak_kb} kB");
+    }
+}
+
 #[cfg(feature = "blocking")]
 #[test]
 fn blocking_file_part() {
@@ -175,3 +281,166 @@ fn blocking_file_part() {
     assert_eq!(res.url().as_str(), &url);
     assert_eq!(res.status(), reqwest::StatusCode::OK);
 }
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn large_reader_part_is_streamed() {
+    use tokio::io::AsyncReadExt;
+
+    let _ = env_logger::try_init();
+
+    const LEN: u64 = 500 * 1024 * 1024;
+
+    let server = server::http(move |mut req| async move {
+        assert_eq!(req.method(), "POST");
+        let content_length: u64 = req.headers()["content-length"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        // only count the bytes, to keep the server from holding them
+        let mut received = 0;
+        while let Some(item) = req.body_mut().frame().await {
+            received += item.unwrap().into_data().unwrap().len() as u64;
+        }
+        assert_eq!(received, content_length);
+        assert!(received > LEN);
+        http::Response::default()
+    });
+
+    let part = reqwest::multipart::Part::reader(tokio::io::repeat(0).take(LEN), Some(LEN))
+        .file_name("big.bin")
+        .mime_str("application/octet-stream")
+        .unwrap();
+    let form = reqwest::multipart::Form::new().part("file", part);
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/upload", server.addr()))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    // the 500 MB part was never held in memory at once
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        let peak_kb: u64 = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap();
+        assert!(peak_kb < 256 * 1024, "peak memory: {peak_kb} kB");
+    }
+}
+
+#[tokio::test]
+async fn fixed_boundary() {
+    let _ = env_logger::try_init();
+
+    let form = reqwest::multipart::Form::new()
+        .set_boundary("golden boundary")
+        .unwrap()
+        .text("foo", "bar");
+
+    let server = server::http(move |req| async move {
+        assert_eq!(
+            req.headers()["content-type"],
+            "multipart/form-data; boundary=\"golden boundary\""
+        );
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(
+            body,
+            "--golden boundary\r\n\
+             Content-Disposition: form-data; name=\"foo\"\r\n\r\n\
+             bar\r\n\
+             --golden boundary--\r\n"
+        );
+        http::Response::default()
+    });
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/multipart/1", server.addr()))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn multipart_raw_sends_an_encoded_body() {
+    let _ = env_logger::try_init();
+
+    // encoded as by something else than reqwest
+    let boundary = "raw boundary:1";
+    let parts = [("name", "ferris"), ("file", "fn main() {}")];
+    let mut encoded = String::new();
+    for (name, value) in parts {
+        encoded.push_str(&format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+        ));
+    }
+    encoded.push_str(&format!("--{boundary}--\r\n"));
+    // chunks split in the middle of boundaries
+    let chunks: Vec<Result<String, std::io::Error>> = encoded
+        .as_bytes()
+        .chunks(7)
+        .map(|chunk| Ok(String::from_utf8(chunk.to_vec()).unwrap()))
+        .collect();
+
+    let server = server::http(move |req| async move {
+        assert_eq!(
+            req.headers()["content-type"],
+            "multipart/form-data; boundary=\"raw boundary:1\""
+        );
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        let body = std::str::from_utf8(&body).unwrap();
+
+        let delimiter = format!("--{boundary}");
+        let (parts, end) = body.rsplit_once(&delimiter).unwrap();
+        assert_eq!(end, "--\r\n");
+        let parts: Vec<&str> = parts.split(&delimiter).skip(1).collect();
+        assert_eq!(parts.len(), 2);
+        assert!(
+            parts[0].ends_with("name=\"name\"\r\n\r\nferris\r\n"),
+            "{}",
+            parts[0]
+        );
+        assert!(
+            parts[1].ends_with("name=\"file\"\r\n\r\nfn main() {}\r\n"),
+            "{}",
+            parts[1]
+        );
+        http::Response::default()
+    });
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/multipart/raw", server.addr()))
+        .multipart_raw(futures_util::stream::iter(chunks), boundary)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let client = reqwest::Client::new();
+    let empty = || futures_util::stream::iter(Vec::<Result<String, std::io::Error>>::new());
+    for boundary in ["", "ends with space ", "no\"quotes"] {
+        let err = client
+            .post("http://localhost/")
+            .multipart_raw(empty(), boundary)
+            .build()
+            .unwrap_err();
+        assert!(err.is_builder(), "{boundary:?}");
+    }
+    let err = client
+        .post("http://localhost/")
+        .header("content-length", 5)
+        .multipart_raw(empty(), "b0undary")
+        .build()
+        .unwrap_err();
+    assert!(err.is_builder());
+}
@@ -213,6 +213,116 @@ async fn test_redirect_removes_sensitive_headers() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn test_redirect_drops_host_header_to_other_host() {
+    let end_server = server::http(move |req| async move {
+        assert_eq!(req.uri(), "/end");
+        assert_ne!(req.headers()["host"], "internal.example");
+        http::Response::default()
+    });
+
+    let end_addr = end_server.addr();
+
+    let mid_server = server::http(move |req| async move {
+        assert_eq!(req.headers()["host"], "internal.example");
+        http::Response::builder()
+            .status(302)
+            .header("location", format!("http://{end_addr}/end"))
+            .body(Body::default())
+            .unwrap()
+    });
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/start", mid_server.addr()))
+        .host_header("internal.example")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.url().path(), "/end");
+}
+
+#[tokio::test]
+async fn test_redirect_policy_can_rewrite_url() {
+    let end_server = server::http(move |req| async move {
+        // the rewritten target is on another host
+        assert_eq!(req.uri(), "/end");
+        assert_eq!(req.headers().get("cookie"), None);
+        assert_eq!(req.headers().get("authorization"), None);
+        http::Response::default()
+    });
+    let end_addr = end_server.addr();
+
+    let mid_server = server::http(move |req| async move {
+        if req.uri().path() == "/kept" {
+            // a rewrite to the same host keeps them
+            assert_eq!(req.headers()["authorization"], "secret");
+            return http::Response::default();
+        }
+        assert_eq!(req.headers()["cookie"], "foo=bar");
+        http::Response::builder()
+            .status(302)
+            .header("location", "/same?tracking=1")
+            .body(Body::default())
+            .unwrap()
+    });
+    let mid_addr = mid_server.addr();
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            let mut url = attempt.url().clone();
+            url.set_query(None);
+            if attempt.previous()[0].path() == "/sensitive" {
+                url.set_port(Some(end_addr.port())).unwrap();
+                url.set_path("/end");
+            } else {
+                url.set_path("/kept");
+            }
+            attempt.follow_to(url)
+        }))
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{mid_addr}/sensitive"))
+        .header("cookie", "foo=bar")
+        .header("authorization", "secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.url().as_str(), format!("http://{end_addr}/end"));
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let res = client
+        .get(format!("http://{mid_addr}/other"))
+        .header("cookie", "foo=bar")
+        .header("authorization", "secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.url().as_str(), format!("http://{mid_addr}/kept"));
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_redirect_remote_addr_is_final_hop() {
+    let end_server = server::http(move |_req| async { http::Response::default() });
+    let end_addr = end_server.addr();
+
+    let mid_server = server::http(move |_req| async move {
+        http::Response::builder()
+            .status(302)
+            .header("location", format!("http://{end_addr}/end"))
+            .body(Body::default())
+            .unwrap()
+    });
+
+    let res = reqwest::get(format!("http://{}/mid", mid_server.addr()))
+        .await
+        .unwrap();
+    assert_eq!(res.remote_addr(), Some(end_addr));
+}
+
 #[tokio::test]
 async fn test_redirect_policy_can_return_errors() {
     let server = server::http(move |req| async move {
@@ -255,6 +365,87 @@ async fn test_redirect_policy_can_stop_redirects_without_an_error() {
     assert_eq!(res.status(), reqwest::StatusCode::FOUND);
 }
 
+#[tokio::test]
+async fn test_redirect_policy_for_head_only() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/end" {
+            return http::Response::default();
+        }
+        http::Response::builder()
+            .status(302)
+            .header("location", "/end")
+            .body(Body::default())
+            .unwrap()
+    });
+
+    let url = format!("http://{}/start", server.addr());
+    let client = reqwest::Client::builder()
+        .redirect(
+            reqwest::redirect::Policy::default()
+                .for_method(reqwest::Method::HEAD, reqwest::redirect::Policy::none()),
+        )
+        .build()
+        .unwrap();
+
+    let res = client.head(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FOUND);
+    assert_eq!(res.url().as_str(), url);
+
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.url().path(), "/end");
+}
+
+#[tokio::test]
+async fn test_redirect_history() {
+    let server = server::http(move |req| async move {
+        let (status, location) = match req.uri().path() {
+            "/start" => (301, "/middle"),
+            "/middle" => (302, "/end"),
+            _ => return http::Response::default(),
+        };
+        http::Response::builder()
+            .status(status)
+            .header("location", location)
+            .body(Body::default())
+            .unwrap()
+    });
+
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("http://{}/start", server.addr()))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.url().path(), "/end");
+    let history = res
+        .redirect_history()
+        .iter()
+        .map(|hop| (hop.url().path(), hop.status().as_u16()))
+        .collect::<Vec<_>>();
+    assert_eq!(history, [("/start", 301), ("/middle", 302)]);
+
+    let res = client
+        .get(format!("http://{}/end", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.redirect_history().is_empty());
+
+    // a redirect that isn't followed isn't part of the history
+    let res = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+        .get(format!("http://{}/start", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::MOVED_PERMANENTLY);
+    assert!(res.redirect_history().is_empty());
+}
+
 #[tokio::test]
 async fn test_referer_is_not_set_if_disabled() {
     let server = server::http(move |req| async move {
@@ -374,4 +565,41 @@ async fn test_redirect_https_only_enforced_gh1312() {
 
     let err = res.unwrap_err();
     assert!(err.is_redirect());
+    assert!(err.is_not_https());
+}
+
+#[tokio::test]
+async fn test_execute_with_history() {
+    let server = server::http(move |req| async move {
+        let (status, location) = match req.uri().path() {
+            "/start" => (301, "/middle"),
+            "/middle" => (307, "end"),
+            _ => return http::Response::default(),
+        };
+        http::Response::builder()
+            .status(status)
+            .header("location", location)
+            .body(Body::default())
+            .unwrap()
+    });
+
+    let client = reqwest::Client::new();
+    let req = client
+        .get(format!("http://{}/start", server.addr()))
+        .build()
+        .unwrap();
+    let (res, redirects) = client.execute_with_history(req).await.unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let expected =
+        ["/middle", "/end"].map(|path| format!("http://{}{path}", server.addr()).parse().unwrap());
+    assert_eq!(redirects, expected);
+    assert_eq!(redirects.last(), Some(res.url()));
+
+    let req = client
+        .get(format!("http://{}/end", server.addr()))
+        .build()
+        .unwrap();
+    let (_, redirects) = client.execute_with_history(req).await.unwrap();
+    assert!(redirects.is_empty());
 }
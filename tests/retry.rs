@@ -0,0 +1,299 @@
+#![cfg(not(target_arch = "wasm32"))]
+mod support;
+use support::server;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{retry, StatusCode};
+
+fn flaky_server(failures: usize) -> (server::Server, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    let server = server::http(move |_req| {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            let status = if n < failures {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::OK
+            };
+            http::Response::builder()
+                .status(status)
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+    (server, hits)
+}
+
+#[tokio::test]
+async fn retry_status_until_success() {
+    let _ = env_logger::try_init();
+
+    let (server, hits) = flaky_server(2);
+    let url = format!("http://{}/flaky", server.addr());
+
+    let res = reqwest::Client::new()
+        .post(&url)
+        .body("replayable")
+        .retry(retry::Policy::constant(3, Duration::from_millis(10)))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn retry_exhausted_returns_last_response() {
+    let _ = env_logger::try_init();
+
+    let (server, hits) = flaky_server(usize::MAX);
+    let url = format!("http://{}/down", server.addr());
+
+    let res = reqwest::Client::new()
+        .get(&url)
+        .retry(retry::Policy::exponential(2))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn retry_ignores_other_statuses() {
+    let _ = env_logger::try_init();
+
+    let (server, hits) = flaky_server(1);
+    let url = format!("http://{}/flaky", server.addr());
+
+    let res = reqwest::Client::new()
+        .get(&url)
+        .retry(
+            retry::Policy::constant(3, Duration::from_millis(10))
+                .statuses([StatusCode::TOO_MANY_REQUESTS]),
+        )
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn retry_skips_streaming_body() {
+    let _ = env_logger::try_init();
+
+    let (server, hits) = flaky_server(1);
+    let url = format!("http://{}/stream", server.addr());
+
+    let body = reqwest::Body::wrap_stream(futures_util::stream::once(async {
+        Ok::<_, std::convert::Infallible>("streamed")
+    }));
+    let res = reqwest::Client::new()
+        .post(&url)
+        .body(body)
+        .retry(retry::Policy::constant(3, Duration::from_millis(10)))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn retry_connect_error() {
+    let _ = env_logger::try_init();
+
+    // grab a free port, then close it so connecting is refused
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let url = format!("http://{addr}/refused");
+
+    let start = std::time::Instant::now();
+    let err = reqwest::Client::new()
+        .get(&url)
+        .retry(retry::Policy::constant(2, Duration::from_millis(100)))
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_connect());
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn retry_calls_bearer_auth_fn_again() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        let status = if req.headers()["authorization"] == "Bearer fresh" {
+            StatusCode::OK
+        } else {
+            StatusCode::UNAUTHORIZED
+        };
+        http::Response::builder()
+            .status(status)
+            .body(Default::default())
+            .unwrap()
+    });
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/token", server.addr()))
+        .bearer_auth("overridden")
+        .bearer_auth_fn(move || match counter.fetch_add(1, Ordering::SeqCst) {
+            0 => "stale".to_owned(),
+            _ => "fresh".to_owned(),
+        })
+        .retry(
+            retry::Policy::constant(2, Duration::from_millis(10))
+                .statuses([StatusCode::UNAUTHORIZED]),
+        )
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn invalid_bearer_auth_fn_token_is_builder_error() {
+    let _ = env_logger::try_init();
+
+    let err = reqwest::Client::new()
+        .get("http://127.0.0.1:1/token")
+        .bearer_auth_fn(|| "bad\ntoken".to_owned())
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_builder());
+}
+
+/// Answers after `delay` for the first `slow` requests, and right away after.
+fn slow_server(slow: usize, delay: Duration) -> (server::Server, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    let server = server::http(move |mut req| {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            let body = http_body_util::BodyExt::collect(req.body_mut())
+                .await
+                .unwrap()
+                .to_bytes();
+            if n < slow {
+                tokio::time::sleep(delay).await;
+            }
+            http::Response::new(body.into())
+        }
+    });
+    (server, hits)
+}
+
+fn no_jitter(max_attempts: u32) -> retry::ExponentialBackoff {
+    retry::ExponentialBackoff {
+        max_attempts,
+        base_delay: Duration::from_millis(10),
+        jitter: false,
+        ..retry::ExponentialBackoff::default()
+    }
+}
+
+#[tokio::test]
+async fn with_retry_resets_timeout_per_attempt() {
+    let _ = env_logger::try_init();
+
+    let (server, hits) = slow_server(2, Duration::from_millis(500));
+    let client = reqwest::Client::builder()
+        .with_retry(no_jitter(3))
+        .build()
+        .unwrap();
+
+    let res = client
+        .post(format!("http://{}/slow", server.addr()))
+        .body("replayable")
+        .timeout(Duration::from_millis(200))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.text().await.unwrap(), "replayable");
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn with_retry_gives_up_after_max_attempts() {
+    let _ = env_logger::try_init();
+
+    let (server, hits) = slow_server(usize::MAX, Duration::from_millis(500));
+    let client = reqwest::Client::builder()
+        .with_retry(no_jitter(2))
+        .build()
+        .unwrap();
+
+    let err = client
+        .get(format!("http://{}/slow", server.addr()))
+        .timeout(Duration::from_millis(100))
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_timeout());
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn with_retry_buffers_streaming_body() {
+    let _ = env_logger::try_init();
+
+    let (server, hits) = slow_server(1, Duration::from_millis(500));
+    let client = reqwest::Client::builder()
+        .with_retry(no_jitter(3))
+        .build()
+        .unwrap();
+    let url = format!("http://{}/stream", server.addr());
+    let stream = || {
+        reqwest::Body::wrap_stream(futures_util::stream::iter(vec![
+            Ok::<_, std::io::Error>("hello"),
+            Ok(" world"),
+        ]))
+    };
+
+    let res = client
+        .post(&url)
+        .body(stream())
+        .timeout(Duration::from_millis(200))
+        .retry_body_buffer_limit(1024)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "hello world");
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    // past the limit, the body is streamed and sent once
+    let (server, hits) = slow_server(1, Duration::from_millis(500));
+    let err = client
+        .post(format!("http://{}/stream", server.addr()))
+        .body(stream())
+        .timeout(Duration::from_millis(200))
+        .retry_body_buffer_limit(4)
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
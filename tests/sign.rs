@@ -0,0 +1,199 @@
+#![cfg(not(target_arch = "wasm32"))]
+mod support;
+use support::server;
+
+use std::sync::Arc;
+
+use http_body_util::BodyExt;
+use reqwest::header::HeaderValue;
+use reqwest::sign::{RequestParts, RequestSigner};
+use reqwest::Body;
+
+/// Signs the method, path, a header set by middleware, and the body.
+struct TestSigner;
+
+fn signature(method: &str, path: &str, layer: &str, body: &[u8]) -> String {
+    let sum = body
+        .iter()
+        .fold(0u32, |sum, b| sum.wrapping_mul(31) ^ u32::from(*b));
+    format!("{} {} {} {}:{}", method, path, layer, body.len(), sum)
+}
+
+impl RequestSigner for TestSigner {
+    fn sign(&self, parts: &mut RequestParts<'_>) {
+        let layer = parts
+            .headers()
+            .get("x-layer")
+            .map(|v| v.to_str().unwrap().to_owned())
+            .unwrap_or_default();
+        let value = signature(
+            parts.method().as_str(),
+            parts.url().path(),
+            &layer,
+            parts.body(),
+        );
+        parts
+            .headers_mut()
+            .insert("x-signature", HeaderValue::from_str(&value).unwrap());
+    }
+}
+
+struct AddLayer;
+
+impl reqwest::middleware::Middleware for AddLayer {
+    fn handle<'a>(
+        &'a self,
+        mut req: reqwest::Request,
+        next: reqwest::middleware::Next<'a>,
+    ) -> reqwest::middleware::Handling<'a> {
+        req.headers_mut()
+            .insert("x-layer", HeaderValue::from_static("mw"));
+        next.run(req)
+    }
+}
+
+#[tokio::test]
+async fn signs_after_middleware() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        assert_eq!(
+            req.headers()["x-signature"],
+            signature("POST", "/sign", "mw", b"hello")
+        );
+        http::Response::default()
+    });
+
+    let client = reqwest::Client::builder()
+        .with_middleware(AddLayer)
+        .sign_with(Arc::new(TestSigner))
+        .build()
+        .unwrap();
+
+    let res = client
+        .post(format!("http://{}/sign", server.addr()))
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn resigns_on_redirect() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |mut req| async move {
+        let body = req.body_mut().collect().await.unwrap().to_bytes();
+        let expected = signature(req.method().as_str(), req.uri().path(), "", &body);
+        assert_eq!(req.headers()["x-signature"], expected);
+
+        if req.uri() == "/start" {
+            http::Response::builder()
+                .status(307)
+                .header("location", "/dst")
+                .body(Body::default())
+                .unwrap()
+        } else {
+            assert_eq!(req.uri(), "/dst");
+            assert_eq!(&body[..], b"hello");
+            http::Response::default()
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .sign_with(Arc::new(TestSigner))
+        .build()
+        .unwrap();
+
+    let res = client
+        .post(format!("http://{}/start", server.addr()))
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.url().path(), "/dst");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn streaming_body_is_rejected() {
+    let _ = env_logger::try_init();
+
+    let client = reqwest::Client::builder()
+        .sign_with(Arc::new(TestSigner))
+        .build()
+        .unwrap();
+
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>("hello")]);
+    let err = client
+        .post("http://localhost/never")
+        .body(Body::wrap_stream(stream))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_builder());
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn streaming_body_is_buffered() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |mut req| async move {
+        let body = req.body_mut().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"hello world");
+        assert_eq!(
+            req.headers()["x-signature"],
+            signature("PUT", "/upload", "", b"hello world")
+        );
+        http::Response::default()
+    });
+
+    let client = reqwest::Client::builder()
+        .sign_with(Arc::new(TestSigner))
+        .buffer_signed_bodies(true)
+        .build()
+        .unwrap();
+
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>("hello"), Ok(" world")]);
+    let res = client
+        .put(format!("http://{}/upload", server.addr()))
+        .body(Body::wrap_stream(stream))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn compressed_body_is_signed_as_sent() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |mut req| async move {
+        assert_eq!(req.headers()["content-encoding"], "gzip");
+        let body = req.body_mut().collect().await.unwrap().to_bytes();
+        assert_eq!(
+            req.headers()["x-signature"],
+            signature("POST", "/gzip", "", &body)
+        );
+        http::Response::default()
+    });
+
+    let client = reqwest::Client::builder()
+        .compress_body(reqwest::ContentEncoding::Gzip)
+        .sign_with(Arc::new(TestSigner))
+        .buffer_signed_bodies(true)
+        .build()
+        .unwrap();
+
+    let res = client
+        .post(format!("http://{}/gzip", server.addr()))
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
@@ -63,6 +63,51 @@ async fn request_timeout() {
     assert_eq!(err.url().map(|u| u.as_str()), Some(url.as_str()));
 }
 
+#[tokio::test]
+async fn request_timeout_fn() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |_req| async {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        http::Response::default()
+    });
+
+    let client = reqwest::Client::builder().build().unwrap();
+    let url = format!("http://{}/slow", server.addr());
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let err = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .timeout_fn(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Some(Duration::from_millis(50))
+        })
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // whichever is set last applies
+    let res = client
+        .get(&url)
+        .timeout_fn(|| Some(Duration::from_millis(50)))
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    // `None` leaves no timeout
+    let res = client.get(&url).timeout_fn(|| None).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::test]
 async fn connect_timeout() {
@@ -86,6 +131,65 @@ async fn connect_timeout() {
     assert!(err.is_connect() && err.is_timeout());
 }
 
+// Resolving is part of the connect phase, so a resolver that never
+// finishes keeps the request connecting forever.
+#[cfg(not(target_arch = "wasm32"))]
+struct Hang;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl reqwest::dns::Resolve for Hang {
+    fn resolve(&self, _: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(futures_util::future::pending())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn request_connect_timeout() {
+    let _ = env_logger::try_init();
+
+    let client = reqwest::Client::builder()
+        .dns_resolver(std::sync::Arc::new(Hang))
+        .connect_timeout(Duration::from_secs(10))
+        .build()
+        .unwrap();
+
+    let res = client
+        .get("http://hang.local/slow")
+        .connect_timeout(Duration::from_millis(100))
+        .timeout(Duration::from_millis(1000))
+        .send()
+        .await;
+
+    let err = res.unwrap_err();
+
+    assert!(err.is_connect() && err.is_timeout());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn total_timeout_while_connecting() {
+    let _ = env_logger::try_init();
+
+    let client = reqwest::Client::builder()
+        .dns_resolver(std::sync::Arc::new(Hang))
+        .build()
+        .unwrap();
+
+    let res = client
+        .get("http://hang.local/slow")
+        .connect_timeout(Duration::from_secs(10))
+        .timeout(Duration::from_millis(100))
+        .send()
+        .await;
+
+    let err = res.unwrap_err();
+
+    // the total timeout fired, not the connect one
+    assert!(err.is_timeout());
+    assert!(!err.is_connect());
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::test]
 async fn connect_many_timeout_succeeds() {
@@ -232,6 +336,28 @@ fn timeout_blocking_request() {
     assert_eq!(err.url().map(|u| u.as_str()), Some(url.as_str()));
 }
 
+#[cfg(feature = "blocking")]
+#[test]
+fn timeout_fn_blocking_request() {
+    let _ = env_logger::try_init();
+
+    let client = reqwest::blocking::Client::builder().build().unwrap();
+
+    let server = server::http(move |_req| async {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        http::Response::default()
+    });
+
+    let url = format!("http://{}/slow", server.addr());
+    let err = client
+        .get(&url)
+        .timeout_fn(|| Some(Duration::from_millis(500)))
+        .send()
+        .unwrap_err();
+
+    assert!(err.is_timeout());
+}
+
 #[cfg(feature = "blocking")]
 #[cfg(feature = "stream")]
 #[test]
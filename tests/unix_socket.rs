@@ -0,0 +1,127 @@
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+
+use http_body_util::BodyExt;
+use hyper_util::rt::TokioIo;
+
+/// Serves HTTP/1 on a new Unix socket, answering with the request's path
+/// and `Host` header.
+fn server(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("reqwest-{}-{name}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (io, _) = listener.accept().await.unwrap();
+            let svc = hyper::service::service_fn(
+                |req: http::Request<hyper::body::Incoming>| async move {
+                    let host = req.headers()["host"].to_str().unwrap().to_owned();
+                    let path = req.uri().path_and_query().unwrap().to_string();
+                    let body = req.into_body().collect().await?.to_bytes();
+                    let text = format!("{host} {path} {}", String::from_utf8_lossy(&body));
+                    Ok::<_, hyper::Error>(http::Response::new(reqwest::Body::from(text)))
+                },
+            );
+            tokio::spawn(async move {
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(io), svc)
+                    .await;
+            });
+        }
+    });
+
+    path
+}
+
+fn client(path: &Path) -> reqwest::Client {
+    reqwest::Client::builder()
+        .unix_socket(path)
+        .no_proxy()
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn get_over_unix_socket() {
+    let path = server("get");
+
+    let text = client(&path)
+        .get("http://docker.local/v1/version?all=1")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert_eq!(text, "docker.local /v1/version?all=1 ");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn post_over_unix_socket_reuses_connection() {
+    let path = server("post");
+    let client = client(&path);
+
+    for n in 0..2 {
+        let res = client
+            .post("http://localhost:8080/echo")
+            .body("hello")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.connection_reused(), n > 0);
+        assert_eq!(res.text().await.unwrap(), "localhost:8080 /echo hello");
+    }
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn https_over_unix_socket_fails() {
+    let path = server("https");
+
+    let err = client(&path)
+        .get("https://localhost/")
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_connect());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn missing_unix_socket_is_connect_error() {
+    let path = std::env::temp_dir().join("reqwest-missing.sock");
+
+    let err = client(&path)
+        .get("http://localhost/")
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_connect());
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn get_over_unix_socket_blocking() {
+    // the server needs a runtime, which the blocking client mustn't run in
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let path = rt.block_on(async { server("blocking") });
+
+    let text = reqwest::blocking::Client::builder()
+        .unix_socket(&path)
+        .build()
+        .unwrap()
+        .get("http://localhost/containers/json")
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+
+    assert_eq!(text, "localhost /containers/json ");
+    let _ = std::fs::remove_file(&path);
+}
@@ -49,3 +49,26 @@ async fn http_upgrade() {
     upgraded.read_to_end(&mut buf).await.unwrap();
     assert_eq!(buf, b"bar=foo");
 }
+
+#[tokio::test]
+async fn upgrade_requires_switching_protocols() {
+    let server = server::http(move |_req| async {
+        http::Response::builder()
+            .header(http::header::CONNECTION, "upgrade")
+            .header(http::header::UPGRADE, "foobar")
+            .body(reqwest::Body::from("not upgraded"))
+            .unwrap()
+    });
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{}", server.addr()))
+        .header(http::header::CONNECTION, "upgrade")
+        .header(http::header::UPGRADE, "foobar")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), http::StatusCode::OK);
+    let err = res.upgrade().await.unwrap_err();
+    assert!(err.is_upgrade());
+}
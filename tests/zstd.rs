@@ -0,0 +1,142 @@
+mod support;
+use support::server;
+
+use http_body_util::BodyExt;
+
+#[tokio::test]
+async fn test_compress_body_zstd() {
+    let content: String = (0..10_000).map(|i| format!("test {i}")).collect();
+    let expected = content.clone();
+
+    let server = server::http(move |req| {
+        let expected = expected.clone();
+        async move {
+            assert_eq!(req.headers()["content-encoding"], "zstd");
+            let len = req.headers()["content-length"].clone();
+
+            let compressed = req.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(len, compressed.len().to_string());
+            let body = zstd_crate::decode_all(&compressed[..]).unwrap();
+            assert_eq!(body, expected.as_bytes());
+            http::Response::default()
+        }
+    });
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/compress", server.addr()))
+        .body(content)
+        .compress_body(reqwest::ContentEncoding::Zstd)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_compress_body_zstd() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["content-encoding"], "zstd");
+        let compressed = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(zstd_crate::decode_all(&compressed[..]).unwrap(), b"hello");
+        http::Response::default()
+    });
+
+    let res = reqwest::blocking::Client::builder()
+        .compress_body(reqwest::ContentEncoding::Zstd)
+        .build()
+        .unwrap()
+        .post(format!("http://{}/compress", server.addr()))
+        .body("hello")
+        .send()
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn zstd_response() {
+    let content: String = (0..10_000).map(|i| format!("test {i}")).collect();
+    let compressed = zstd_crate::encode_all(content.as_bytes(), 3).unwrap();
+
+    let server = server::http(move |req| {
+        assert!(req.headers()["accept-encoding"]
+            .to_str()
+            .unwrap()
+            .contains("zstd"));
+
+        let compressed = compressed.clone();
+        async move {
+            http::Response::builder()
+                .header("content-encoding", "zstd")
+                .header("content-length", compressed.len())
+                .body(compressed.into())
+                .unwrap()
+        }
+    });
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/zstd", server.addr()))
+        .send()
+        .await
+        .expect("response");
+
+    assert!(res.headers().get("content-encoding").is_none());
+    assert_eq!(res.text().await.expect("text"), content);
+}
+
+#[tokio::test]
+async fn test_zstd_empty_body() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.method(), "HEAD");
+
+        http::Response::builder()
+            .header("content-encoding", "zstd")
+            .body(Default::default())
+            .unwrap()
+    });
+
+    let res = reqwest::Client::new()
+        .head(format!("http://{}/zstd", server.addr()))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.text().await.unwrap(), "");
+}
+
+#[tokio::test]
+async fn test_no_zstd_keeps_body_compressed() {
+    let compressed = zstd_crate::encode_all(&b"hello"[..], 3).unwrap();
+    let expected = compressed.clone();
+
+    let server = server::http(move |req| {
+        let accept_encoding = req
+            .headers()
+            .get("accept-encoding")
+            .map(|v| v.to_str().unwrap().to_owned())
+            .unwrap_or_default();
+        assert!(!accept_encoding.contains("zstd"));
+
+        let compressed = compressed.clone();
+        async move {
+            http::Response::builder()
+                .header("content-encoding", "zstd")
+                .body(compressed.into())
+                .unwrap()
+        }
+    });
+
+    let res = reqwest::Client::builder()
+        .no_zstd()
+        .build()
+        .unwrap()
+        .get(format!("http://{}/zstd", server.addr()))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.headers()["content-encoding"], "zstd");
+    assert_eq!(&res.bytes().await.unwrap()[..], &expected[..]);
+}